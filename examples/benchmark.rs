@@ -0,0 +1,119 @@
+//! Measure the cost of common operations.
+//!
+//! Run with:
+//!
+//! ```sh
+//! cargo run --release --example benchmark
+//! cargo run --release --example benchmark --features prefetch
+//! cargo run --release --example benchmark --features tagged
+//! ```
+//!
+//! Iteration is measured on a list whose order was scrambled by random
+//! removals and insertions, so that following links jumps all over memory.
+//!
+//! Lookups compare the validation strategies: `get()` checks the range of
+//! the slot and an occupancy bitmap by default, or only the backward link
+//! of the slot with the `tagged` feature, which covers both, and
+//! `get_unchecked()` doesn't validate anything.
+
+use std::{hint::black_box, time::Instant};
+
+use rand::prelude::*;
+use slabigator::Slab;
+
+const CAPACITY: usize = 1 << 20;
+const ROUNDS: usize = 20;
+
+#[derive(Clone, Copy)]
+struct Entry {
+    value: u64,
+    _padding: [u64; 7],
+}
+
+fn scrambled_slab(rng: &mut impl Rng) -> Slab<Entry> {
+    let mut slab = Slab::with_capacity(CAPACITY).unwrap();
+    let mut slots = Vec::with_capacity(CAPACITY);
+    for i in 0..CAPACITY {
+        let entry = Entry {
+            value: i as u64,
+            _padding: [0; 7],
+        };
+        slots.push(slab.push_front(entry).unwrap());
+    }
+    for _ in 0..CAPACITY {
+        let idx = rng.gen_range(0..slots.len());
+        let entry = slab[slots[idx]];
+        slab.remove(slots[idx]).unwrap();
+        slots[idx] = slab.push_front(entry).unwrap();
+    }
+    slab
+}
+
+fn main() {
+    let mut rng = rand::thread_rng();
+
+    let start = Instant::now();
+    let mut slab = scrambled_slab(&mut rng);
+    println!(
+        "push_front + remove: {:.1} ns/op",
+        start.elapsed().as_nanos() as f64 / (3 * CAPACITY) as f64
+    );
+
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        black_box(slab.iter().map(|entry| entry.value).sum::<u64>());
+    }
+    println!(
+        "iter: {:.1} ns/element (prefetch: {})",
+        start.elapsed().as_nanos() as f64 / (ROUNDS * CAPACITY) as f64,
+        cfg!(feature = "prefetch")
+    );
+
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        black_box(slab.iter().rev().map(|entry| entry.value).sum::<u64>());
+    }
+    println!(
+        "iter().rev(): {:.1} ns/element",
+        start.elapsed().as_nanos() as f64 / (ROUNDS * CAPACITY) as f64
+    );
+
+    let slots: Vec<_> = (0..CAPACITY)
+        .map(|_| rng.gen_range(0..CAPACITY) as _)
+        .collect();
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        for &slot in &slots {
+            black_box(slab.get(black_box(slot)).unwrap().value);
+        }
+    }
+    println!(
+        "get: {:.1} ns/op (validation: {})",
+        start.elapsed().as_nanos() as f64 / (ROUNDS * CAPACITY) as f64,
+        if cfg!(feature = "tagged") {
+            "tagged"
+        } else if cfg!(feature = "releasefast") {
+            "none"
+        } else {
+            "bitmap"
+        }
+    );
+
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        for &slot in &slots {
+            black_box(unsafe { slab.get_unchecked(black_box(slot)) }.value);
+        }
+    }
+    println!(
+        "get_unchecked: {:.1} ns/op",
+        start.elapsed().as_nanos() as f64 / (ROUNDS * CAPACITY) as f64
+    );
+
+    let start = Instant::now();
+    while slab.pop_back().is_some() {}
+    println!(
+        "pop_back: {:.1} ns/op",
+        start.elapsed().as_nanos() as f64 / CAPACITY as f64
+    );
+}
@@ -0,0 +1,145 @@
+//! Measure how much a writer on another core slows down lookups.
+//!
+//! Run with:
+//!
+//! ```sh
+//! cargo run --release --example contention
+//! cargo run --release --example contention --features generational
+//! ```
+//!
+//! A reader thread calls `get()` on a small list, while a writer thread
+//! keeps pushing and popping elements on another list: first a large one,
+//! allocated far away, then a small one, allocated right after the list of
+//! the reader. Small allocations made in a row are usually adjacent, so the
+//! links updated by the writer could share cache lines with the occupancy
+//! bitmap checked by the reader, and every write would then evict that
+//! line from the cache of the reader. The bitmap and the generations are
+//! kept in cache lines of their own, so both measurements should be close;
+//! the difference with the first measurement, made without a writer, is
+//! only due to sharing the CPU.
+//!
+//! For comparison, the last two measurements repeat the same access pattern
+//! on plain arrays standing for the links and the bitmap: first padded to
+//! separate cache lines, then packed in the same cache line, which is what
+//! the padding avoids. The padding costs up to 127 bytes per array, see the
+//! `padded` module.
+
+use std::{
+    hint::black_box,
+    sync::atomic::{AtomicBool, AtomicU32, AtomicU8, Ordering},
+    thread,
+    time::Instant,
+};
+
+use slabigator::Slab;
+
+const CAPACITY: usize = 16;
+const LOOKUPS: usize = 100_000_000;
+
+// Keep the lists themselves in different cache lines, since their heads
+// are written by every insertion and removal as well.
+#[repr(align(128))]
+struct Aligned<T>(T);
+
+fn lookups<S: slabigator::SlotType>(slab: &Slab<u64, S>, slots: &[S]) -> f64 {
+    let start = Instant::now();
+    for i in 0..LOOKUPS {
+        black_box(slab.get(black_box(slots[i % slots.len()])).ok());
+    }
+    start.elapsed().as_nanos() as f64 / LOOKUPS as f64
+}
+
+// Measure lookups on `reader` while another thread writes to `writer`.
+fn contended_lookups<S: slabigator::SlotType>(
+    reader: &Slab<u64, S>,
+    slots: &[S],
+    writer: &mut Slab<u64, S>,
+) -> f64 {
+    let stop = AtomicBool::new(false);
+    thread::scope(|s| {
+        s.spawn(|| {
+            while !stop.load(Ordering::Relaxed) {
+                for i in 0..CAPACITY as u64 {
+                    writer.push_front(i).unwrap();
+                }
+                while writer.pop_back().is_some() {}
+            }
+        });
+        let ns = lookups(reader, slots);
+        stop.store(true, Ordering::Relaxed);
+        ns
+    })
+}
+
+// Links and a bitmap, in the same cache line.
+#[repr(C, align(128))]
+#[derive(Default)]
+struct Packed {
+    links: [AtomicU32; CAPACITY],
+    bitmap: [AtomicU8; CAPACITY / 8],
+}
+
+// Links and a bitmap, in different cache lines.
+#[derive(Default)]
+struct Padded {
+    links: Aligned<[AtomicU32; CAPACITY]>,
+    bitmap: Aligned<[AtomicU8; CAPACITY / 8]>,
+}
+
+impl<T: Default> Default for Aligned<T> {
+    fn default() -> Self {
+        Aligned(T::default())
+    }
+}
+
+// Check bits of `bitmap` while another thread keeps updating `links`.
+fn contended_bitmap_checks(links: &[AtomicU32], bitmap: &[AtomicU8]) -> f64 {
+    let stop = AtomicBool::new(false);
+    thread::scope(|s| {
+        s.spawn(|| {
+            let mut i = 0;
+            while !stop.load(Ordering::Relaxed) {
+                links[i % links.len()].store(i as u32, Ordering::Relaxed);
+                i += 1;
+            }
+        });
+        let start = Instant::now();
+        for i in 0..LOOKUPS {
+            let slot = black_box(i % CAPACITY);
+            black_box(bitmap[slot / 8].load(Ordering::Relaxed) & (1 << (slot % 8)));
+        }
+        let ns = start.elapsed().as_nanos() as f64 / LOOKUPS as f64;
+        stop.store(true, Ordering::Relaxed);
+        ns
+    })
+}
+
+fn main() {
+    let mut distant = Slab::with_capacity(1 << 20).unwrap();
+    let mut reader = Aligned(Slab::with_capacity(CAPACITY).unwrap());
+    let mut adjacent = Aligned(Slab::with_capacity(CAPACITY).unwrap());
+    let slots: Vec<_> = (0..CAPACITY as u64)
+        .map(|i| reader.0.push_front(i).unwrap())
+        .collect();
+
+    println!("get(): {:.2} ns/op", lookups(&reader.0, &slots));
+    println!(
+        "get() while writing to a distant list: {:.2} ns/op",
+        contended_lookups(&reader.0, &slots, &mut distant)
+    );
+    println!(
+        "get() while writing to an adjacent list: {:.2} ns/op",
+        contended_lookups(&reader.0, &slots, &mut adjacent.0)
+    );
+
+    let padded = Padded::default();
+    let packed = Packed::default();
+    println!(
+        "bitmap check while writing padded links: {:.2} ns/op",
+        contended_bitmap_checks(&padded.links.0, &padded.bitmap.0)
+    );
+    println!(
+        "bitmap check while writing links in the same cache line: {:.2} ns/op",
+        contended_bitmap_checks(&packed.links, &packed.bitmap)
+    );
+}
@@ -0,0 +1,175 @@
+//! Per-connection state of a `mio` TCP echo server, indexed by token.
+//!
+//! Run with:
+//!
+//! ```sh
+//! cargo run --example mio
+//! ```
+//!
+//! Slots are used directly as `mio::Token` values, so finding the state of a
+//! connection when an event arrives is a single lookup. Connections are
+//! moved to the front of the list on activity, so the least recently active
+//! one is always at the tail, and idle connections can be swept without
+//! scanning the whole table.
+
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    net::SocketAddr,
+    thread,
+    time::{Duration, Instant},
+};
+
+use mio::{
+    net::{TcpListener, TcpStream},
+    Events, Interest, Poll, Registry, Token,
+};
+use slabigator::Slab;
+
+const LISTENER: Token = Token(usize::MAX);
+const CAPACITY: usize = 64;
+const IDLE_TIMEOUT: Duration = Duration::from_millis(300);
+
+/// A table of per-connection state, keyed by `mio` tokens.
+struct TokenTable<T> {
+    slab: Slab<(Instant, T)>,
+}
+
+impl<T> TokenTable<T> {
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            slab: Slab::with_capacity(capacity).unwrap(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.slab.len()
+    }
+
+    /// Insert a new entry and return its token, or `None` if the table is
+    /// full.
+    fn insert(&mut self, value: T) -> Option<Token> {
+        let slot = self.slab.push_front((Instant::now(), value)).ok()?;
+        Some(Token(slot as _))
+    }
+
+    /// Return the entry of a token, and mark it as recently active.
+    fn get_mut(&mut self, token: Token) -> Option<&mut T> {
+        let slot = token.0 as _;
+        self.slab.move_to_front(slot).ok()?;
+        let (last_active, value) = self.slab.get_mut(slot).ok()?;
+        *last_active = Instant::now();
+        Some(value)
+    }
+
+    /// Remove the entry of a token.
+    fn remove(&mut self, token: Token) {
+        let _ = self.slab.remove(token.0 as _);
+    }
+
+    /// Remove the entries that haven't been active for `timeout`, and pass
+    /// them to `f`.
+    fn sweep(&mut self, timeout: Duration, mut f: impl FnMut(T)) {
+        let now = Instant::now();
+        while let Some((_, value)) = self
+            .slab
+            .pop_back_if(|(last_active, _)| now - *last_active >= timeout)
+        {
+            f(value);
+        }
+    }
+}
+
+/// Echo everything that can be read. Return `false` once the peer is gone.
+fn echo(stream: &mut TcpStream) -> bool {
+    let mut buf = [0u8; 1024];
+    loop {
+        match stream.read(&mut buf) {
+            Ok(0) => return false,
+            Ok(n) => {
+                if stream.write_all(&buf[..n]).is_err() {
+                    return false;
+                }
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => return true,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(_) => return false,
+        }
+    }
+}
+
+fn accept_all(
+    listener: &TcpListener,
+    registry: &Registry,
+    table: &mut TokenTable<TcpStream>,
+) -> io::Result<usize> {
+    let mut accepted = 0;
+    loop {
+        let stream = match listener.accept() {
+            Ok((stream, _)) => stream,
+            Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(accepted),
+            Err(e) => return Err(e),
+        };
+        accepted += 1;
+        let Some(token) = table.insert(stream) else {
+            println!("table is full, rejecting a connection");
+            continue;
+        };
+        let stream = table.get_mut(token).unwrap();
+        registry.register(stream, token, Interest::READABLE)?;
+        println!("accepted connection {}", token.0);
+    }
+}
+
+/// Two clients that exchange a message and disconnect, and one that stays
+/// idle until the server closes its connection.
+fn clients(addr: SocketAddr) {
+    let idle = std::net::TcpStream::connect(addr).unwrap();
+    for message in ["hello", "world"] {
+        let mut client = std::net::TcpStream::connect(addr).unwrap();
+        client.write_all(message.as_bytes()).unwrap();
+        let mut reply = [0u8; 5];
+        client.read_exact(&mut reply).unwrap();
+        assert_eq!(&reply, message.as_bytes());
+    }
+    let mut idle = idle;
+    assert_eq!(idle.read(&mut [0u8; 1]).unwrap(), 0);
+}
+
+fn main() -> io::Result<()> {
+    let mut poll = Poll::new()?;
+    let mut events = Events::with_capacity(CAPACITY);
+    let mut listener = TcpListener::bind("127.0.0.1:0".parse().unwrap())?;
+    let addr = listener.local_addr()?;
+    poll.registry()
+        .register(&mut listener, LISTENER, Interest::READABLE)?;
+
+    let client = thread::spawn(move || clients(addr));
+
+    let mut table = TokenTable::with_capacity(CAPACITY);
+    let mut accepted = 0;
+    while accepted < 3 || table.len() > 0 {
+        poll.poll(&mut events, Some(IDLE_TIMEOUT / 4))?;
+        for event in &events {
+            let token = event.token();
+            if token == LISTENER {
+                accepted += accept_all(&listener, poll.registry(), &mut table)?;
+                continue;
+            }
+            let Some(stream) = table.get_mut(token) else {
+                continue;
+            };
+            if !echo(stream) {
+                poll.registry().deregister(stream)?;
+                table.remove(token);
+                println!("connection {} closed", token.0);
+            }
+        }
+        table.sweep(IDLE_TIMEOUT, |mut stream| {
+            let _ = poll.registry().deregister(&mut stream);
+            println!("idle connection timed out");
+        });
+    }
+
+    client.join().unwrap();
+    Ok(())
+}
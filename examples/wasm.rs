@@ -0,0 +1,79 @@
+//! A fixed-capacity queue exported to JavaScript with `wasm-bindgen`.
+//!
+//! The example only uses `core` and `alloc`, and the crate is built without
+//! its default `std` feature:
+//!
+//! ```sh
+//! cargo build --release --target wasm32-unknown-unknown --no-default-features --example wasm
+//! wasm-bindgen --target web --out-dir pkg target/wasm32-unknown-unknown/release/examples/wasm.wasm
+//! ```
+//!
+//! Slots are plain integers, so they cross the JS boundary as numbers:
+//!
+//! ```js
+//! import init, { Queue } from "./pkg/wasm.js";
+//!
+//! await init();
+//! const q = new Queue(16);
+//! const slot = q.push(42);  // a number, or undefined if the queue is full
+//! q.get(slot);              // 42
+//! q.remove(slot);           // true
+//! q.pop();                  // undefined
+//! ```
+
+#![no_std]
+
+use slabigator::Slab;
+use wasm_bindgen::prelude::*;
+
+/// A queue of numbers, owned by JavaScript.
+#[wasm_bindgen]
+pub struct Queue {
+    slab: Slab<f64>,
+}
+
+#[wasm_bindgen]
+impl Queue {
+    /// Create a queue with the given capacity.
+    #[wasm_bindgen(constructor)]
+    pub fn new(capacity: u32) -> Result<Queue, JsError> {
+        let slab = Slab::with_capacity(capacity as usize)
+            .map_err(|_| JsError::new("capacity is too large"))?;
+        Ok(Queue { slab })
+    }
+
+    /// Push a value to the front of the queue and return its slot, or
+    /// `undefined` if the queue is full.
+    pub fn push(&mut self, value: f64) -> Option<u32> {
+        self.slab.push_front(value).ok().map(|slot| slot as _)
+    }
+
+    /// Pop the oldest value, or return `undefined` if the queue is empty.
+    pub fn pop(&mut self) -> Option<f64> {
+        self.slab.pop_back()
+    }
+
+    /// Return the value stored at a slot, or `undefined` if the slot is not
+    /// in use.
+    pub fn get(&self, slot: u32) -> Option<f64> {
+        self.slab.get(slot as _).ok().copied()
+    }
+
+    /// Remove the value stored at a slot, and return `false` if the slot
+    /// is not in use.
+    pub fn remove(&mut self, slot: u32) -> bool {
+        self.slab.remove(slot as _).is_ok()
+    }
+
+    /// Return the number of values in the queue.
+    #[wasm_bindgen(getter)]
+    pub fn len(&self) -> u32 {
+        self.slab.len() as u32
+    }
+
+    /// Return true if the queue is empty.
+    #[wasm_bindgen(js_name = isEmpty)]
+    pub fn is_empty(&self) -> bool {
+        self.slab.is_empty()
+    }
+}
@@ -0,0 +1,122 @@
+//! A pool allocator for fixed-size blocks.
+//!
+//! This module requires the `allocator_api` feature, and a nightly compiler.
+
+use core::{
+    alloc::{AllocError, Allocator, Layout},
+    cell::RefCell,
+    ptr::NonNull,
+};
+
+use crate::{Error, Slab, Slot, SlotType};
+
+/// An allocator handing out blocks of up to `SIZE` bytes, aligned to `ALIGN`
+/// bytes, from a pool allocated upfront.
+///
+/// Allocating and freeing a block are O(1), and never touch the global
+/// allocator. Requests for larger or more aligned blocks fail.
+///
+/// The allocator is meant to be used by reference:
+///
+/// ```ignore
+/// let pool = FixedBlockAlloc::<64, 8>::with_capacity(1024)?;
+/// let x = Box::new_in(42u64, &pool);
+/// ```
+#[derive(Debug)]
+pub struct FixedBlockAlloc<const SIZE: usize, const ALIGN: usize> {
+    slots: RefCell<Slab<()>>,
+    memory: NonNull<u8>,
+    layout: Layout,
+    stride: usize,
+}
+
+impl<const SIZE: usize, const ALIGN: usize> FixedBlockAlloc<SIZE, ALIGN> {
+    /// Create a new pool of `capacity` blocks.
+    /// `ALIGN` must be a power of two. Return `Error::OutOfMemory` if the
+    /// pool can't be allocated.
+    pub fn with_capacity(capacity: usize) -> Result<Self, Error> {
+        let block = Layout::from_size_align(SIZE.max(1), ALIGN).map_err(|_| Error::TooLarge)?;
+        let stride = block.pad_to_align().size();
+        let size = stride.checked_mul(capacity).ok_or(Error::TooLarge)?;
+        let layout = Layout::from_size_align(size, ALIGN).map_err(|_| Error::TooLarge)?;
+        let slots = RefCell::new(Slab::with_capacity(capacity)?);
+        // A zero-sized pool hands out no blocks, and must not be allocated.
+        let memory = if layout.size() == 0 {
+            NonNull::dangling()
+        } else {
+            NonNull::new(unsafe { alloc::alloc::alloc(layout) }).ok_or(Error::OutOfMemory)?
+        };
+        Ok(Self {
+            slots,
+            memory,
+            layout,
+            stride,
+        })
+    }
+
+    /// Return the number of blocks in the pool.
+    pub fn capacity(&self) -> usize {
+        self.slots.borrow().capacity()
+    }
+
+    /// Return the number of blocks currently allocated.
+    pub fn allocated(&self) -> usize {
+        self.slots.borrow().len()
+    }
+}
+
+unsafe impl<const SIZE: usize, const ALIGN: usize> Allocator for FixedBlockAlloc<SIZE, ALIGN> {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        if layout.size() > SIZE || layout.align() > ALIGN {
+            return Err(AllocError);
+        }
+        let slot = self
+            .slots
+            .borrow_mut()
+            .push_front(())
+            .map_err(|_| AllocError)?;
+        let block = unsafe { self.memory.add(slot.to_usize() * self.stride) };
+        Ok(NonNull::slice_from_raw_parts(block, SIZE))
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, _layout: Layout) {
+        let offset = unsafe { ptr.offset_from(self.memory) } as usize;
+        invariant_eq!(offset % self.stride, 0);
+        let slot = Slot::from_usize(offset / self.stride);
+        let removed = self.slots.borrow_mut().remove(slot);
+        invariant!(removed.is_ok());
+    }
+}
+
+impl<const SIZE: usize, const ALIGN: usize> Drop for FixedBlockAlloc<SIZE, ALIGN> {
+    fn drop(&mut self) {
+        if self.layout.size() != 0 {
+            unsafe { alloc::alloc::dealloc(self.memory.as_ptr(), self.layout) };
+        }
+    }
+}
+
+#[test]
+fn test_fixed_block_alloc() {
+    use alloc::boxed::Box;
+
+    let pool = FixedBlockAlloc::<16, 8>::with_capacity(2).unwrap();
+    let a = Box::new_in(1u64, &pool);
+    let b = Box::new_in([2u32; 4], &pool);
+    assert_eq!(pool.allocated(), 2);
+    assert!(Box::try_new_in(3u8, &pool).is_err());
+    assert!(FixedBlockAlloc::<4, 4>::with_capacity(1)
+        .unwrap()
+        .allocate(Layout::new::<u64>())
+        .is_err());
+    assert_eq!(*a + b[3] as u64, 3);
+    drop(a);
+    let c = Box::new_in(4u16, &pool);
+    assert_eq!(*c, 4);
+    drop((b, c));
+    assert_eq!(pool.allocated(), 0);
+
+    let empty = FixedBlockAlloc::<16, 8>::with_capacity(0).unwrap();
+    assert_eq!(empty.capacity(), 0);
+    assert!(Box::try_new_in(1u64, &empty).is_err());
+}
@@ -0,0 +1,204 @@
+//! A list of boxed, possibly unsized elements, such as trait objects.
+
+use alloc::boxed::Box;
+
+use crate::{Error, Slab, Slot, SlotType};
+
+/// A list of elements of an unsized type, such as `dyn Trait` or `[u8]`,
+/// stored as `Box<T>`.
+///
+/// This is a thin wrapper over `Slab<Box<T>>`: links and slots are the same,
+/// but accessors return `&T` rather than `&Box<T>`. The list itself only
+/// stores pointers, so elements are allocated individually when they are
+/// added, and freed when they are removed.
+///
+/// Elements of a concrete type can be added with `emplace_dyn()`, which
+/// checks that the list has room for them before boxing them:
+///
+/// ```
+/// use core::fmt::Display;
+/// use slabigator::boxed::BoxedSlab;
+///
+/// let mut slab: BoxedSlab<dyn Display> = BoxedSlab::with_capacity(2).unwrap();
+/// let a = slab.emplace_dyn(42, |x| x).unwrap();
+/// let b = slab.push_front_box(Box::new("hello")).unwrap();
+/// assert_eq!(slab.get(a).unwrap().to_string(), "42");
+/// assert_eq!(slab.get(b).unwrap().to_string(), "hello");
+/// ```
+pub struct BoxedSlab<T: ?Sized, S: SlotType = Slot> {
+    slab: Slab<Box<T>, S>,
+}
+
+impl<T: ?Sized> BoxedSlab<T> {
+    /// Create a new list with the given capacity.
+    pub fn with_capacity(capacity: usize) -> Result<Self, Error> {
+        Ok(Self {
+            slab: Slab::with_capacity(capacity)?,
+        })
+    }
+}
+
+impl<T: ?Sized, S: SlotType> BoxedSlab<T, S> {
+    /// Create a new list with the given capacity, using the slot type `S`.
+    pub fn with_slot_type(capacity: usize) -> Result<Self, Error> {
+        Ok(Self {
+            slab: Slab::with_slot_type(capacity)?,
+        })
+    }
+
+    /// Return the capacity of the list.
+    pub fn capacity(&self) -> usize {
+        self.slab.capacity()
+    }
+
+    /// Return the number of elements.
+    pub fn len(&self) -> usize {
+        self.slab.len()
+    }
+
+    /// Return true if the list is empty.
+    pub fn is_empty(&self) -> bool {
+        self.slab.is_empty()
+    }
+
+    /// Return true if the list is full.
+    pub fn is_full(&self) -> bool {
+        self.slab.is_full()
+    }
+
+    /// Prepend a boxed element to the beginning of the list, and return its
+    /// slot.
+    pub fn push_front_box(&mut self, value: Box<T>) -> Result<S, Error> {
+        self.slab.push_front(value)
+    }
+
+    /// Box an element and prepend it to the beginning of the list, and
+    /// return its slot.
+    ///
+    /// Converting a `Box<U>` to a `Box<dyn Trait>` requires an unsizing
+    /// coercion, that can't be expressed as a trait bound. So, `coerce`
+    /// performs it, and is usually the identity closure `|x| x`.
+    ///
+    /// Return `Error::Full`, without allocating, if the list is full.
+    pub fn emplace_dyn<U>(
+        &mut self,
+        value: U,
+        coerce: impl FnOnce(Box<U>) -> Box<T>,
+    ) -> Result<S, Error> {
+        if self.is_full() {
+            return Err(Error::Full);
+        }
+        self.push_front_box(coerce(Box::new(value)))
+    }
+
+    /// Return a reference to an element given its slot number.
+    pub fn get(&self, slot: S) -> Result<&T, Error> {
+        self.slab.get_checked(slot).map(|value| &**value)
+    }
+
+    /// Return a mutable reference to an element given its slot number.
+    pub fn get_mut(&mut self, slot: S) -> Result<&mut T, Error> {
+        self.slab.get_mut_checked(slot).map(|value| &mut **value)
+    }
+
+    /// Remove an element given its slot, and return it.
+    pub fn remove(&mut self, slot: S) -> Result<Box<T>, Error> {
+        self.slab.take_checked(slot)
+    }
+
+    /// Remove and return the tail element of the list.
+    pub fn pop_back(&mut self) -> Option<Box<T>> {
+        self.slab.pop_back()
+    }
+
+    /// Iterate over the elements, from the head to the tail.
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.slab.iter().map(|value| &**value)
+    }
+
+    /// Remove all the elements.
+    pub fn clear(&mut self) {
+        self.slab.clear();
+    }
+
+    /// Return the underlying list of boxes.
+    pub fn as_slab(&self) -> &Slab<Box<T>, S> {
+        &self.slab
+    }
+
+    /// Return the underlying list of boxes, mutably.
+    pub fn as_slab_mut(&mut self) -> &mut Slab<Box<T>, S> {
+        &mut self.slab
+    }
+}
+
+impl<T: ?Sized, S: SlotType> core::fmt::Debug for BoxedSlab<T, S> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("BoxedSlab")
+            .field("len", &self.len())
+            .field("capacity", &self.capacity())
+            .finish()
+    }
+}
+
+#[test]
+fn test_boxed_slab() {
+    use alloc::{string::ToString, vec::Vec};
+
+    trait Shape {
+        fn area(&self) -> u32;
+        fn scale(&mut self, factor: u32);
+    }
+
+    struct Square(u32);
+
+    impl Shape for Square {
+        fn area(&self) -> u32 {
+            self.0 * self.0
+        }
+
+        fn scale(&mut self, factor: u32) {
+            self.0 *= factor;
+        }
+    }
+
+    struct Rect(u32, u32);
+
+    impl Shape for Rect {
+        fn area(&self) -> u32 {
+            self.0 * self.1
+        }
+
+        fn scale(&mut self, factor: u32) {
+            self.0 *= factor;
+            self.1 *= factor;
+        }
+    }
+
+    let mut slab: BoxedSlab<dyn Shape> = BoxedSlab::with_capacity(3).unwrap();
+    slab.emplace_dyn(Square(2), |x| x).unwrap();
+    let b = slab.push_front_box(Box::new(Rect(2, 3))).unwrap();
+    let c = slab.emplace_dyn(Square(1), |x| x).unwrap();
+    assert!(slab.is_full());
+    assert_eq!(slab.emplace_dyn(Square(3), |x| x).err(), Some(Error::Full));
+
+    slab.get_mut(b).unwrap().scale(2);
+    assert_eq!(slab.get(b).unwrap().area(), 24);
+    let areas: Vec<_> = slab.iter().map(|shape| shape.area()).collect();
+    assert_eq!(areas, [1, 24, 4]);
+
+    assert_eq!(slab.remove(c).unwrap().area(), 1);
+    assert_eq!(slab.get(c).err(), Some(Error::InvalidSlot));
+    assert_eq!(slab.pop_back().unwrap().area(), 4);
+    assert_eq!(slab.as_slab().iter_slots().collect::<Vec<_>>(), [b]);
+    slab.clear();
+    assert!(slab.is_empty());
+    assert!(slab.emplace_dyn(Square(3), |x| x).is_ok());
+
+    let mut strings: BoxedSlab<str, u16> = BoxedSlab::with_slot_type(2).unwrap();
+    let s = strings
+        .push_front_box("hello".to_string().into_boxed_str())
+        .unwrap();
+    strings.get_mut(s).unwrap().make_ascii_uppercase();
+    assert_eq!(strings.get(s), Ok("HELLO"));
+}
@@ -0,0 +1,286 @@
+//! A log of the changes made to a list, to replicate it.
+//!
+//! With the `changelog` feature, every insertion and removal is recorded,
+//! with a sequence number, in a ring buffer holding as many events as the
+//! list can hold elements. A follower that has applied all the changes up
+//! to a sequence number can then catch up with `changes_since()` instead
+//! of copying the whole list. If it lags behind by more than the capacity,
+//! the events it needs have been overwritten, and a snapshot is not more
+//! expensive than the changes would have been anyway.
+//!
+//! Only changes to the set of occupied slots are recorded. Operations that
+//! reorder elements, such as `move_to_front()` or `sort_by()`, are not.
+//!
+//! The follower replays the events with `apply_change()`, which places
+//! elements in the same slots as on the primary list, so that slots can be
+//! exchanged between both.
+
+use alloc::boxed::Box;
+
+use crate::{Error, Slab, Slot, SlotType};
+
+/// The kind of a change.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Op {
+    /// An element was added to the beginning of the list.
+    Push,
+    /// An element was removed given its slot.
+    Remove,
+    /// The tail element was removed.
+    Pop,
+}
+
+/// A change made to a list.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct Event<S: SlotType = Slot> {
+    /// The sequence number of the change.
+    pub seq: u64,
+    /// The kind of change.
+    pub op: Op,
+    /// The slot of the element that was added or removed.
+    pub slot: S,
+}
+
+/// A change to apply to a list, with `apply_change()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change<D, S: SlotType = Slot> {
+    /// Store an element in a free slot, at the beginning of the list.
+    Push {
+        /// The slot the element is stored in.
+        slot: S,
+        /// The element.
+        value: D,
+    },
+    /// Remove the element stored in a slot.
+    Remove {
+        /// The slot of the element.
+        slot: S,
+    },
+    /// Remove the tail element, stored in a slot.
+    Pop {
+        /// The slot of the element.
+        slot: S,
+    },
+}
+
+#[derive(Debug)]
+pub(crate) struct ChangeLog<S: SlotType> {
+    events: Box<[Event<S>]>,
+    next_seq: u64,
+}
+
+impl<S: SlotType> ChangeLog<S> {
+    pub(crate) fn with_capacity(capacity: usize) -> Result<Self, Error> {
+        let event = Event {
+            seq: 0,
+            op: Op::Push,
+            slot: S::NUL,
+        };
+        let mut events = crate::try_vec(capacity)?;
+        events.resize(capacity, event);
+        Ok(ChangeLog {
+            events: events.into_boxed_slice(),
+            next_seq: 0,
+        })
+    }
+
+    #[inline]
+    pub(crate) fn record(&mut self, op: Op, slot: S) {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        if self.events.is_empty() {
+            return;
+        }
+        let index = (seq % self.events.len() as u64) as usize;
+        self.events[index] = Event { seq, op, slot };
+    }
+
+    fn oldest_seq(&self) -> u64 {
+        self.next_seq.saturating_sub(self.events.len() as u64)
+    }
+}
+
+/// An iterator over the changes made to a list, from the oldest to the
+/// most recent one.
+pub struct Changes<'a, S: SlotType = Slot> {
+    events: &'a [Event<S>],
+    seq: u64,
+    end: u64,
+}
+
+impl<S: SlotType> Iterator for Changes<'_, S> {
+    type Item = Event<S>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.seq == self.end {
+            return None;
+        }
+        let event = self.events[(self.seq % self.events.len() as u64) as usize];
+        self.seq += 1;
+        Some(event)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.end - self.seq) as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<S: SlotType> ExactSizeIterator for Changes<'_, S> {}
+
+impl<D, S: SlotType> Slab<D, S> {
+    /// Return the sequence number the next change will get.
+    ///
+    /// A follower copying the list should save it along with the copy, and
+    /// later pass it to `changes_since()` to get the changes made since.
+    pub fn next_change_seq(&self) -> u64 {
+        self.changelog.next_seq
+    }
+
+    /// Return the changes whose sequence number is at least `seq`, from the
+    /// oldest to the most recent one.
+    ///
+    /// Return `None` if some of them are no longer in the log, or if `seq`
+    /// is larger than `next_change_seq()`. The follower then has to make a
+    /// new copy of the list.
+    pub fn changes_since(&self, seq: u64) -> Option<Changes<'_, S>> {
+        let log = &self.changelog;
+        if seq < log.oldest_seq() || seq > log.next_seq {
+            return None;
+        }
+        Some(Changes {
+            events: &log.events,
+            seq,
+            end: log.next_seq,
+        })
+    }
+
+    /// Apply a change made to another list, typically built from an event
+    /// returned by `changes_since()`, placing elements in the same slots.
+    ///
+    /// Return `Error::InvalidSlot`, leaving the list unchanged, if a slot
+    /// to store an element in is out of range or already occupied, or if a
+    /// slot to remove an element from is free. The list is then out of
+    /// sync with the other one, and has to be copied again.
+    ///
+    /// Since reordering operations are not recorded, `Change::Pop` removes
+    /// the element in the given slot even if it is not the tail, so that
+    /// both lists keep the same slots occupied. Checking slots is O(n) if
+    /// the crate is compiled with `releasefast` and without `generational`
+    /// or `tagged`.
+    pub fn apply_change(&mut self, change: Change<D, S>) -> Result<(), Error> {
+        match change {
+            Change::Push { slot, value } => self.insert_at(slot, value),
+            Change::Remove { slot } | Change::Pop { slot } => {
+                if !self.is_occupied(slot) {
+                    return Err(Error::InvalidSlot);
+                }
+                if matches!(change, Change::Pop { .. }) && slot == self.tail {
+                    self.pop_back();
+                } else {
+                    unsafe { self.drop_unchecked(slot) };
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[test]
+fn test_changes_since() {
+    let mut slab = Slab::with_capacity(3).unwrap();
+    assert_eq!(slab.next_change_seq(), 0);
+    assert_eq!(slab.changes_since(0).unwrap().count(), 0);
+    assert!(slab.changes_since(1).is_none());
+
+    let a = slab.push_front('a').unwrap();
+    let b = slab.push_front('b').unwrap();
+    let seq = slab.next_change_seq();
+    let c = slab.push_front('c').unwrap();
+    slab.remove(b).unwrap();
+    assert_eq!(slab.pop_back(), Some('a'));
+
+    let changes = slab.changes_since(seq).unwrap();
+    assert_eq!(changes.len(), 3);
+    let changes: Vec<_> = changes
+        .map(|event| (event.seq, event.op, event.slot))
+        .collect();
+    assert_eq!(
+        changes,
+        [(2, Op::Push, c), (3, Op::Remove, b), (4, Op::Pop, a)]
+    );
+
+    // Only the last `capacity()` changes are kept.
+    assert!(slab.changes_since(1).is_none());
+    assert_eq!(slab.changes_since(5).unwrap().count(), 0);
+
+    slab.push_front('d').unwrap();
+    slab.push_front('e').unwrap();
+    slab.drain_back(2).for_each(drop);
+    slab.clear();
+    let ops: Vec<_> = slab
+        .changes_since(7)
+        .unwrap()
+        .map(|event| event.op)
+        .collect();
+    assert_eq!(ops, [Op::Pop, Op::Pop, Op::Remove]);
+    assert!(slab.is_empty());
+
+    let mut slab = Slab::with_capacity(0).unwrap();
+    assert_eq!(slab.push_front(()), Err(crate::Error::Full));
+    assert_eq!(slab.changes_since(0).unwrap().count(), 0);
+}
+
+#[test]
+fn test_apply_change() {
+    // Every round makes at most 12 changes, which all fit in the log.
+    let mut primary = Slab::with_capacity(16).unwrap();
+    let mut follower = Slab::with_capacity(16).unwrap();
+    let mut seq = follower.next_change_seq();
+    for round in 0..20u32 {
+        let a = primary.push_front(round).unwrap();
+        primary.push_front(round + 100).unwrap();
+        primary.push_front(round + 200).unwrap();
+        primary.remove(a).unwrap();
+        primary.pop_back();
+        primary.push_front(round + 300).unwrap();
+        if round % 3 == 0 {
+            primary.clear();
+        }
+
+        // Elements that have been removed since are replaced with a
+        // placeholder, that a later change removes as well.
+        let changes: Vec<_> = primary.changes_since(seq).unwrap().collect();
+        for event in changes {
+            let slot = event.slot;
+            let change = match event.op {
+                Op::Push => Change::Push {
+                    slot,
+                    value: primary.get(slot).copied().unwrap_or(u32::MAX),
+                },
+                Op::Remove => Change::Remove { slot },
+                Op::Pop => Change::Pop { slot },
+            };
+            follower.apply_change(change).unwrap();
+        }
+        seq = primary.next_change_seq();
+        assert_eq!(follower.to_vec_with_slots(), primary.to_vec_with_slots());
+    }
+
+    let slot = follower.peek_next_free().unwrap();
+    assert_eq!(
+        follower.apply_change(Change::Remove { slot }),
+        Err(Error::InvalidSlot)
+    );
+    assert_eq!(
+        follower.apply_change(Change::Pop { slot }),
+        Err(Error::InvalidSlot)
+    );
+    let occupied = follower.iter_slots().next().unwrap();
+    let change = Change::Push {
+        slot: occupied,
+        value: 0,
+    };
+    assert_eq!(follower.apply_change(change), Err(Error::InvalidSlot));
+    assert_eq!(follower.to_vec_with_slots(), primary.to_vec_with_slots());
+}
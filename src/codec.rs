@@ -0,0 +1,254 @@
+//! A compact binary encoding of a slab, preserving slots and order.
+//!
+//! The layout is the one `borsh` would produce for:
+//!
+//! ```ignore
+//! struct EncodedSlab<D, S> {
+//!     capacity: u64,
+//!     entries: Vec<(S, D)>, // from the head to the tail
+//! }
+//! ```
+//!
+//! with little-endian integers, and a `u32` length prefix for `entries`.
+//! With the `borsh` feature, `Slab` implements `BorshSerialize` and
+//! `BorshDeserialize` using that layout. Otherwise, elements are encoded
+//! and decoded by caller-provided functions, so any element format can be
+//! used, including `postcard` ones.
+//!
+//! The input of a decoder may come from an untrusted source. Nothing is
+//! allocated for the entries before they have been read, and the capacity
+//! is checked against a limit before the slab is created.
+
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use crate::{Error, Slab, SlotType};
+
+fn read<'a>(bytes: &mut &'a [u8], len: usize) -> Result<&'a [u8], Error> {
+    if bytes.len() < len {
+        return Err(Error::InvalidSlot);
+    }
+    let (head, tail) = bytes.split_at(len);
+    *bytes = tail;
+    Ok(head)
+}
+
+// Slots are encoded as little-endian integers of the size of `S`.
+fn encode_slot<S: SlotType>(slot: S, out: &mut Vec<u8>) {
+    out.extend_from_slice(&(slot.to_usize() as u64).to_le_bytes()[..size_of::<S>()]);
+}
+
+fn decode_slot<S: SlotType>(input: &mut &[u8], capacity: usize) -> Result<S, Error> {
+    let mut buf = [0u8; 8];
+    buf[..size_of::<S>()].copy_from_slice(read(input, size_of::<S>())?);
+    match usize::try_from(u64::from_le_bytes(buf)) {
+        Ok(index) if index < capacity => Ok(S::from_usize(index)),
+        _ => Err(Error::InvalidSlot),
+    }
+}
+
+// Check an encoded capacity before allocating a slab for it.
+fn check_capacity<S: SlotType>(capacity: u64, max_capacity: usize) -> Result<usize, Error> {
+    match usize::try_from(capacity) {
+        Ok(capacity) if capacity <= max_capacity.min(S::MAX_CAPACITY) => Ok(capacity),
+        _ => Err(Error::TooLarge),
+    }
+}
+
+// Create a slab from entries listed from the head to the tail.
+fn build<D, S: SlotType>(capacity: usize, entries: Vec<(S, D)>) -> Result<Slab<D, S>, Error> {
+    let mut slab = Slab::with_slot_type(capacity)?;
+    // Elements are linked at the head, so insert them from the tail.
+    for (slot, value) in entries.into_iter().rev() {
+        slab.insert_at(slot, value)?;
+    }
+    Ok(slab)
+}
+
+impl<D, S: SlotType> Slab<D, S> {
+    /// Append the encoding of the list to `out`, using `encode` to encode
+    /// every element.
+    pub fn encode_with<F>(&self, out: &mut Vec<u8>, mut encode: F) -> Result<(), Error>
+    where
+        F: FnMut(&D, &mut Vec<u8>),
+    {
+        let len = u32::try_from(self.len()).map_err(|_| Error::TooLarge)?;
+        out.extend_from_slice(&(self.capacity() as u64).to_le_bytes());
+        out.extend_from_slice(&len.to_le_bytes());
+        for slot in self.iter_slots() {
+            encode_slot(slot, out);
+            encode(unsafe { self.get_unchecked(slot) }, out);
+        }
+        Ok(())
+    }
+
+    /// Decode a list encoded with `encode_with()`, using `decode` to decode
+    /// every element. `decode` returns the element along with the number of
+    /// bytes it consumed.
+    ///
+    /// Return the list and the number of bytes consumed, `Error::TooLarge`
+    /// if the encoded capacity is larger than `max_capacity`, or
+    /// `Error::InvalidSlot` if the input is truncated or malformed.
+    pub fn decode_with<F>(
+        bytes: &[u8],
+        max_capacity: usize,
+        mut decode: F,
+    ) -> Result<(Self, usize), Error>
+    where
+        F: FnMut(&[u8]) -> Result<(D, usize), Error>,
+    {
+        let mut input = bytes;
+        let capacity = u64::from_le_bytes(read(&mut input, 8)?.try_into().unwrap());
+        let capacity = check_capacity::<S>(capacity, max_capacity)?;
+        let len = u32::from_le_bytes(read(&mut input, 4)?.try_into().unwrap()) as usize;
+        // Every entry takes at least the size of a slot.
+        if len > capacity || len > input.len() / size_of::<S>() {
+            return Err(Error::InvalidSlot);
+        }
+        let mut entries = Vec::new();
+        for _ in 0..len {
+            let slot = decode_slot(&mut input, capacity)?;
+            let (value, used) = decode(input)?;
+            read(&mut input, used)?;
+            entries.push((slot, value));
+        }
+        Ok((build(capacity, entries)?, bytes.len() - input.len()))
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl<D, S> borsh::BorshSerialize for Slab<D, S>
+where
+    D: borsh::BorshSerialize,
+    S: SlotType + borsh::BorshSerialize,
+{
+    fn serialize<W: borsh::io::Write>(&self, writer: &mut W) -> borsh::io::Result<()> {
+        let len = u32::try_from(self.len())
+            .map_err(|_| borsh::io::Error::from(borsh::io::ErrorKind::InvalidData))?;
+        (self.capacity() as u64).serialize(writer)?;
+        len.serialize(writer)?;
+        for (slot, value) in self.iter_slots().zip(self.iter()) {
+            slot.serialize(writer)?;
+            value.serialize(writer)?;
+        }
+        Ok(())
+    }
+}
+
+/// The capacity is only bounded by the largest capacity of the slot type,
+/// since there is no way to pass a limit. Use `decode_with()`, or a small
+/// slot type such as `u16`, if the input is not trusted.
+#[cfg(feature = "borsh")]
+impl<D, S> borsh::BorshDeserialize for Slab<D, S>
+where
+    D: borsh::BorshDeserialize,
+    S: SlotType + borsh::BorshDeserialize,
+{
+    fn deserialize_reader<R: borsh::io::Read>(reader: &mut R) -> borsh::io::Result<Self> {
+        let invalid = |_| borsh::io::Error::from(borsh::io::ErrorKind::InvalidData);
+        let capacity = u64::deserialize_reader(reader)?;
+        let capacity = check_capacity::<S>(capacity, usize::MAX).map_err(invalid)?;
+        let len = u32::deserialize_reader(reader)? as usize;
+        if len > capacity {
+            return Err(invalid(Error::InvalidSlot));
+        }
+        // Entries are read before being stored, so that a length that
+        // doesn't match the input doesn't allocate anything.
+        let mut entries = Vec::new();
+        for _ in 0..len {
+            let slot = S::deserialize_reader(reader)?;
+            if slot.to_usize() >= capacity {
+                return Err(invalid(Error::InvalidSlot));
+            }
+            entries.push((slot, D::deserialize_reader(reader)?));
+        }
+        build(capacity, entries).map_err(invalid)
+    }
+}
+
+#[test]
+fn test_codec() {
+    let mut slab = Slab::with_capacity(4).unwrap();
+    let [a, b, c] = slab.push_front_many([1u16, 2, 3]).unwrap();
+    slab.remove(b).unwrap();
+    slab.move_to_front(a).unwrap();
+
+    let mut bytes = Vec::new();
+    slab.encode_with(&mut bytes, |value, out| {
+        out.extend_from_slice(&value.to_le_bytes())
+    })
+    .unwrap();
+    let slot_size = size_of::<crate::Slot>();
+    assert_eq!(bytes.len(), 8 + 4 + 2 * (slot_size + 2));
+    bytes.push(0xff);
+
+    let decode = |bytes: &[u8]| {
+        let value = bytes.get(..2).ok_or(Error::InvalidSlot)?;
+        Ok((u16::from_le_bytes(value.try_into().unwrap()), 2))
+    };
+    let (decoded, used) = Slab::<u16>::decode_with(&bytes, 4, decode).unwrap();
+    assert_eq!(used, bytes.len() - 1);
+    assert_eq!(decoded.capacity(), 4);
+    assert_eq!(decoded.iter().copied().collect::<Vec<_>>(), [1, 3]);
+    assert_eq!(decoded.get(a), Ok(&1));
+    assert_eq!(decoded.get(c), Ok(&3));
+    assert!(!decoded.is_occupied(b));
+
+    assert!(Slab::<u16>::decode_with(&bytes[..used - 1], 4, decode).is_err());
+    assert_eq!(
+        Slab::<u16>::decode_with(&bytes, 3, decode).err(),
+        Some(Error::TooLarge)
+    );
+
+    // A length that doesn't match the input is rejected before anything
+    // is allocated for it.
+    let mut forged = Vec::new();
+    forged.extend_from_slice(&4u64.to_le_bytes());
+    forged.extend_from_slice(&u32::MAX.to_le_bytes());
+    assert_eq!(
+        Slab::<u16>::decode_with(&forged, usize::MAX, decode).err(),
+        Some(Error::InvalidSlot)
+    );
+    forged[..8].copy_from_slice(&u64::MAX.to_le_bytes());
+    assert_eq!(
+        Slab::<u16>::decode_with(&forged, 1 << 20, decode).err(),
+        Some(Error::TooLarge)
+    );
+
+    let mut small = Slab::<u8, u16>::with_slot_type(3).unwrap();
+    small.push_front_many([7, 8]).unwrap();
+    let mut bytes = Vec::new();
+    small
+        .encode_with(&mut bytes, |value, out| out.push(*value))
+        .unwrap();
+    assert_eq!(bytes.len(), 8 + 4 + 2 * (2 + 1));
+    let (decoded, _) = Slab::<u8, u16>::decode_with(&bytes, 3, |bytes| Ok((bytes[0], 1))).unwrap();
+    assert!(decoded.iter_slots().eq(small.iter_slots()));
+    assert!(decoded.iter().eq(small.iter()));
+}
+
+#[cfg(feature = "borsh")]
+#[test]
+fn test_codec_borsh() {
+    let mut slab = Slab::<u16, u32>::with_slot_type(4).unwrap();
+    let [a, b, _] = slab.push_front_many([1, 2, 3]).unwrap();
+    slab.remove(b).unwrap();
+    slab.move_to_front(a).unwrap();
+
+    let bytes = borsh::to_vec(&slab).unwrap();
+    let mut expected = Vec::new();
+    slab.encode_with(&mut expected, |value, out| {
+        out.extend_from_slice(&value.to_le_bytes())
+    })
+    .unwrap();
+    assert_eq!(bytes, expected);
+
+    let decoded: Slab<u16, u32> = borsh::from_slice(&bytes).unwrap();
+    assert!(decoded.iter_slots().eq(slab.iter_slots()));
+    assert!(decoded.iter().eq(slab.iter()));
+
+    assert!(borsh::from_slice::<Slab<u16, u32>>(&bytes[..bytes.len() - 1]).is_err());
+    let mut forged = bytes.clone();
+    forged[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(borsh::from_slice::<Slab<u16, u32>>(&forged).is_err());
+}
@@ -0,0 +1,321 @@
+//! Adapters mimicking the API of other slab-like crates.
+//!
+//! They are meant to ease migrations: switching from `slab::Slab` to
+//! `slabigator::compat::SlabCompat` should only require changing an import.
+
+use core::ops::{Index, IndexMut};
+
+#[cfg(feature = "generational")]
+use crate::VersionedSlot;
+use crate::{Error, Slab, Slot, SlotType, NUL};
+
+/// A fixed-capacity slab exposing the same methods as the `slab` crate.
+///
+/// Keys are `usize` values, and remain stable until the entry is removed.
+/// Unlike `slab::Slab`, the storage never grows: `insert()` panics if
+/// the slab is full, and `try_insert()` returns `Error::Full` instead.
+#[derive(Debug)]
+pub struct SlabCompat<D> {
+    slab: Slab<D>,
+}
+
+impl<D> SlabCompat<D> {
+    /// Create a new slab with the given capacity.
+    /// Panics if the capacity is too large for the slot type.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            slab: Slab::with_capacity(capacity).expect("capacity too large"),
+        }
+    }
+
+    /// Return the capacity of the slab.
+    pub fn capacity(&self) -> usize {
+        self.slab.capacity()
+    }
+
+    /// Return the number of stored values.
+    pub fn len(&self) -> usize {
+        self.slab.len()
+    }
+
+    /// Return true if no values are stored.
+    pub fn is_empty(&self) -> bool {
+        self.slab.is_empty()
+    }
+
+    /// Remove all the values.
+    pub fn clear(&mut self) {
+        while self.slab.pop_back().is_some() {}
+    }
+
+    /// Return a reference to the value associated with the given key.
+    pub fn get(&self, key: usize) -> Option<&D> {
+        self.slab.get_checked(Self::slot(key)?).ok()
+    }
+
+    /// Return a mutable reference to the value associated with the given key.
+    pub fn get_mut(&mut self, key: usize) -> Option<&mut D> {
+        self.slab.get_mut_checked(Self::slot(key)?).ok()
+    }
+
+    /// Return the key of the next value to be inserted.
+    /// Panics if the slab is full.
+    pub fn vacant_key(&self) -> usize {
+        assert!(self.slab.free_head != NUL, "slab is full");
+        self.slab.free_head.to_usize()
+    }
+
+    /// Insert a value and return its key.
+    /// Panics if the slab is full.
+    pub fn insert(&mut self, val: D) -> usize {
+        self.try_insert(val).expect("slab is full")
+    }
+
+    /// Insert a value and return its key, or `Error::Full` if the slab is full.
+    pub fn try_insert(&mut self, val: D) -> Result<usize, Error> {
+        self.slab.push_front(val).map(|slot| slot.to_usize())
+    }
+
+    /// Remove and return the value associated with the given key.
+    /// Panics if the key is not associated with a value.
+    pub fn remove(&mut self, key: usize) -> D {
+        self.try_remove(key).expect("invalid key")
+    }
+
+    /// Remove and return the value associated with the given key, if any.
+    pub fn try_remove(&mut self, key: usize) -> Option<D> {
+        self.slab.take_checked(Self::slot(key)?).ok()
+    }
+
+    /// Return true if a value is associated with the given key.
+    pub fn contains(&self, key: usize) -> bool {
+        Self::slot(key).is_some_and(|slot| self.slab.contains_slot(slot))
+    }
+
+    /// Keep only the values for which the predicate returns true.
+    pub fn retain(&mut self, mut f: impl FnMut(usize, &mut D) -> bool) {
+        let mut slot = self.slab.head;
+        while slot != NUL {
+            let next = self.slab.vec_next[slot.to_usize()];
+            let keep = f(slot.to_usize(), unsafe {
+                self.slab.get_unchecked_mut(slot)
+            });
+            if !keep {
+                let _ = self.slab.remove_checked(slot);
+            }
+            slot = next;
+        }
+    }
+
+    /// Iterate over `(key, &value)` pairs, from the most recently inserted
+    /// value to the oldest one.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &D)> + '_ {
+        let slab = &self.slab;
+        let mut slot = slab.head;
+        core::iter::from_fn(move || {
+            if slot == NUL {
+                return None;
+            }
+            let current = slot;
+            slot = slab.vec_next[current.to_usize()];
+            Some((current.to_usize(), unsafe { slab.get_unchecked(current) }))
+        })
+    }
+
+    /// Return the underlying slab.
+    pub fn into_inner(self) -> Slab<D> {
+        self.slab
+    }
+
+    fn slot(key: usize) -> Option<Slot> {
+        Slot::try_from(key).ok().filter(|&slot| slot != NUL)
+    }
+}
+
+impl<D> From<Slab<D>> for SlabCompat<D> {
+    fn from(slab: Slab<D>) -> Self {
+        Self { slab }
+    }
+}
+
+impl<D> Index<usize> for SlabCompat<D> {
+    type Output = D;
+
+    fn index(&self, key: usize) -> &D {
+        self.get(key).expect("invalid key")
+    }
+}
+
+impl<D> IndexMut<usize> for SlabCompat<D> {
+    fn index_mut(&mut self, key: usize) -> &mut D {
+        self.get_mut(key).expect("invalid key")
+    }
+}
+
+/// A fixed-capacity map with versioned keys, exposing the same methods as
+/// the `slotmap` crate.
+///
+/// Keys are `VersionedSlot` values: once an entry is removed, its key is
+/// never considered valid again, even if the slot gets reused.
+/// Iteration follows the list order, from the newest to the oldest entry.
+#[cfg(feature = "generational")]
+#[derive(Debug)]
+pub struct SlotMapLike<D> {
+    slab: Slab<D>,
+}
+
+#[cfg(feature = "generational")]
+impl<D> SlotMapLike<D> {
+    /// Create a new map with the given capacity.
+    /// Panics if the capacity is too large for the slot type.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            slab: Slab::with_capacity(capacity).expect("capacity too large"),
+        }
+    }
+
+    /// Return the capacity of the map.
+    pub fn capacity(&self) -> usize {
+        self.slab.capacity()
+    }
+
+    /// Return the number of stored values.
+    pub fn len(&self) -> usize {
+        self.slab.len()
+    }
+
+    /// Return true if no values are stored.
+    pub fn is_empty(&self) -> bool {
+        self.slab.is_empty()
+    }
+
+    /// Remove all the values.
+    pub fn clear(&mut self) {
+        while self.slab.pop_back().is_some() {}
+    }
+
+    /// Insert a value and return its key.
+    /// Panics if the map is full.
+    pub fn insert(&mut self, value: D) -> VersionedSlot {
+        self.try_insert(value).expect("map is full")
+    }
+
+    /// Insert a value and return its key, or `Error::Full` if the map is full.
+    pub fn try_insert(&mut self, value: D) -> Result<VersionedSlot, Error> {
+        self.slab.push_front_versioned(value)
+    }
+
+    /// Return true if the key references a value.
+    pub fn contains_key(&self, key: VersionedSlot) -> bool {
+        self.slab.contains_versioned(key)
+    }
+
+    /// Return a reference to the value associated with the given key.
+    pub fn get(&self, key: VersionedSlot) -> Option<&D> {
+        self.slab.get_versioned(key).ok()
+    }
+
+    /// Return a mutable reference to the value associated with the given key.
+    pub fn get_mut(&mut self, key: VersionedSlot) -> Option<&mut D> {
+        self.slab.get_versioned_mut(key).ok()
+    }
+
+    /// Remove and return the value associated with the given key, if any.
+    pub fn remove(&mut self, key: VersionedSlot) -> Option<D> {
+        self.slab.take_versioned(key).ok()
+    }
+
+    /// Keep only the values for which the predicate returns true.
+    pub fn retain(&mut self, mut f: impl FnMut(VersionedSlot, &mut D) -> bool) {
+        let mut slot = self.slab.head;
+        while slot != NUL {
+            let next = self.slab.vec_next[slot.to_usize()];
+            let key = self.slab.versioned_unchecked(slot);
+            if !f(key, unsafe { self.slab.get_unchecked_mut(slot) }) {
+                let _ = self.slab.remove_versioned(key);
+            }
+            slot = next;
+        }
+    }
+
+    /// Iterate over `(key, &value)` pairs.
+    pub fn iter(&self) -> impl Iterator<Item = (VersionedSlot, &D)> + '_ {
+        let slab = &self.slab;
+        let mut slot = slab.head;
+        core::iter::from_fn(move || {
+            if slot == NUL {
+                return None;
+            }
+            let current = slot;
+            slot = slab.vec_next[current.to_usize()];
+            Some((slab.versioned_unchecked(current), unsafe {
+                slab.get_unchecked(current)
+            }))
+        })
+    }
+
+    /// Iterate over the keys.
+    pub fn keys(&self) -> impl Iterator<Item = VersionedSlot> + '_ {
+        self.iter().map(|(key, _)| key)
+    }
+
+    /// Iterate over the values.
+    pub fn values(&self) -> impl Iterator<Item = &D> + '_ {
+        self.slab.iter()
+    }
+
+    /// Return the underlying slab.
+    pub fn into_inner(self) -> Slab<D> {
+        self.slab
+    }
+}
+
+#[test]
+fn test_slab_compat() {
+    let mut slab = SlabCompat::with_capacity(3);
+    let next = slab.vacant_key();
+    let a = slab.insert("a");
+    assert_eq!(a, next);
+    let b = slab.insert("b");
+    let c = slab.insert("c");
+    assert!(slab.try_insert("d").is_err());
+    assert_eq!(slab[b], "b");
+    assert!(slab.contains(a));
+    assert_eq!(slab.remove(a), "a");
+    assert!(!slab.contains(a));
+    assert_eq!(slab.try_remove(a), None);
+    assert_eq!(slab.get(usize::MAX), None);
+    slab[c] = "C";
+    assert_eq!(
+        slab.iter().collect::<alloc::vec::Vec<_>>(),
+        [(c, &"C"), (b, &"b")]
+    );
+    slab.retain(|key, _| key != b);
+    assert_eq!(slab.len(), 1);
+    slab.clear();
+    assert!(slab.is_empty());
+}
+
+#[cfg(feature = "generational")]
+#[test]
+fn test_slotmap_like() {
+    let mut map = SlotMapLike::with_capacity(2);
+    let a = map.insert(1);
+    let b = map.insert(2);
+    assert!(map.try_insert(3).is_err());
+    assert_eq!(map.remove(a), Some(1));
+    assert_eq!(map.remove(a), None);
+    let c = map.insert(3);
+    assert_eq!(c.slot(), a.slot());
+    assert!(!map.contains_key(a));
+    assert_eq!(map.get(a), None);
+    *map.get_mut(c).unwrap() += 10;
+    assert_eq!(map.keys().collect::<alloc::vec::Vec<_>>(), [c, b]);
+    assert_eq!(
+        map.values().copied().collect::<alloc::vec::Vec<_>>(),
+        [13, 2]
+    );
+    map.retain(|key, _| key == b);
+    assert_eq!(map.len(), 1);
+    assert_eq!(map.get(b), Some(&2));
+}
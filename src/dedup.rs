@@ -0,0 +1,86 @@
+//! A sliding window of recently seen keys, for replay protection.
+
+use core::hash::{BuildHasher, Hash};
+
+use crate::{
+    map::{DefaultHashBuilder, SlabMap},
+    Error,
+};
+
+/// Remembers the last `capacity` distinct keys it has seen.
+///
+/// Once the window is full, inserting a new key evicts the oldest one.
+/// Both lookups and insertions are O(1).
+#[derive(Debug)]
+pub struct DedupWindow<K, S = DefaultHashBuilder> {
+    map: SlabMap<K, (), S>,
+}
+
+impl<K: Hash + Eq> DedupWindow<K, DefaultHashBuilder> {
+    /// Create a new window remembering up to `capacity` keys.
+    pub fn with_capacity(capacity: usize) -> Result<Self, Error> {
+        Self::with_capacity_and_hasher(capacity, Default::default())
+    }
+}
+
+impl<K: Hash + Eq, S: BuildHasher> DedupWindow<K, S> {
+    /// Create a new window remembering up to `capacity` keys, using the
+    /// given hasher to index them.
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Result<Self, Error> {
+        Ok(Self {
+            map: SlabMap::with_capacity_and_hasher(capacity, hash_builder)?,
+        })
+    }
+
+    /// Return the maximum number of keys remembered.
+    pub fn capacity(&self) -> usize {
+        self.map.capacity()
+    }
+
+    /// Return the number of keys currently remembered.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Return true if no keys are remembered.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Return true if the key is in the window.
+    pub fn contains(&self, key: &K) -> bool {
+        self.map.contains_key(key)
+    }
+
+    /// Add a key to the window, evicting the oldest key if necessary.
+    /// Return `false` if the key was already in the window, which then
+    /// remains unchanged.
+    pub fn insert_if_absent(&mut self, key: K) -> bool {
+        if self.map.contains_key(&key) {
+            return false;
+        }
+        if self.map.is_full() {
+            self.map.pop_oldest();
+        }
+        self.map.insert(key, ()).is_ok()
+    }
+
+    /// Forget all the keys.
+    pub fn clear(&mut self) {
+        self.map.clear()
+    }
+}
+
+#[test]
+fn test_dedup_window() {
+    let mut window = DedupWindow::with_capacity(3).unwrap();
+    assert!(window.insert_if_absent(1));
+    assert!(window.insert_if_absent(2));
+    assert!(!window.insert_if_absent(1));
+    assert!(window.insert_if_absent(3));
+    assert!(window.insert_if_absent(4));
+    assert!(!window.contains(&1));
+    assert!(window.insert_if_absent(1));
+    assert!(!window.insert_if_absent(4));
+    assert_eq!(window.len(), 3);
+}
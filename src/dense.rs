@@ -0,0 +1,158 @@
+//! A dense array of the occupied slots of a list.
+
+use alloc::boxed::Box;
+
+use crate::{Error, Slab, Slot, SlotType};
+
+/// The occupied slots of a list, stored contiguously, in no particular
+/// order.
+///
+/// Iterating over a list follows links, and visits slots in the order of
+/// the list. When that order doesn't matter, iterating over a `DenseIndex`
+/// reads slots sequentially instead. The index is kept up to date by
+/// calling `insert()` after adding an element to the list, and `remove()`
+/// after removing one, both in O(1): removing a slot moves the last slot
+/// of the array in its place.
+#[derive(Debug, Clone)]
+pub struct DenseIndex<S: SlotType = Slot> {
+    slots: Box<[S]>,
+    len: usize,
+    // The position of every slot in `slots`, or `NUL` if it is not indexed.
+    positions: Box<[S]>,
+}
+
+impl<S: SlotType> DenseIndex<S> {
+    /// Create an index for a list, containing the slots of its current
+    /// elements.
+    pub fn for_slab<D>(slab: &Slab<D, S>) -> Self {
+        let capacity = slab.capacity();
+        let mut index = DenseIndex {
+            slots: alloc::vec![S::NUL; capacity].into_boxed_slice(),
+            len: 0,
+            positions: alloc::vec![S::NUL; capacity].into_boxed_slice(),
+        };
+        for slot in slab.iter_slots() {
+            index
+                .insert(slot)
+                .expect("the slots of a list are distinct and in range");
+        }
+        index
+    }
+
+    /// Return the number of indexed slots.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Return true if no slots are indexed.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return true if the slot is indexed.
+    pub fn contains(&self, slot: S) -> bool {
+        self.positions
+            .get(slot.to_usize())
+            .is_some_and(|&position| position != S::NUL)
+    }
+
+    /// Add a slot to the index.
+    /// Return `Error::InvalidSlot` if it is out of range, or already indexed.
+    pub fn insert(&mut self, slot: S) -> Result<(), Error> {
+        if slot.to_usize() >= self.positions.len() || self.contains(slot) {
+            return Err(Error::InvalidSlot);
+        }
+        self.slots[self.len] = slot;
+        self.positions[slot.to_usize()] = S::from_usize(self.len);
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Remove a slot from the index, replacing it with the last indexed
+    /// slot.
+    /// Return `Error::InvalidSlot` if it is not indexed.
+    pub fn remove(&mut self, slot: S) -> Result<(), Error> {
+        if !self.contains(slot) {
+            return Err(Error::InvalidSlot);
+        }
+        let position = self.positions[slot.to_usize()];
+        self.len -= 1;
+        let last = self.slots[self.len];
+        self.slots[position.to_usize()] = last;
+        self.positions[last.to_usize()] = position;
+        self.positions[slot.to_usize()] = S::NUL;
+        Ok(())
+    }
+
+    /// Remove all the slots from the index.
+    pub fn clear(&mut self) {
+        for &slot in &self.slots[..self.len] {
+            self.positions[slot.to_usize()] = S::NUL;
+        }
+        self.len = 0;
+    }
+
+    /// Return the indexed slots.
+    pub fn as_slice(&self) -> &[S] {
+        &self.slots[..self.len]
+    }
+
+    /// Iterate over the elements of a list whose slots are indexed, along
+    /// with their slots.
+    /// Panics if a slot is not occupied in the list.
+    pub fn iter<'a, D>(&'a self, slab: &'a Slab<D, S>) -> impl Iterator<Item = (S, &'a D)> + 'a {
+        self.as_slice().iter().map(move |&slot| {
+            let value = slab.get_checked(slot).expect("the index is out of sync");
+            (slot, value)
+        })
+    }
+
+    /// Call a function with every element of a list whose slot is indexed,
+    /// along with its slot.
+    /// Panics if a slot is not occupied in the list.
+    pub fn for_each_mut<D>(&self, slab: &mut Slab<D, S>, mut f: impl FnMut(S, &mut D)) {
+        for &slot in self.as_slice() {
+            f(
+                slot,
+                slab.get_mut_checked(slot)
+                    .expect("the index is out of sync"),
+            );
+        }
+    }
+}
+
+#[test]
+fn test_dense_index() {
+    let mut slab = Slab::with_capacity(5).unwrap();
+    let a = slab.push_front(1).unwrap();
+    let mut index = DenseIndex::for_slab(&slab);
+    assert_eq!(index.as_slice(), [a]);
+
+    let b = slab.push_front(2).unwrap();
+    index.insert(b).unwrap();
+    let c = slab.push_front(3).unwrap();
+    index.insert(c).unwrap();
+    assert_eq!(index.insert(c), Err(Error::InvalidSlot));
+    assert_eq!(index.insert(5), Err(Error::InvalidSlot));
+
+    slab.remove(a).unwrap();
+    index.remove(a).unwrap();
+    assert_eq!(index.remove(a), Err(Error::InvalidSlot));
+    assert_eq!(index.as_slice(), [c, b]);
+    assert!(!index.contains(a));
+    assert_eq!(index.len(), slab.len());
+
+    index.for_each_mut(&mut slab, |_, value| *value *= 10);
+    let mut values: Vec<_> = index
+        .iter(&slab)
+        .map(|(slot, &value)| (slot, value))
+        .collect();
+    values.sort_unstable();
+    assert_eq!(values, [(b, 20), (c, 30)]);
+
+    index.clear();
+    assert!(index.is_empty());
+    assert!(!index.contains(b));
+    index.insert(b).unwrap();
+    assert_eq!(index.as_slice(), [b]);
+}
@@ -0,0 +1,248 @@
+//! A directed graph whose nodes and edges are stored in slabs.
+
+use crate::{Error, Slab, Slot, NUL};
+
+/// The handle of a node. It remains valid until the node is removed.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct NodeId(Slot);
+
+impl NodeId {
+    /// Return the slot of the node.
+    pub fn slot(&self) -> Slot {
+        self.0
+    }
+}
+
+/// The handle of an edge. It remains valid until the edge is removed.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EdgeId(Slot);
+
+impl EdgeId {
+    /// Return the slot of the edge.
+    pub fn slot(&self) -> Slot {
+        self.0
+    }
+}
+
+#[derive(Debug)]
+struct Node<N> {
+    data: N,
+    first_out: Slot,
+    first_in: Slot,
+}
+
+#[derive(Debug)]
+struct Edge<E> {
+    data: E,
+    from: Slot,
+    to: Slot,
+    prev_out: Slot,
+    next_out: Slot,
+    prev_in: Slot,
+    next_in: Slot,
+}
+
+/// A directed graph with a fixed capacity for nodes and edges.
+///
+/// Every node keeps intrusive lists of its outgoing and incoming edges,
+/// threaded through the edge slots, so adding and removing an edge is O(1),
+/// and removing a node is O(degree). Handles are stable: removing a node or
+/// an edge never invalidates the handles of other nodes and edges.
+#[derive(Debug)]
+pub struct GraphArena<N, E> {
+    nodes: Slab<Node<N>>,
+    edges: Slab<Edge<E>>,
+}
+
+impl<N, E> GraphArena<N, E> {
+    /// Create a new graph with the given capacities.
+    pub fn with_capacity(node_capacity: usize, edge_capacity: usize) -> Result<Self, Error> {
+        Ok(Self {
+            nodes: Slab::with_capacity(node_capacity)?,
+            edges: Slab::with_capacity(edge_capacity)?,
+        })
+    }
+
+    /// Return the number of nodes.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Return the number of edges.
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Add a node.
+    pub fn add_node(&mut self, data: N) -> Result<NodeId, Error> {
+        let node = Node {
+            data,
+            first_out: NUL,
+            first_in: NUL,
+        };
+        self.nodes.push_front(node).map(NodeId)
+    }
+
+    /// Add an edge between two existing nodes.
+    pub fn add_edge(&mut self, from: NodeId, to: NodeId, data: E) -> Result<EdgeId, Error> {
+        let first_out = self.nodes.get_checked(from.0)?.first_out;
+        let first_in = self.nodes.get_checked(to.0)?.first_in;
+        let edge = Edge {
+            data,
+            from: from.0,
+            to: to.0,
+            prev_out: NUL,
+            next_out: first_out,
+            prev_in: NUL,
+            next_in: first_in,
+        };
+        let slot = self.edges.push_front(edge)?;
+        if first_out != NUL {
+            unsafe { self.edges.get_unchecked_mut(first_out) }.prev_out = slot;
+        }
+        if first_in != NUL {
+            unsafe { self.edges.get_unchecked_mut(first_in) }.prev_in = slot;
+        }
+        unsafe { self.nodes.get_unchecked_mut(from.0) }.first_out = slot;
+        unsafe { self.nodes.get_unchecked_mut(to.0) }.first_in = slot;
+        Ok(EdgeId(slot))
+    }
+
+    /// Return the data of a node.
+    pub fn node(&self, id: NodeId) -> Result<&N, Error> {
+        self.nodes.get_checked(id.0).map(|node| &node.data)
+    }
+
+    /// Return a mutable reference to the data of a node.
+    pub fn node_mut(&mut self, id: NodeId) -> Result<&mut N, Error> {
+        self.nodes.get_mut_checked(id.0).map(|node| &mut node.data)
+    }
+
+    /// Return the data of an edge.
+    pub fn edge(&self, id: EdgeId) -> Result<&E, Error> {
+        self.edges.get_checked(id.0).map(|edge| &edge.data)
+    }
+
+    /// Return a mutable reference to the data of an edge.
+    pub fn edge_mut(&mut self, id: EdgeId) -> Result<&mut E, Error> {
+        self.edges.get_mut_checked(id.0).map(|edge| &mut edge.data)
+    }
+
+    /// Return the source and target nodes of an edge.
+    pub fn edge_endpoints(&self, id: EdgeId) -> Result<(NodeId, NodeId), Error> {
+        self.edges
+            .get(id.0)
+            .map(|edge| (NodeId(edge.from), NodeId(edge.to)))
+    }
+
+    /// Remove an edge, and return its data.
+    pub fn remove_edge(&mut self, id: EdgeId) -> Result<E, Error> {
+        let edge = self.edges.take_checked(id.0)?;
+        if edge.prev_out != NUL {
+            unsafe { self.edges.get_unchecked_mut(edge.prev_out) }.next_out = edge.next_out;
+        } else {
+            unsafe { self.nodes.get_unchecked_mut(edge.from) }.first_out = edge.next_out;
+        }
+        if edge.next_out != NUL {
+            unsafe { self.edges.get_unchecked_mut(edge.next_out) }.prev_out = edge.prev_out;
+        }
+        if edge.prev_in != NUL {
+            unsafe { self.edges.get_unchecked_mut(edge.prev_in) }.next_in = edge.next_in;
+        } else {
+            unsafe { self.nodes.get_unchecked_mut(edge.to) }.first_in = edge.next_in;
+        }
+        if edge.next_in != NUL {
+            unsafe { self.edges.get_unchecked_mut(edge.next_in) }.prev_in = edge.prev_in;
+        }
+        Ok(edge.data)
+    }
+
+    /// Remove a node along with all its edges, and return its data.
+    pub fn remove_node(&mut self, id: NodeId) -> Result<N, Error> {
+        self.nodes.get_checked(id.0)?;
+        loop {
+            let node = unsafe { self.nodes.get_unchecked(id.0) };
+            let edge = if node.first_out != NUL {
+                node.first_out
+            } else if node.first_in != NUL {
+                node.first_in
+            } else {
+                break;
+            };
+            self.remove_edge(EdgeId(edge))?;
+        }
+        self.nodes.take_checked(id.0).map(|node| node.data)
+    }
+
+    /// Iterate over the outgoing edges of a node, as `(edge, target, &data)`
+    /// tuples, from the most recently added edge to the oldest one.
+    pub fn outgoing(&self, id: NodeId) -> impl Iterator<Item = (EdgeId, NodeId, &E)> + '_ {
+        let mut slot = self
+            .nodes
+            .get_checked(id.0)
+            .map_or(NUL, |node| node.first_out);
+        core::iter::from_fn(move || {
+            if slot == NUL {
+                return None;
+            }
+            let edge = unsafe { self.edges.get_unchecked(slot) };
+            let item = (EdgeId(slot), NodeId(edge.to), &edge.data);
+            slot = edge.next_out;
+            Some(item)
+        })
+    }
+
+    /// Iterate over the incoming edges of a node, as `(edge, source, &data)`
+    /// tuples, from the most recently added edge to the oldest one.
+    pub fn incoming(&self, id: NodeId) -> impl Iterator<Item = (EdgeId, NodeId, &E)> + '_ {
+        let mut slot = self
+            .nodes
+            .get_checked(id.0)
+            .map_or(NUL, |node| node.first_in);
+        core::iter::from_fn(move || {
+            if slot == NUL {
+                return None;
+            }
+            let edge = unsafe { self.edges.get_unchecked(slot) };
+            let item = (EdgeId(slot), NodeId(edge.from), &edge.data);
+            slot = edge.next_in;
+            Some(item)
+        })
+    }
+}
+
+#[test]
+fn test_graph() {
+    let mut graph = GraphArena::with_capacity(3, 4).unwrap();
+    let a = graph.add_node("a").unwrap();
+    let b = graph.add_node("b").unwrap();
+    let c = graph.add_node("c").unwrap();
+    let ab = graph.add_edge(a, b, 1).unwrap();
+    let ac = graph.add_edge(a, c, 2).unwrap();
+    let bc = graph.add_edge(b, c, 3).unwrap();
+    let ca = graph.add_edge(c, a, 4).unwrap();
+    assert_eq!(graph.add_edge(b, a, 5), Err(Error::Full));
+
+    let targets = |graph: &GraphArena<&str, i32>, id| {
+        graph.outgoing(id).map(|(_, to, _)| to).collect::<Vec<_>>()
+    };
+    assert_eq!(targets(&graph, a), [c, b]);
+    assert_eq!(graph.edge_endpoints(bc), Ok((b, c)));
+
+    assert_eq!(graph.remove_edge(ac), Ok(2));
+    assert_eq!(targets(&graph, a), [b]);
+    assert_eq!(
+        graph.incoming(c).map(|(e, _, _)| e).collect::<Vec<_>>(),
+        [bc]
+    );
+    assert!(graph.remove_edge(ac).is_err());
+
+    assert_eq!(graph.remove_node(b), Ok("b"));
+    assert_eq!(graph.edge_count(), 1);
+    assert!(graph.edge(ab).is_err());
+    assert_eq!(graph.edge(ca), Ok(&4));
+    assert_eq!(targets(&graph, a), []);
+    assert_eq!(graph.incoming(c).count(), 0);
+    *graph.node_mut(c).unwrap() = "C";
+    assert_eq!(graph.node(c), Ok(&"C"));
+}
@@ -0,0 +1,266 @@
+//! A fixed-capacity priority queue with stable handles.
+
+use alloc::vec::Vec;
+
+use crate::{Error, Slab, Slot, SlotType, NUL};
+
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    child: Slot,
+    next: Slot,
+    // Parent for the first child, previous sibling otherwise.
+    prev: Slot,
+}
+
+const DETACHED: Node = Node {
+    child: NUL,
+    next: NUL,
+    prev: NUL,
+};
+
+/// A min-heap (pairing heap) storing its elements in a slab.
+///
+/// `push()` returns a slot that remains valid until the element is removed,
+/// and that can be used to change the element's key or to remove it.
+///
+/// - `push()`, `peek()` and decreasing a key are O(1).
+/// - `pop()`, `remove()` and increasing a key are O(log n) amortized.
+///
+/// Use `core::cmp::Reverse` to get a max-heap.
+#[derive(Debug)]
+pub struct SlabHeap<D: Ord> {
+    slab: Slab<D>,
+    nodes: Vec<Node>,
+    root: Slot,
+}
+
+impl<D: Ord> SlabHeap<D> {
+    /// Create a new heap with the given capacity.
+    pub fn with_capacity(capacity: usize) -> Result<Self, Error> {
+        Ok(Self {
+            slab: Slab::with_capacity(capacity)?,
+            nodes: alloc::vec![DETACHED; capacity],
+            root: NUL,
+        })
+    }
+
+    /// Return the capacity of the heap.
+    pub fn capacity(&self) -> usize {
+        self.slab.capacity()
+    }
+
+    /// Return the number of elements in the heap.
+    pub fn len(&self) -> usize {
+        self.slab.len()
+    }
+
+    /// Return true if the heap is empty.
+    pub fn is_empty(&self) -> bool {
+        self.slab.is_empty()
+    }
+
+    /// Return true if the heap is full.
+    pub fn is_full(&self) -> bool {
+        self.slab.is_full()
+    }
+
+    /// Add an element to the heap, and return its slot.
+    pub fn push(&mut self, value: D) -> Result<Slot, Error> {
+        let slot = self.slab.push_front(value)?;
+        self.nodes[slot.to_usize()] = DETACHED;
+        self.root = self.meld(self.root, slot);
+        Ok(slot)
+    }
+
+    /// Return the smallest element.
+    pub fn peek(&self) -> Option<&D> {
+        self.peek_slot()
+            .map(|slot| unsafe { self.slab.get_unchecked(slot) })
+    }
+
+    /// Return the slot of the smallest element.
+    pub fn peek_slot(&self) -> Option<Slot> {
+        Some(self.root).filter(|&slot| slot != NUL)
+    }
+
+    /// Remove and return the smallest element.
+    pub fn pop(&mut self) -> Option<D> {
+        let slot = self.peek_slot()?;
+        self.remove(slot).ok()
+    }
+
+    /// Return an element given its slot number.
+    pub fn get(&self, slot: Slot) -> Result<&D, Error> {
+        self.slab.get_checked(slot)
+    }
+
+    /// Check if the slot contains an element.
+    pub fn contains_slot(&self, slot: Slot) -> bool {
+        self.slab.contains_slot(slot)
+    }
+
+    /// Remove an element given its slot, and return it.
+    pub fn remove(&mut self, slot: Slot) -> Result<D, Error> {
+        if !self.slab.contains_slot(slot) {
+            return Err(Error::InvalidSlot);
+        }
+        self.detach(slot);
+        self.slab.take_checked(slot)
+    }
+
+    /// Replace an element with a new value, and return the previous one.
+    /// The slot doesn't change. This is O(1) if the new value is not larger
+    /// than the previous one.
+    pub fn update_key(&mut self, slot: Slot, value: D) -> Result<D, Error> {
+        let current = self.slab.get_mut_checked(slot)?;
+        let decreased = value <= *current;
+        let previous = core::mem::replace(current, value);
+        if !decreased {
+            self.detach(slot);
+        } else if slot != self.root {
+            self.cut(slot);
+        } else {
+            return Ok(previous);
+        }
+        self.root = self.meld(self.root, slot);
+        Ok(previous)
+    }
+
+    /// Iterate over the elements, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = &D> + '_ {
+        self.slab.iter()
+    }
+
+    // Link two detached trees, and return the root of the resulting tree.
+    fn meld(&mut self, a: Slot, b: Slot) -> Slot {
+        if a == NUL {
+            return b;
+        }
+        if b == NUL {
+            return a;
+        }
+        let (parent, child) = if unsafe { self.slab.get_unchecked(b) < self.slab.get_unchecked(a) }
+        {
+            (b, a)
+        } else {
+            (a, b)
+        };
+        let first = self.nodes[parent.to_usize()].child;
+        self.nodes[child.to_usize()].next = first;
+        self.nodes[child.to_usize()].prev = parent;
+        if first != NUL {
+            self.nodes[first.to_usize()].prev = child;
+        }
+        self.nodes[parent.to_usize()].child = child;
+        parent
+    }
+
+    // Detach a non-root node, along with its subtree.
+    fn cut(&mut self, slot: Slot) {
+        let Node { prev, next, .. } = self.nodes[slot.to_usize()];
+        invariant_ne!(prev, NUL);
+        if self.nodes[prev.to_usize()].child == slot {
+            self.nodes[prev.to_usize()].child = next;
+        } else {
+            self.nodes[prev.to_usize()].next = next;
+        }
+        if next != NUL {
+            self.nodes[next.to_usize()].prev = prev;
+        }
+        self.nodes[slot.to_usize()].prev = NUL;
+        self.nodes[slot.to_usize()].next = NUL;
+    }
+
+    // Remove a node from the heap, keeping its children in the heap.
+    fn detach(&mut self, slot: Slot) {
+        if slot == self.root {
+            self.root = NUL;
+        } else {
+            self.cut(slot);
+        }
+        let child = self.nodes[slot.to_usize()].child;
+        self.nodes[slot.to_usize()] = DETACHED;
+        let merged = self.merge_pairs(child);
+        self.root = self.meld(self.root, merged);
+    }
+
+    // Two-pass pairing of a list of siblings.
+    fn merge_pairs(&mut self, mut first: Slot) -> Slot {
+        // First pass: meld pairs from left to right, stacking the results.
+        let mut stack = NUL;
+        while first != NUL {
+            let a = first;
+            let b = self.nodes[a.to_usize()].next;
+            first = if b != NUL {
+                self.nodes[b.to_usize()].next
+            } else {
+                NUL
+            };
+            for slot in [a, b] {
+                if slot != NUL {
+                    self.nodes[slot.to_usize()].prev = NUL;
+                    self.nodes[slot.to_usize()].next = NUL;
+                }
+            }
+            let melded = self.meld(a, b);
+            self.nodes[melded.to_usize()].next = stack;
+            stack = melded;
+        }
+        // Second pass: meld the stacked trees from right to left.
+        let mut root = NUL;
+        while stack != NUL {
+            let tree = stack;
+            stack = self.nodes[tree.to_usize()].next;
+            self.nodes[tree.to_usize()].next = NUL;
+            root = self.meld(root, tree);
+        }
+        root
+    }
+}
+
+#[test]
+fn test_heap() {
+    use rand::prelude::*;
+
+    let mut rng = rand::thread_rng();
+    let capacity = 64;
+    let mut heap = SlabHeap::with_capacity(capacity).unwrap();
+    let mut model: Vec<(Slot, u32)> = Vec::new();
+    for _ in 0..100_000 {
+        match rng.gen_range(0..4) {
+            0 => {
+                let value = rng.gen_range(0..1000);
+                match heap.push(value) {
+                    Ok(slot) => model.push((slot, value)),
+                    Err(_) => assert_eq!(model.len(), capacity),
+                }
+            }
+            1 => {
+                let min = model.iter().map(|&(_, value)| value).min();
+                if let Some(slot) = heap.peek_slot() {
+                    let idx = model.iter().position(|&(s, _)| s == slot).unwrap();
+                    model.swap_remove(idx);
+                }
+                assert_eq!(heap.pop(), min);
+            }
+            2 if !model.is_empty() => {
+                let idx = rng.gen_range(0..model.len());
+                let (slot, value) = model.swap_remove(idx);
+                assert_eq!(heap.remove(slot), Ok(value));
+            }
+            3 if !model.is_empty() => {
+                let idx = rng.gen_range(0..model.len());
+                let value = rng.gen_range(0..1000);
+                let (slot, previous) = model[idx];
+                assert_eq!(heap.update_key(slot, value), Ok(previous));
+                model[idx].1 = value;
+            }
+            _ => {}
+        }
+        assert_eq!(heap.len(), model.len());
+        assert_eq!(
+            heap.peek().copied(),
+            model.iter().map(|&(_, value)| value).min()
+        );
+    }
+}
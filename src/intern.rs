@@ -0,0 +1,148 @@
+//! An interner for short byte strings, backed by the slab.
+
+use core::hash::{BuildHasher, Hash, Hasher};
+
+use crate::{
+    map::{DefaultHashBuilder, SlabMap},
+    Error, Slot,
+};
+
+/// A byte string stored inline, up to `MAX_LEN` bytes.
+#[derive(Clone, Copy)]
+struct InlineBytes<const MAX_LEN: usize> {
+    len: usize,
+    bytes: [u8; MAX_LEN],
+}
+
+impl<const MAX_LEN: usize> InlineBytes<MAX_LEN> {
+    fn new(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() > MAX_LEN {
+            return Err(Error::TooLarge);
+        }
+        let mut inline = Self {
+            len: bytes.len(),
+            bytes: [0; MAX_LEN],
+        };
+        inline.bytes[..bytes.len()].copy_from_slice(bytes);
+        Ok(inline)
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+}
+
+impl<const MAX_LEN: usize> PartialEq for InlineBytes<MAX_LEN> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
+impl<const MAX_LEN: usize> Eq for InlineBytes<MAX_LEN> {}
+
+impl<const MAX_LEN: usize> Hash for InlineBytes<MAX_LEN> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_bytes().hash(state)
+    }
+}
+
+impl<const MAX_LEN: usize> core::fmt::Debug for InlineBytes<MAX_LEN> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        self.as_bytes().fmt(f)
+    }
+}
+
+/// A table of deduplicated byte strings, each up to `MAX_LEN` bytes long.
+///
+/// Strings are copied into pre-allocated slots, so interning never
+/// allocates. Interning the same string twice returns the same slot.
+/// Resolving a slot back to its string is O(1).
+#[derive(Debug)]
+pub struct Interner<const MAX_LEN: usize, S = DefaultHashBuilder> {
+    map: SlabMap<InlineBytes<MAX_LEN>, (), S>,
+}
+
+impl<const MAX_LEN: usize> Interner<MAX_LEN, DefaultHashBuilder> {
+    /// Create a new interner that can store up to `capacity` strings.
+    pub fn with_capacity(capacity: usize) -> Result<Self, Error> {
+        Self::with_capacity_and_hasher(capacity, DefaultHashBuilder::default())
+    }
+}
+
+impl<const MAX_LEN: usize, S: BuildHasher> Interner<MAX_LEN, S> {
+    /// Create a new interner that can store up to `capacity` strings,
+    /// using the given hasher for deduplication.
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Result<Self, Error> {
+        Ok(Self {
+            map: SlabMap::with_capacity_and_hasher(capacity, hash_builder)?,
+        })
+    }
+
+    /// Return the maximum number of strings.
+    pub fn capacity(&self) -> usize {
+        self.map.capacity()
+    }
+
+    /// Return the number of stored strings.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Return true if no strings are stored.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Store a string if it is not already present, and return its slot.
+    /// Return `Error::TooLarge` if the string is longer than `MAX_LEN`, and
+    /// `Error::Full` if a new string can't be stored.
+    pub fn intern(&mut self, bytes: &[u8]) -> Result<Slot, Error> {
+        let key = InlineBytes::new(bytes)?;
+        if let Some(slot) = self.map.get_slot(&key) {
+            return Ok(slot);
+        }
+        self.map.insert_full(key, ()).map(|(slot, _)| slot)
+    }
+
+    /// Return the slot of a string, if it has been interned.
+    pub fn lookup(&self, bytes: &[u8]) -> Option<Slot> {
+        self.map.get_slot(&InlineBytes::new(bytes).ok()?)
+    }
+
+    /// Return the string stored in a slot.
+    pub fn resolve(&self, slot: Slot) -> Result<&[u8], Error> {
+        self.map.get_by_slot(slot).map(|(key, _)| key.as_bytes())
+    }
+
+    /// Remove a string given its slot.
+    pub fn remove(&mut self, slot: Slot) -> Result<(), Error> {
+        self.map.remove_by_slot(slot).map(drop)
+    }
+
+    /// Iterate over the strings, from the oldest to the most recently
+    /// interned one.
+    pub fn iter(&self) -> impl Iterator<Item = &[u8]> + '_ {
+        self.map.keys().map(InlineBytes::as_bytes)
+    }
+}
+
+#[test]
+fn test_interner() {
+    let mut interner = Interner::<16>::with_capacity(2).unwrap();
+    let a = interner.intern(b"example.com").unwrap();
+    assert_eq!(interner.intern(b"example.com"), Ok(a));
+    let b = interner.intern(b"example.org").unwrap();
+    assert_ne!(a, b);
+    assert_eq!(interner.len(), 2);
+    assert_eq!(interner.intern(b"example.net"), Err(Error::Full));
+    assert_eq!(
+        interner.intern(b"a-very-long-name.example.com"),
+        Err(Error::TooLarge)
+    );
+    assert_eq!(interner.resolve(b), Ok(&b"example.org"[..]));
+    assert_eq!(interner.lookup(b"example.com"), Some(a));
+    interner.remove(a).unwrap();
+    assert_eq!(interner.lookup(b"example.com"), None);
+    assert!(interner.resolve(a).is_err());
+    assert_eq!(interner.iter().collect::<Vec<_>>(), [&b"example.org"[..]]);
+}
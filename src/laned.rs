@@ -0,0 +1,127 @@
+//! Priority lanes sharing a single pool of elements.
+
+use crate::{multilist::MultiListSlab, Error, Slot};
+
+/// A set of `N` FIFO queues ("lanes") sharing a single fixed-capacity pool.
+///
+/// Lane `0` has the highest priority, lane `N - 1` the lowest. Elements are
+/// added to the front of a lane, and `pop_back_any()` returns the oldest
+/// element of the highest priority non-empty lane.
+///
+/// Lane identifiers are in the `0..N` range; methods panic if they are
+/// given an identifier outside of that range.
+#[derive(Debug)]
+pub struct LanedSlab<D, const N: usize> {
+    lists: MultiListSlab<D, N>,
+}
+
+impl<D, const N: usize> LanedSlab<D, N> {
+    /// Create a new set of lanes, sharing the given capacity.
+    pub fn with_capacity(capacity: usize) -> Result<Self, Error> {
+        Ok(Self {
+            lists: MultiListSlab::with_capacity(capacity)?,
+        })
+    }
+
+    /// Return the total capacity.
+    pub fn capacity(&self) -> usize {
+        self.lists.capacity()
+    }
+
+    /// Return the total number of elements, across all lanes.
+    pub fn len(&self) -> usize {
+        self.lists.len()
+    }
+
+    /// Return true if all the lanes are empty.
+    pub fn is_empty(&self) -> bool {
+        self.lists.is_empty()
+    }
+
+    /// Return true if no more elements can be added to any lane.
+    pub fn is_full(&self) -> bool {
+        self.lists.is_full()
+    }
+
+    /// Return the number of elements in the given lane.
+    pub fn len_of(&self, lane: usize) -> usize {
+        self.lists.len_of(lane)
+    }
+
+    /// Prepend an element to the beginning of the given lane.
+    pub fn push_front(&mut self, lane: usize, value: D) -> Result<Slot, Error> {
+        assert!(lane < N, "invalid lane");
+        let slot = self.lists.insert(value)?;
+        self.lists.push_front_in(lane, slot)?;
+        Ok(slot)
+    }
+
+    /// Remove and return the tail element of the given lane.
+    pub fn pop_back(&mut self, lane: usize) -> Option<D> {
+        let slot = self.lists.back_of(lane)?;
+        self.lists.remove(slot).ok()
+    }
+
+    /// Remove and return the tail element of the highest priority
+    /// non-empty lane, along with that lane.
+    pub fn pop_back_any(&mut self) -> Option<(usize, D)> {
+        (0..N).find_map(|lane| self.pop_back(lane).map(|value| (lane, value)))
+    }
+
+    /// Return an element given its slot number.
+    pub fn get(&self, slot: Slot) -> Result<&D, Error> {
+        self.lists.get(slot)
+    }
+
+    /// Return a mutable reference to an element given its slot number.
+    pub fn get_mut(&mut self, slot: Slot) -> Result<&mut D, Error> {
+        self.lists.get_mut(slot)
+    }
+
+    /// Remove an element given its slot, and return it.
+    pub fn remove(&mut self, slot: Slot) -> Result<D, Error> {
+        self.lists.remove(slot)
+    }
+
+    /// Return the lane an element belongs to.
+    pub fn lane_of(&self, slot: Slot) -> Result<usize, Error> {
+        (0..N)
+            .find(|&lane| self.lists.contains_in(lane, slot))
+            .ok_or(Error::InvalidSlot)
+    }
+
+    /// Move an element to the beginning of another lane.
+    /// Its slot doesn't change.
+    pub fn move_to_lane(&mut self, slot: Slot, lane: usize) -> Result<(), Error> {
+        assert!(lane < N, "invalid lane");
+        let current = self.lane_of(slot)?;
+        self.lists.remove_from(current, slot)?;
+        self.lists.push_front_in(lane, slot)
+    }
+
+    /// Iterate over the `(slot, &element)` pairs of the given lane,
+    /// from the newest to the oldest element.
+    pub fn iter_lane(&self, lane: usize) -> impl Iterator<Item = (Slot, &D)> + '_ {
+        self.lists.iter_in(lane)
+    }
+}
+
+#[test]
+fn test_laned() {
+    let mut lanes = LanedSlab::<u32, 3>::with_capacity(4).unwrap();
+    lanes.push_front(2, 20).unwrap();
+    lanes.push_front(2, 21).unwrap();
+    let a = lanes.push_front(1, 10).unwrap();
+    lanes.push_front(0, 0).unwrap();
+    assert!(lanes.push_front(0, 1).is_err());
+    assert_eq!(lanes.len_of(2), 2);
+
+    assert_eq!(lanes.pop_back_any(), Some((0, 0)));
+    lanes.move_to_lane(a, 2).unwrap();
+    assert_eq!(lanes.lane_of(a), Ok(2));
+    assert_eq!(lanes.pop_back_any(), Some((2, 20)));
+    assert_eq!(lanes.pop_back_any(), Some((2, 21)));
+    assert_eq!(lanes.pop_back_any(), Some((2, 10)));
+    assert_eq!(lanes.pop_back_any(), None);
+    assert!(lanes.is_empty());
+}
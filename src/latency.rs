@@ -0,0 +1,245 @@
+//! Time-in-queue statistics, for lists used as FIFO queues.
+//!
+//! With the `latency_stats` feature, every element is timestamped when it
+//! is added, and the time it spent in the list is recorded when it is
+//! popped from the tail, by `pop_back()`, `drain_back()` and the methods
+//! built on them. Elements removed given their slot are not counted, since
+//! they didn't go through the queue. The elements of a list reassembled
+//! with `from_raw_parts()` are considered added at that time.
+//!
+//! Durations are recorded in a log-linear histogram: values are grouped by
+//! power of two, and every power of two is split into 8 buckets, so that
+//! quantiles are estimated within about 6%, using a fixed amount of memory
+//! allocated along with the list. Recording a duration is O(1), and
+//! computing a snapshot is proportional to the number of buckets.
+
+use alloc::boxed::Box;
+use std::time::{Duration, Instant};
+
+use crate::{Error, Slab, SlotType};
+
+const SUB_BITS: u32 = 3;
+const SUB_BUCKETS: usize = 1 << SUB_BITS;
+// Values below `SUB_BUCKETS` get a bucket each, and every larger power of
+// two is split into `SUB_BUCKETS` buckets.
+const BUCKETS: usize = (64 - SUB_BITS as usize + 1) * SUB_BUCKETS;
+
+fn bucket(nanos: u64) -> usize {
+    if nanos < SUB_BUCKETS as u64 {
+        return nanos as usize;
+    }
+    let shift = 63 - nanos.leading_zeros() - SUB_BITS;
+    (shift as usize + 1) * SUB_BUCKETS + (nanos >> shift) as usize - SUB_BUCKETS
+}
+
+// Return the smallest and the largest value of a bucket.
+fn bucket_range(index: usize) -> (u64, u64) {
+    if index < SUB_BUCKETS {
+        return (index as u64, index as u64);
+    }
+    let shift = index / SUB_BUCKETS - 1;
+    let mantissa = (index % SUB_BUCKETS + SUB_BUCKETS) as u64;
+    let low = mantissa << shift;
+    (low, low + ((1 << shift) - 1))
+}
+
+/// Statistics about the time elements spent in a list, returned by
+/// `Slab::latency_snapshot()`.
+///
+/// Quantiles are estimates, within the bucket of the actual value. All the
+/// fields are zero if no elements have been popped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LatencySnapshot {
+    /// Number of elements popped.
+    pub count: u64,
+    /// Shortest time spent in the list.
+    pub min: Duration,
+    /// Longest time spent in the list.
+    pub max: Duration,
+    /// Average time spent in the list.
+    pub mean: Duration,
+    /// Median time spent in the list.
+    pub p50: Duration,
+    /// 90th percentile.
+    pub p90: Duration,
+    /// 99th percentile.
+    pub p99: Duration,
+    /// 99.9th percentile.
+    pub p999: Duration,
+}
+
+pub(crate) struct LatencyStats {
+    epoch: Instant,
+    // The time every element was added, in nanoseconds since `epoch`.
+    pushed_at: Box<[u64]>,
+    buckets: Box<[u64]>,
+    count: u64,
+    sum: u128,
+    min: u64,
+    max: u64,
+}
+
+impl LatencyStats {
+    pub(crate) fn with_capacity(capacity: usize) -> Result<Self, Error> {
+        let mut pushed_at = crate::try_vec(capacity)?;
+        pushed_at.resize(capacity, 0);
+        let mut buckets = crate::try_vec(BUCKETS)?;
+        buckets.resize(BUCKETS, 0);
+        Ok(LatencyStats {
+            epoch: Instant::now(),
+            pushed_at: pushed_at.into_boxed_slice(),
+            buckets: buckets.into_boxed_slice(),
+            count: 0,
+            sum: 0,
+            min: u64::MAX,
+            max: 0,
+        })
+    }
+
+    #[inline]
+    pub(crate) fn now(&self) -> u64 {
+        self.epoch.elapsed().as_nanos() as u64
+    }
+
+    #[inline]
+    pub(crate) fn record_push<S: SlotType>(&mut self, slot: S) {
+        self.pushed_at[slot.to_usize()] = self.now();
+    }
+
+    #[inline]
+    pub(crate) fn record_pop<S: SlotType>(&mut self, slot: S, now: u64) {
+        let nanos = now.saturating_sub(self.pushed_at[slot.to_usize()]);
+        self.buckets[bucket(nanos)] += 1;
+        self.count += 1;
+        self.sum += nanos as u128;
+        self.min = self.min.min(nanos);
+        self.max = self.max.max(nanos);
+    }
+
+    fn reset(&mut self) {
+        self.buckets.fill(0);
+        self.count = 0;
+        self.sum = 0;
+        self.min = u64::MAX;
+        self.max = 0;
+    }
+
+    // Return the smallest recorded value such that at least
+    // `per_mille / 1000` of the values are lower or equal, estimated as the
+    // middle of its bucket.
+    fn quantile(&self, per_mille: u64) -> u64 {
+        let rank = (self.count as u128 * per_mille as u128).div_ceil(1000) as u64;
+        let rank = rank.clamp(1, self.count);
+        let mut seen = 0;
+        for (index, &count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let (low, high) = bucket_range(index);
+                return (low + (high - low) / 2).clamp(self.min, self.max);
+            }
+        }
+        self.max
+    }
+
+    fn snapshot(&self) -> LatencySnapshot {
+        if self.count == 0 {
+            return LatencySnapshot::default();
+        }
+        LatencySnapshot {
+            count: self.count,
+            min: Duration::from_nanos(self.min),
+            max: Duration::from_nanos(self.max),
+            mean: Duration::from_nanos((self.sum / self.count as u128) as u64),
+            p50: Duration::from_nanos(self.quantile(500)),
+            p90: Duration::from_nanos(self.quantile(900)),
+            p99: Duration::from_nanos(self.quantile(990)),
+            p999: Duration::from_nanos(self.quantile(999)),
+        }
+    }
+}
+
+impl core::fmt::Debug for LatencyStats {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.snapshot().fmt(f)
+    }
+}
+
+impl<D, S: SlotType> Slab<D, S> {
+    /// Return statistics about the time the elements popped from the tail
+    /// of the list spent in it, since the list was created or the
+    /// statistics were reset.
+    pub fn latency_snapshot(&self) -> LatencySnapshot {
+        self.latency.snapshot()
+    }
+
+    /// Forget the recorded durations, for example after taking a snapshot,
+    /// so that the next one only covers the elements popped since. The
+    /// timestamps of the elements currently in the list are kept.
+    pub fn reset_latency_stats(&mut self) {
+        self.latency.reset();
+    }
+}
+
+#[test]
+fn test_latency_buckets() {
+    let mut last = None;
+    for nanos in (0..100_000).chain([u64::MAX / 3, u64::MAX - 1, u64::MAX]) {
+        let index = bucket(nanos);
+        assert!(index < BUCKETS);
+        let (low, high) = bucket_range(index);
+        assert!(low <= nanos && nanos <= high);
+        // Buckets are at most 1/8th of their lowest value wide.
+        assert!(high - low <= low / SUB_BUCKETS as u64);
+        if let Some(last) = last {
+            assert!(index == last || index == last + 1 || nanos > 100_000);
+        }
+        last = Some(index);
+    }
+    assert_eq!(bucket(u64::MAX), BUCKETS - 1);
+}
+
+#[test]
+fn test_latency_snapshot() {
+    let mut slab = Slab::with_capacity(1000).unwrap();
+    assert_eq!(slab.latency_snapshot(), LatencySnapshot::default());
+
+    // Record known durations, with fixed timestamps.
+    for i in 1..=1000u64 {
+        let slot = slab.push_front(i).unwrap();
+        slab.latency.pushed_at[slot.to_usize()] = 0;
+        let now = i * 1000;
+        slab.latency.record_pop(slot, now);
+        slab.remove(slot).unwrap();
+    }
+    let snapshot = slab.latency_snapshot();
+    assert_eq!(snapshot.count, 1000);
+    assert_eq!(snapshot.min, Duration::from_micros(1));
+    assert_eq!(snapshot.max, Duration::from_micros(1000));
+    assert_eq!(snapshot.mean, Duration::from_nanos(500_500));
+    for (estimate, actual) in [
+        (snapshot.p50, 500_000),
+        (snapshot.p90, 900_000),
+        (snapshot.p99, 990_000),
+        (snapshot.p999, 999_000),
+    ] {
+        let estimate = estimate.as_nanos() as u64;
+        assert!(
+            estimate.abs_diff(actual) <= actual / 16,
+            "{estimate} {actual}"
+        );
+    }
+
+    // Only pops from the tail are recorded.
+    slab.reset_latency_stats();
+    assert_eq!(slab.latency_snapshot().count, 0);
+    let a = slab.push_front(1).unwrap();
+    slab.push_front(2).unwrap();
+    slab.push_front(3).unwrap();
+    slab.push_front(4).unwrap();
+    slab.remove(a).unwrap();
+    slab.pop_back();
+    slab.drain_back(2).for_each(drop);
+    let snapshot = slab.latency_snapshot();
+    assert_eq!(snapshot.count, 3);
+    assert!(snapshot.min <= snapshot.p50 && snapshot.p50 <= snapshot.max);
+}
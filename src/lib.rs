@@ -1,8 +1,99 @@
-use std::{iter::Iterator, mem::MaybeUninit};
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
 
-#[cfg(feature = "slot_u32")]
+extern crate alloc;
+
+use alloc::{boxed::Box, vec::Vec};
+use core::{cmp::Ordering, iter::Iterator, mem::MaybeUninit, pin::Pin};
+
+// Check an internal invariant in debug builds, and also in release builds
+// with the `strict` feature.
+macro_rules! invariant {
+    ($($arg:tt)*) => {
+        if cfg!(any(debug_assertions, feature = "strict")) {
+            assert!($($arg)*);
+        }
+    };
+}
+
+macro_rules! invariant_eq {
+    ($($arg:tt)*) => {
+        if cfg!(any(debug_assertions, feature = "strict")) {
+            assert_eq!($($arg)*);
+        }
+    };
+}
+
+macro_rules! invariant_ne {
+    ($($arg:tt)*) => {
+        if cfg!(any(debug_assertions, feature = "strict")) {
+            assert_ne!($($arg)*);
+        }
+    };
+}
+
+#[cfg(feature = "allocator_api")]
+pub mod allocator;
+pub mod boxed;
+#[cfg(feature = "changelog")]
+pub mod changelog;
+pub mod codec;
+pub mod compat;
+pub mod dedup;
+pub mod dense;
+pub mod graph;
+pub mod heap;
+pub mod intern;
+pub mod laned;
+#[cfg(feature = "latency_stats")]
+pub mod latency;
+pub mod map;
+pub mod meta;
+#[cfg(test)]
+mod model;
+pub mod multilist;
+pub mod occupancy;
+#[cfg(feature = "generational")]
+pub mod packed;
+#[cfg(any(
+    feature = "generational",
+    not(any(feature = "releasefast", feature = "tagged"))
+))]
+mod padded;
+#[cfg(feature = "std")]
+pub mod pool;
+#[cfg(all(kani, not(feature = "releasefast")))]
+mod proofs;
+pub mod ratelimit;
+pub mod raw;
+pub mod region;
+pub mod session;
+pub mod set;
+pub mod shared;
+#[cfg(feature = "shm")]
+pub mod shm;
+pub mod slot;
+pub mod small;
+#[cfg(feature = "async")]
+pub mod stream;
+pub mod traits;
+pub mod wakers;
+
+pub use slot::SlotType;
+
+#[cfg(all(feature = "slot_u32", feature = "slot_u64"))]
+compile_error!("the `slot_u32` and `slot_u64` features are mutually exclusive");
+#[cfg(all(feature = "slot_u32", feature = "slot_usize"))]
+compile_error!("the `slot_u32` and `slot_usize` features are mutually exclusive");
+#[cfg(all(feature = "slot_u64", feature = "slot_usize"))]
+compile_error!("the `slot_u64` and `slot_usize` features are mutually exclusive");
+
+#[cfg(all(
+    feature = "slot_u32",
+    not(any(feature = "slot_u64", feature = "slot_usize"))
+))]
 type Slot = u32;
-#[cfg(feature = "slot_u64")]
+#[cfg(all(feature = "slot_u64", not(feature = "slot_usize")))]
 type Slot = u64;
 #[cfg(feature = "slot_usize")]
 type Slot = usize;
@@ -11,22 +102,278 @@ type Slot = u32;
 
 const NUL: Slot = Slot::MAX;
 
+// The maximum number of slots passed to the leak hook at once, with `realtime`.
+#[cfg(all(feature = "leak_diagnostics", feature = "realtime"))]
+const LEAK_BATCH: usize = 64;
+
 /// A linked list that doesn't do dynamic allocations.
-#[derive(Debug)]
-pub struct Slab<D: Sized> {
-    vec_next: Vec<Slot>,
-    vec_prev: Vec<Slot>,
-    free_head: Slot,
-    head: Slot,
-    tail: Slot,
-    len: usize,
-    data: Vec<MaybeUninit<D>>,
-    #[cfg(not(feature = "releasefast"))]
-    bitmap: Vec<u8>,
+///
+/// Slots are values of type `S`, `u32` unless a `slot_*` feature selects
+/// another default; see `SlotType`.
+#[cfg_attr(not(feature = "realtime"), derive(Debug))]
+#[repr(C)]
+pub struct Slab<D: Sized, S: SlotType = Slot> {
+    // The fields used by every operation come first, so that they fit in a
+    // single cache line with the default slot type.
+    head: S,
+    tail: S,
+    free_head: S,
+    len: S,
+    vec_next: Box<[S]>,
+    vec_prev: Box<[S]>,
+    data: Box<[MaybeUninit<D>]>,
+    // Validation data is kept away from the links, see `padded`.
+    #[cfg(not(any(feature = "releasefast", feature = "tagged")))]
+    bitmap: padded::PaddedSlice<u8>,
+    #[cfg(feature = "generational")]
+    generations: padded::PaddedSlice<u32>,
+    // Elements removed with `remove_deferred()`, not dropped yet, linked
+    // through `vec_next`.
+    deferred_head: S,
+    deferred_len: S,
+    #[cfg(feature = "leak_diagnostics")]
+    leak_hook: Option<fn(&[S])>,
+    #[cfg(feature = "changelog")]
+    changelog: changelog::ChangeLog<S>,
+    #[cfg(feature = "latency_stats")]
+    latency: latency::LatencyStats,
+}
+
+/// A slot number paired with the generation of the element stored in it.
+///
+/// A slot is recycled once its element is removed, so a bare slot number
+/// may end up referencing a different element. A `VersionedSlot` becomes
+/// stale instead, and is then rejected by the `*_versioned()` methods.
+///
+/// Versioned slots can be sent to another process, for example as request
+/// identifiers, with `to_le_bytes()`, or `to_u64()` for `u32` slots, and
+/// checked on the way back with `from_le_bytes()` or `from_u64()`.
+#[cfg(feature = "generational")]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VersionedSlot<S: SlotType = Slot> {
+    slot: S,
+    generation: u32,
+}
+
+#[cfg(feature = "generational")]
+impl<S: SlotType> VersionedSlot<S> {
+    /// Return the slot number.
+    pub fn slot(&self) -> S {
+        self.slot
+    }
+
+    /// Return the generation.
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    /// Create a weak handle to the element.
+    pub fn downgrade(&self) -> WeakSlot<S> {
+        WeakSlot(*self)
+    }
+
+    /// Encode the versioned slot as the slot number in a little-endian
+    /// `u64`, followed by the generation in a little-endian `u32`.
+    /// The encoding is the same for all slot types.
+    pub fn to_le_bytes(&self) -> [u8; 12] {
+        let mut bytes = [0; 12];
+        bytes[..8].copy_from_slice(&(self.slot.to_usize() as u64).to_le_bytes());
+        bytes[8..].copy_from_slice(&self.generation.to_le_bytes());
+        bytes
+    }
+
+    /// Decode a versioned slot encoded with `to_le_bytes()`.
+    /// Return `Error::InvalidSlot` if the slot number is out of the range
+    /// of the slot type, or if the generation is not the one of an element.
+    /// The slot still has to be checked against the list it is used with.
+    pub fn from_le_bytes(bytes: [u8; 12]) -> Result<Self, Error> {
+        let (slot, generation) = bytes.split_at(8);
+        let slot = u64::from_le_bytes(slot.try_into().unwrap());
+        let generation = u32::from_le_bytes(generation.try_into().unwrap());
+        Self::from_parts(slot, generation)
+    }
+
+    fn from_parts(slot: u64, generation: u32) -> Result<Self, Error> {
+        let slot = usize::try_from(slot)
+            .ok()
+            .filter(|&slot| slot < S::MAX_CAPACITY)
+            .ok_or(Error::InvalidSlot)?;
+        // Generations are odd while a slot is occupied.
+        if generation & 1 == 0 {
+            return Err(Error::InvalidSlot);
+        }
+        Ok(VersionedSlot {
+            slot: S::from_usize(slot),
+            generation,
+        })
+    }
+}
+
+#[cfg(feature = "generational")]
+impl VersionedSlot<u32> {
+    /// Pack the versioned slot into a `u64`, with the generation in the
+    /// upper 32 bits, and the slot number in the lower 32 bits.
+    pub fn to_u64(&self) -> u64 {
+        (u64::from(self.generation) << 32) | u64::from(self.slot)
+    }
+
+    /// Unpack a versioned slot packed with `to_u64()`, with the same checks
+    /// as `from_le_bytes()`.
+    pub fn from_u64(packed: u64) -> Result<Self, Error> {
+        Self::from_parts(packed & 0xffff_ffff, (packed >> 32) as u32)
+    }
+}
+
+/// A handle to an element that may have been removed since the handle was
+/// created.
+///
+/// Unlike a `VersionedSlot`, a `WeakSlot` can't be used to access an element
+/// directly: it must first be upgraded with `Slab::upgrade()`, which returns
+/// `None` once the element is gone.
+#[cfg(feature = "generational")]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WeakSlot<S: SlotType = Slot>(VersionedSlot<S>);
+
+#[cfg(feature = "generational")]
+impl<S: SlotType> From<VersionedSlot<S>> for WeakSlot<S> {
+    fn from(key: VersionedSlot<S>) -> Self {
+        key.downgrade()
+    }
+}
+
+/// A mapping from the slots of a slab to the slots of another slab, as
+/// returned by operations that relocate elements.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SlotRemap<S: SlotType = Slot> {
+    new_slots: Vec<S>,
+}
+
+impl<S: SlotType> SlotRemap<S> {
+    /// Return the new slot of the element that was stored in `old`, or
+    /// `None` if that slot didn't contain an element.
+    pub fn map(&self, old: S) -> Option<S> {
+        self.new_slots
+            .get(old.to_usize())
+            .copied()
+            .filter(|&new| new != S::NUL)
+    }
+
+    /// Return the number of relocated elements.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Return true if no elements were relocated.
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    /// Iterate over the `(old, new)` slot pairs, ordered by old slot.
+    pub fn iter(&self) -> impl Iterator<Item = (S, S)> + '_ {
+        self.new_slots
+            .iter()
+            .enumerate()
+            .filter(|&(_, &new)| new != S::NUL)
+            .map(|(old, &new)| (S::from_usize(old), new))
+    }
+
+    /// Rewrite a slot in place. Return `false`, leaving the slot unchanged,
+    /// if it wasn't relocated.
+    pub fn apply(&self, slot: &mut S) -> bool {
+        match self.map(*slot) {
+            Some(new) => {
+                *slot = new;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// The layout of free and occupied slots in storage order, as returned by
+/// `Slab::fragmentation_report()`.
+///
+/// A run is a maximal sequence of consecutive slots that are all free, or
+/// all occupied.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FragmentationReport {
+    /// Number of free slots.
+    pub free_slots: usize,
+    /// Number of runs of free slots.
+    pub free_runs: usize,
+    /// Length of the longest run of free slots.
+    pub longest_free_run: usize,
+    /// Number of runs of occupied slots.
+    pub occupied_runs: usize,
+    /// Length of the longest run of occupied slots.
+    pub longest_occupied_run: usize,
+    /// One past the highest occupied slot, or 0 if the list is empty.
+    pub high_water_mark: usize,
+}
+
+impl FragmentationReport {
+    /// Return true if the occupied slots are all at the beginning of the
+    /// storage, so that compacting the list wouldn't move anything.
+    pub fn is_compact(&self) -> bool {
+        self.occupied_runs <= 1 && self.longest_occupied_run == self.high_water_mark
+    }
+
+    fn add_run(&mut self, occupied: bool, len: usize) {
+        if len == 0 {
+            return;
+        }
+        if occupied {
+            self.occupied_runs += 1;
+            self.longest_occupied_run = self.longest_occupied_run.max(len);
+        } else {
+            self.free_runs += 1;
+            self.longest_free_run = self.longest_free_run.max(len);
+        }
+    }
+}
+
+/// The progress of an incremental sweep.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Sweep {
+    /// Nothing is left to remove. Contains the number of removed elements.
+    Done(usize),
+    /// The budget was exhausted while elements were still left to remove.
+    /// Contains the number of removed elements.
+    Pending(usize),
+}
+
+impl Sweep {
+    /// Return the number of removed elements.
+    pub fn removed(&self) -> usize {
+        match *self {
+            Sweep::Done(count) | Sweep::Pending(count) => count,
+        }
+    }
+
+    /// Return true if nothing is left to remove.
+    pub fn is_done(&self) -> bool {
+        matches!(self, Sweep::Done(_))
+    }
+}
+
+/// What to do with an element visited by `Slab::for_each_removable()`.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Visit {
+    /// Keep the element in the list.
+    Keep,
+    /// Remove the element from the list, and drop it.
+    Remove,
 }
 
 /// An error.
+///
+/// New variants may be added in minor releases, so matches must include a
+/// wildcard arm.
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum Error {
     /// Too large.
     TooLarge,
@@ -36,65 +383,190 @@ pub enum Error {
     InvalidSlot,
     /// Slab is empty.
     Empty,
+    /// Element is already borrowed.
+    Borrowed,
+    /// Memory allocation failed.
+    OutOfMemory,
+    /// Memory region is not suitably aligned.
+    Misaligned,
 }
 
+impl Error {
+    /// Return true if the error is caused by the capacity of the list:
+    /// `Full`, `TooLarge` or `OutOfMemory`.
+    pub fn is_capacity(&self) -> bool {
+        matches!(self, Error::Full | Error::TooLarge | Error::OutOfMemory)
+    }
+
+    /// Return true if the error reveals a bug in the caller, such as using
+    /// a slot that doesn't contain an element: `InvalidSlot` or
+    /// `Misaligned`.
+    pub fn is_usage_bug(&self) -> bool {
+        matches!(self, Error::InvalidSlot | Error::Misaligned)
+    }
+
+    /// Return true if the same operation may succeed later, once elements
+    /// have been added or removed, or borrows released: `Full`, `Empty` or
+    /// `Borrowed`.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Error::Full | Error::Empty | Error::Borrowed)
+    }
+
+    /// Return true if retrying the same operation will fail again.
+    pub fn is_fatal(&self) -> bool {
+        !self.is_retryable()
+    }
+}
+
+#[cfg(feature = "std")]
 impl std::error::Error for Error {}
 
-impl std::fmt::Display for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+/// `Full`, `Empty` and `Borrowed` map to `WouldBlock`, `InvalidSlot` to
+/// `NotFound`, `TooLarge` and `Misaligned` to `InvalidInput`, and
+/// `OutOfMemory` to `OutOfMemory`.
+#[cfg(feature = "std")]
+impl From<Error> for std::io::ErrorKind {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::Full | Error::Empty | Error::Borrowed => std::io::ErrorKind::WouldBlock,
+            Error::InvalidSlot => std::io::ErrorKind::NotFound,
+            Error::TooLarge | Error::Misaligned => std::io::ErrorKind::InvalidInput,
+            Error::OutOfMemory => std::io::ErrorKind::OutOfMemory,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<Error> for std::io::Error {
+    fn from(err: Error) -> Self {
+        std::io::Error::new(err.clone().into(), err)
+    }
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
         match self {
             Error::TooLarge => write!(f, "Too large"),
             Error::Full => write!(f, "Full"),
             Error::InvalidSlot => write!(f, "Invalid slot"),
             Error::Empty => write!(f, "Empty"),
+            Error::Borrowed => write!(f, "Borrowed"),
+            Error::OutOfMemory => write!(f, "Out of memory"),
+            Error::Misaligned => write!(f, "Misaligned"),
         }
     }
 }
 
+// Allocate an empty vector that can hold `len` elements without
+// reallocating. Return `Error::TooLarge` if its size would overflow, and
+// `Error::OutOfMemory` if the allocation fails, instead of aborting.
+fn try_vec<T>(len: usize) -> Result<Vec<T>, Error> {
+    if core::alloc::Layout::array::<T>(len).is_err() {
+        return Err(Error::TooLarge);
+    }
+    let mut vec = Vec::new();
+    vec.try_reserve_exact(len).map_err(|_| Error::OutOfMemory)?;
+    Ok(vec)
+}
+
 impl<D: Sized> Slab<D> {
-    /// Create a new list with the given capacity.
+    /// Create a new list with the given capacity, using the default slot
+    /// type.
     pub fn with_capacity(capacity: usize) -> Result<Self, Error> {
-        if capacity as Slot == NUL {
+        Self::with_slot_type(capacity)
+    }
+}
+
+impl<D: Sized, S: SlotType> Slab<D, S> {
+    /// Create a new list with the given capacity, using the slot type `S`,
+    /// for example `Slab::<D, u16>::with_slot_type(capacity)`.
+    ///
+    /// Smaller slot types make the links, and the slots stored alongside
+    /// other data, smaller, but limit the capacity to `max_capacity()`.
+    ///
+    /// Return `Error::TooLarge` if the capacity is larger than that, or if
+    /// the size of the storage would overflow, and `Error::OutOfMemory` if
+    /// it can't be allocated.
+    pub fn with_slot_type(capacity: usize) -> Result<Self, Error> {
+        if capacity > Self::max_capacity() {
             return Err(Error::TooLarge);
         }
-        let mut vec_next = Vec::with_capacity(capacity);
-        for i in 0..(capacity - 1) {
-            vec_next.push(i as Slot + 1);
-        }
-        vec_next.push(NUL);
-        let mut vec_prev = Vec::with_capacity(capacity);
-        vec_prev.push(NUL);
+        // The elements are usually the largest array, so allocate them
+        // first, to fail early.
+        let mut data = try_vec(capacity)?;
+        unsafe { data.set_len(capacity) };
+        let mut vec_next = try_vec(capacity)?;
         for i in 1..capacity {
-            vec_prev.push(i as Slot - 1);
+            vec_next.push(S::from_usize(i));
         }
-        let mut data = Vec::with_capacity(capacity);
-        unsafe { data.set_len(capacity) };
+        if capacity > 0 {
+            vec_next.push(S::NUL);
+        }
+        #[cfg(not(feature = "tagged"))]
+        let vec_prev = {
+            let mut vec_prev = try_vec(capacity)?;
+            if capacity > 0 {
+                vec_prev.push(S::NUL);
+            }
+            for i in 1..capacity {
+                vec_prev.push(S::from_usize(i - 1));
+            }
+            vec_prev
+        };
+        // The extra entry is read by `check_slot()` for out-of-range slots.
+        #[cfg(feature = "tagged")]
+        let vec_prev = {
+            let mut vec_prev = try_vec(capacity + 1)?;
+            vec_prev.resize(capacity + 1, S::FREE);
+            vec_prev
+        };
         Ok(Self {
-            vec_next,
-            vec_prev,
-            free_head: 0,
-            head: NUL,
-            tail: NUL,
-            len: 0,
-            data,
-            #[cfg(not(feature = "releasefast"))]
-            bitmap: vec![0u8; (capacity + 7) / 8],
+            head: S::NUL,
+            tail: S::NUL,
+            free_head: if capacity > 0 {
+                S::from_usize(0)
+            } else {
+                S::NUL
+            },
+            len: S::from_usize(0),
+            vec_next: vec_next.into_boxed_slice(),
+            vec_prev: vec_prev.into_boxed_slice(),
+            data: data.into_boxed_slice(),
+            #[cfg(not(any(feature = "releasefast", feature = "tagged")))]
+            bitmap: padded::PaddedSlice::try_zeroed(capacity.div_ceil(8))?,
+            #[cfg(feature = "generational")]
+            generations: padded::PaddedSlice::try_zeroed(capacity)?,
+            deferred_head: S::NUL,
+            deferred_len: S::from_usize(0),
+            #[cfg(feature = "leak_diagnostics")]
+            leak_hook: None,
+            #[cfg(feature = "changelog")]
+            changelog: changelog::ChangeLog::with_capacity(capacity)?,
+            #[cfg(feature = "latency_stats")]
+            latency: latency::LatencyStats::with_capacity(capacity)?,
         })
     }
 
+    /// Return the largest capacity accepted by `with_slot_type()` for the
+    /// slot type of the list.
+    /// Allocating a list that large may still fail.
+    pub const fn max_capacity() -> usize {
+        S::MAX_CAPACITY
+    }
+
     /// Return the capacity of the list.
     pub fn capacity(&self) -> usize {
-        self.data.capacity()
+        self.vec_next.len()
     }
 
     /// Return the length of the list.
     pub fn len(&self) -> usize {
-        self.len
+        self.len.to_usize()
     }
 
     /// Return the number of elements that can still be stored.
     pub fn free(&self) -> usize {
-        self.capacity() - self.len()
+        self.capacity() - self.len() - self.deferred_len()
     }
 
     /// Return true if the list is empty.
@@ -104,109 +576,486 @@ impl<D: Sized> Slab<D> {
 
     /// Return true if the list is full.
     pub fn is_full(&self) -> bool {
-        self.free_head == NUL
+        self.free_head == S::NUL
+    }
+
+    /// Return the slot the next call to `push_front()` will use, or `None`
+    /// if the list is full.
+    pub fn peek_next_free(&self) -> Option<S> {
+        Some(self.free_head).filter(|&slot| slot != S::NUL)
     }
 
     /// Return an element given its slot number.
     /// If the crate is compiled with the `releasefast` feature (which is not the
     /// case by default), `get()` should never be called on a slot index that
     /// was not set.
-    pub fn get(&self, slot: Slot) -> Result<&D, Error> {
-        if slot as usize >= self.capacity() {
+    ///
+    /// This runs a constant number of steps, and never allocates.
+    #[inline]
+    pub fn get(&self, slot: S) -> Result<&D, Error> {
+        if !self.check_slot(slot) {
             return Err(Error::InvalidSlot);
         }
-        #[cfg(not(feature = "releasefast"))]
-        {
-            if !self.bitmap_get(slot) {
-                return Err(Error::InvalidSlot);
-            }
-        }
-        Ok(unsafe { self.data[slot as usize].assume_init_ref() })
+        Ok(unsafe { self.get_unchecked(slot) })
+    }
+
+    /// Return an element given its slot number, without any checks.
+    ///
+    /// # Safety
+    ///
+    /// The slot must contain an element.
+    #[inline]
+    pub unsafe fn get_unchecked(&self, slot: S) -> &D {
+        invariant!(slot.to_usize() < self.capacity());
+        self.data.get_unchecked(slot.to_usize()).assume_init_ref()
     }
 
     /// Return a mutable reference to an element given its slot number.
     /// If the crate is compiled with the `releasefast` feature (which is not the
     /// case by default), `get_mut()` should never be called on a slot index that
     /// was not set.
-    pub fn get_mut(&mut self, slot: Slot) -> Result<&mut D, Error> {
-        if slot as usize >= self.capacity() {
+    ///
+    /// This runs a constant number of steps, and never allocates.
+    #[inline]
+    pub fn get_mut(&mut self, slot: S) -> Result<&mut D, Error> {
+        if !self.check_slot(slot) {
             return Err(Error::InvalidSlot);
         }
-        #[cfg(not(feature = "releasefast"))]
-        {
-            if !self.bitmap_get(slot) {
-                return Err(Error::InvalidSlot);
+        Ok(unsafe { self.get_unchecked_mut(slot) })
+    }
+
+    /// Return a mutable reference to an element given its slot number,
+    /// without any checks.
+    ///
+    /// # Safety
+    ///
+    /// The slot must contain an element.
+    #[inline]
+    pub unsafe fn get_unchecked_mut(&mut self, slot: S) -> &mut D {
+        invariant!(slot.to_usize() < self.capacity());
+        self.data
+            .get_unchecked_mut(slot.to_usize())
+            .assume_init_mut()
+    }
+
+    /// Apply a function to an element given its slot number, and return
+    /// its result.
+    pub fn map_slot<R>(&self, slot: S, f: impl FnOnce(&D) -> R) -> Result<R, Error> {
+        self.get(slot).map(f)
+    }
+
+    /// Apply a function to a mutable reference to an element given its slot
+    /// number, and return its result.
+    ///
+    /// This is the same as `get_mut()` followed by a modification, as a
+    /// single validated call.
+    pub fn update<R>(&mut self, slot: S, f: impl FnOnce(&mut D) -> R) -> Result<R, Error> {
+        self.get_mut(slot).map(f)
+    }
+
+    /// Return an element given its slot number, along with the previous
+    /// element (closer to the head) and the next element (closer to the
+    /// tail), if any.
+    pub fn get_with_neighbors(&self, slot: S) -> Result<(Option<&D>, &D, Option<&D>), Error> {
+        let value = self.get(slot)?;
+        let neighbor = |slot: S| {
+            if slot == S::NUL {
+                None
+            } else {
+                Some(unsafe { self.get_unchecked(slot) })
             }
+        };
+        let prev = neighbor(self.vec_prev[slot.to_usize()]);
+        let next = neighbor(self.vec_next[slot.to_usize()]);
+        Ok((prev, value, next))
+    }
+
+    /// Return a raw pointer to an element given its slot number.
+    ///
+    /// Elements never move while they are in the list, so the pointer
+    /// remains valid until the element is removed (`remove()`,
+    /// `pop_back()`, `truncate()`, `clear()`...), moved out of the list
+    /// (`into_vec()`...), or the list is dropped. Inserting other elements,
+    /// reordering the list, and `swap_contents()` don't invalidate it.
+    ///
+    /// The pointer must not be used to write to the element; use
+    /// `as_mut_ptr()` for that.
+    pub fn as_ptr(&self, slot: S) -> Result<*const D, Error> {
+        self.get(slot)?;
+        Ok(self.data[slot.to_usize()].as_ptr())
+    }
+
+    /// Return a raw mutable pointer to an element given its slot number.
+    ///
+    /// The pointer remains valid under the same rules as `as_ptr()`. It
+    /// must not be used while a reference to the same element obtained
+    /// from the list is alive.
+    pub fn as_mut_ptr(&mut self, slot: S) -> Result<*mut D, Error> {
+        self.get(slot)?;
+        Ok(self.data[slot.to_usize()].as_mut_ptr())
+    }
+
+    /// Return several elements given their slot numbers.
+    /// All the slots are validated first, and `Error::InvalidSlot` is returned
+    /// if any of them is invalid; the elements are then resolved without
+    /// further checks.
+    pub fn get_many<const N: usize>(&self, slots: [S; N]) -> Result<[&D; N], Error> {
+        for &slot in &slots {
+            self.get(slot)?;
         }
-        Ok(unsafe { self.data[slot as usize].assume_init_mut() })
+        Ok(slots.map(|slot| unsafe { self.data[slot.to_usize()].assume_init_ref() }))
+    }
+
+    /// Return an iterator resolving a sequence of slots to elements.
+    pub fn get_many_iter<'a>(
+        &'a self,
+        slots: impl IntoIterator<Item = S> + 'a,
+    ) -> impl Iterator<Item = Result<&'a D, Error>> + 'a {
+        slots.into_iter().map(move |slot| self.get(slot))
     }
 
     /// Prepend an element to the beginning of the list.
-    pub fn push_front(&mut self, value: D) -> Result<Slot, Error> {
-        let free_slot = self.free_head;
-        if free_slot == NUL {
+    ///
+    /// This runs a constant number of steps, and never allocates.
+    #[inline]
+    pub fn push_front(&mut self, value: D) -> Result<S, Error> {
+        if self.free_head == S::NUL {
             return Err(Error::Full);
         }
-        let prev = self.vec_prev[free_slot as usize];
-        let next = self.vec_next[free_slot as usize];
-        if prev != NUL {
-            debug_assert_eq!(self.vec_next[prev as usize], free_slot);
-            self.vec_next[prev as usize] = next;
+        Ok(unsafe { self.push_front_unchecked(value) })
+    }
+
+    /// Prepend an element to the beginning of the list, without checking
+    /// that the list has room for it.
+    ///
+    /// # Safety
+    ///
+    /// The list must not be full.
+    pub unsafe fn push_front_unchecked(&mut self, value: D) -> S {
+        let free_slot = self.free_head;
+        invariant_ne!(free_slot, S::NUL);
+        self.push_front_at(free_slot, value);
+        free_slot
+    }
+
+    /// Store an element in a specific free slot, and link it at the
+    /// beginning of the list.
+    ///
+    /// This is useful to restore a snapshot, or to mirror slot assignments
+    /// made elsewhere. Return `Error::InvalidSlot` if the slot is out of
+    /// range, already occupied, or still holds an element removed with
+    /// `remove_deferred()`. Checking that the slot is free is O(n) if
+    /// the crate is compiled with `releasefast` and without `generational`.
+    /// With the `tagged` feature, unlinking the slot from the free list is
+    /// O(n) as well, unless it is the next free slot. Otherwise, this runs
+    /// a constant number of steps, and never allocates.
+    pub fn insert_at(&mut self, slot: S, value: D) -> Result<(), Error> {
+        if slot.to_usize() >= self.capacity() || self.is_occupied(slot) || self.is_deferred(slot) {
+            return Err(Error::InvalidSlot);
+        }
+        unsafe { self.push_front_at(slot, value) };
+        Ok(())
+    }
+
+    /// Return a mutable reference to the element stored in a specific slot,
+    /// first storing the value returned by `f` in it, and linking it at the
+    /// beginning of the list, if the slot is free.
+    ///
+    /// Return `Error::InvalidSlot` if the slot is out of range, or still
+    /// holds an element removed with `remove_deferred()`. Checking whether
+    /// the slot is occupied has the same cost as in `insert_at()`.
+    pub fn get_or_insert_at(&mut self, slot: S, f: impl FnOnce() -> D) -> Result<&mut D, Error> {
+        if slot.to_usize() >= self.capacity() || self.is_deferred(slot) {
+            return Err(Error::InvalidSlot);
+        }
+        if !self.is_occupied(slot) {
+            unsafe { self.push_front_at(slot, f()) };
+        }
+        Ok(unsafe { self.get_unchecked_mut(slot) })
+    }
+
+    // Unlink a free slot from the free list, and link it at the head of the
+    // list with the given value.
+    unsafe fn push_front_at(&mut self, free_slot: S, value: D) {
+        let prev = self.free_prev(free_slot);
+        let next = self.vec_next[free_slot.to_usize()];
+        if prev != S::NUL {
+            invariant_eq!(self.vec_next[prev.to_usize()], free_slot);
+            self.vec_next[prev.to_usize()] = next;
         }
-        if next != NUL {
+        if next != S::NUL {
+            #[cfg(not(feature = "tagged"))]
             if !self.is_empty() {
-                debug_assert_eq!(self.vec_prev[next as usize], free_slot);
+                invariant_eq!(self.vec_prev[next.to_usize()], free_slot);
             }
-            self.vec_prev[next as usize] = prev;
+            self.set_free_prev(next, prev);
+        }
+        if self.head != S::NUL {
+            self.vec_prev[self.head.to_usize()] = free_slot;
         }
-        if self.head != NUL {
-            self.vec_prev[self.head as usize] = free_slot;
+        if self.free_head == free_slot {
+            invariant_eq!(prev, S::NUL);
+            self.free_head = next;
         }
-        self.free_head = next;
-        self.vec_next[free_slot as usize] = self.head;
-        self.vec_prev[free_slot as usize] = NUL;
-        if self.head == NUL {
+        self.vec_next[free_slot.to_usize()] = self.head;
+        self.vec_prev[free_slot.to_usize()] = S::NUL;
+        if self.head == S::NUL {
             self.tail = free_slot;
         }
         self.head = free_slot;
 
-        self.data[free_slot as usize] = MaybeUninit::new(value);
-        self.len += 1;
-        debug_assert!(self.len <= self.capacity());
+        self.data[free_slot.to_usize()] = MaybeUninit::new(value);
+        self.len = S::from_usize(self.len() + 1);
+        invariant!(self.len() <= self.capacity());
         #[cfg(not(feature = "releasefast"))]
         {
             self.bitmap_set(free_slot);
         }
-        Ok(free_slot)
+        #[cfg(feature = "generational")]
+        {
+            self.generations[free_slot.to_usize()] =
+                self.generations[free_slot.to_usize()].wrapping_add(1);
+        }
+        #[cfg(feature = "changelog")]
+        self.changelog.record(changelog::Op::Push, free_slot);
+        #[cfg(feature = "latency_stats")]
+        self.latency.record_push(free_slot);
+    }
+
+    /// Prepend several elements to the beginning of the list, in order, and
+    /// return their slots in the same order.
+    /// Either all the elements are inserted, or none are and `Error::Full` is
+    /// returned.
+    pub fn push_front_many<const N: usize>(&mut self, values: [D; N]) -> Result<[S; N], Error> {
+        if self.free() < N {
+            return Err(Error::Full);
+        }
+        let mut slots = [S::NUL; N];
+        for (slot, value) in slots.iter_mut().zip(values) {
+            *slot = self.push_front(value)?;
+        }
+        Ok(slots)
+    }
+
+    /// Prepend an element to the beginning of the list, removing the tail
+    /// element first if the list is full.
+    /// Return the slot of the new element, and the evicted element, if any.
+    /// Return `Error::Full` if the capacity of the list is zero.
+    ///
+    /// This runs a constant number of steps, plus the destructor of the
+    /// evicted element, and never allocates.
+    pub fn push_front_evicting(&mut self, value: D) -> Result<(S, Option<D>), Error> {
+        let evicted = if self.is_full() {
+            self.pop_back()
+        } else {
+            None
+        };
+        let slot = self.push_front(value)?;
+        Ok((slot, evicted))
+    }
+
+    /// Prepend every element of the iterator to the list, in order.
+    ///
+    /// Unlike `extend()`, this doesn't panic if the list becomes full, but
+    /// returns `Error::Full`. The elements added until then are kept, the
+    /// element that didn't fit is dropped, and the remaining ones are left
+    /// in the iterator.
+    pub fn try_extend<I: IntoIterator<Item = D>>(&mut self, iter: I) -> Result<(), Error> {
+        for value in iter {
+            self.push_front(value)?;
+        }
+        Ok(())
     }
 
     /// Remove an element from the list given its slot.
     /// If the crate is compiled with the `releasefast` feature (which is not the
     /// case by default), `remove()` should never be called on a slot index that
-    /// was already removed.
-    pub fn remove(&mut self, slot: Slot) -> Result<(), Error> {
-        if slot as usize >= self.capacity() {
+    /// was already removed. Removing the same slot twice in a row is still
+    /// detected, and returns `Error::InvalidSlot`.
+    ///
+    /// This runs a constant number of steps, plus the destructor of the
+    /// element, and never allocates.
+    #[inline]
+    pub fn remove(&mut self, slot: S) -> Result<(), Error> {
+        self.get(slot)?;
+        #[cfg(feature = "releasefast")]
+        {
+            if self.is_known_free(slot) {
+                return Err(Error::InvalidSlot);
+            }
+        }
+        unsafe { self.drop_unchecked(slot) };
+        Ok(())
+    }
+
+    /// Remove an element from the list given its slot, and return it, or
+    /// `None` if the slot doesn't contain an element, for example because
+    /// it was already removed.
+    ///
+    /// The slot is always checked, even with the `releasefast` feature, which
+    /// makes this O(n) in that case, unless `generational` or `tagged` are
+    /// enabled as well.
+    pub fn remove_if_present(&mut self, slot: S) -> Option<D> {
+        self.take_checked(slot).ok()
+    }
+
+    /// Return a pinned mutable reference to an element given its slot number.
+    ///
+    /// The storage of a list is allocated once, and elements never move
+    /// while they are in the list. So, once the list itself is pinned, its
+    /// elements are pinned as well, and can be self-referential types such as
+    /// futures. The address of an element remains the same until it is
+    /// removed with `remove_pinned()`, or until the list is dropped. In both
+    /// cases, the element is dropped in place.
+    pub fn get_pin_mut(self: Pin<&mut Self>, slot: S) -> Result<Pin<&mut D>, Error> {
+        // Elements are never moved out of a pinned list.
+        let list = unsafe { self.get_unchecked_mut() };
+        invariant_eq!(list.data.len(), list.capacity());
+        let value = list.get_mut(slot)?;
+        Ok(unsafe { Pin::new_unchecked(value) })
+    }
+
+    /// Prepend an element to the beginning of a pinned list.
+    pub fn push_front_pinned(self: Pin<&mut Self>, value: D) -> Result<S, Error> {
+        // Adding an element doesn't move the other ones.
+        unsafe { self.get_unchecked_mut() }.push_front(value)
+    }
+
+    /// Remove an element from a pinned list given its slot, and drop it in
+    /// place.
+    pub fn remove_pinned(self: Pin<&mut Self>, slot: S) -> Result<(), Error> {
+        // `remove()` drops the element without moving it.
+        unsafe { self.get_unchecked_mut() }.remove(slot)
+    }
+
+    /// Remove an element from the list given its slot, without any checks,
+    /// and return it.
+    ///
+    /// This is meant for hot paths where the slot is known to be valid, for
+    /// example because it was just returned by `push_front()`.
+    ///
+    /// # Safety
+    ///
+    /// The slot must be lower than the capacity, and contain an element that
+    /// hasn't been removed yet. Calling this twice on the same slot without
+    /// reinserting in between corrupts the list.
+    pub unsafe fn remove_unchecked(&mut self, slot: S) -> D {
+        self.take_unchecked(slot)
+    }
+
+    /// Remove an element from the list given its slot, and return it.
+    #[cfg(any(feature = "generational", test))]
+    pub(crate) fn take(&mut self, slot: S) -> Result<D, Error> {
+        if !self.check_slot(slot) {
             return Err(Error::InvalidSlot);
         }
-        #[cfg(not(feature = "releasefast"))]
+        #[cfg(feature = "releasefast")]
         {
-            if !self.bitmap_get(slot) {
+            if self.is_known_free(slot) {
                 return Err(Error::InvalidSlot);
             }
         }
-        unsafe { self.data[slot as usize].assume_init_drop() };
-        self.data[slot as usize] = MaybeUninit::uninit();
-        let prev = self.vec_prev[slot as usize];
-        let next = self.vec_next[slot as usize];
-        if prev != NUL {
-            debug_assert_eq!(self.vec_next[prev as usize], slot);
-            self.vec_next[prev as usize] = next;
+        Ok(unsafe { self.take_unchecked(slot) })
+    }
+
+    // Like `get()`, `get_mut()`, `take()` and `remove()`, but always check
+    // that the slot contains an element, even with `releasefast`. The types
+    // built on top of `Slab` use these for slots given by their users, so
+    // that they remain sound when another crate enables that feature. This
+    // is as fast as the unchecked versions without `releasefast`, and
+    // follows `is_occupied()` otherwise.
+    pub(crate) fn get_checked(&self, slot: S) -> Result<&D, Error> {
+        if !self.is_occupied(slot) {
+            return Err(Error::InvalidSlot);
+        }
+        Ok(unsafe { self.get_unchecked(slot) })
+    }
+
+    pub(crate) fn get_mut_checked(&mut self, slot: S) -> Result<&mut D, Error> {
+        if !self.is_occupied(slot) {
+            return Err(Error::InvalidSlot);
+        }
+        Ok(unsafe { self.get_unchecked_mut(slot) })
+    }
+
+    pub(crate) fn take_checked(&mut self, slot: S) -> Result<D, Error> {
+        if !self.is_occupied(slot) {
+            return Err(Error::InvalidSlot);
+        }
+        Ok(unsafe { self.take_unchecked(slot) })
+    }
+
+    pub(crate) fn remove_checked(&mut self, slot: S) -> Result<(), Error> {
+        if !self.is_occupied(slot) {
+            return Err(Error::InvalidSlot);
+        }
+        unsafe { self.drop_unchecked(slot) };
+        Ok(())
+    }
+
+    /// Return true if a slot lower than the capacity is known to be free,
+    /// in O(1).
+    ///
+    /// Without occupancy information, only the slots whose links can't be
+    /// the ones of an element are recognized. This includes the most
+    /// recently released slot, so that removing a slot twice in a row is
+    /// detected.
+    #[cfg(feature = "releasefast")]
+    fn is_known_free(&self, slot: S) -> bool {
+        #[cfg(any(feature = "tagged", feature = "generational"))]
+        {
+            !self.is_occupied(slot)
+        }
+        #[cfg(not(any(feature = "tagged", feature = "generational")))]
+        {
+            // Only the head has no predecessor, and only the tail has no
+            // successor. The head of the free list has no predecessor.
+            let index = slot.to_usize();
+            self.is_empty()
+                || slot == self.free_head
+                || self.vec_prev[index] == S::FREE
+                || (self.vec_prev[index] == S::NUL && slot != self.head)
+                || (self.vec_next[index] == S::NUL && slot != self.tail)
+        }
+    }
+
+    unsafe fn take_unchecked(&mut self, slot: S) -> D {
+        invariant!(self.is_occupied(slot), "slot {slot} is not occupied");
+        let value = unsafe { self.data[slot.to_usize()].assume_init_read() };
+        self.data[slot.to_usize()] = MaybeUninit::uninit();
+        self.unlink(slot);
+        value
+    }
+
+    /// Remove an element from the list given its slot, and drop it without
+    /// moving it first.
+    unsafe fn drop_unchecked(&mut self, slot: S) {
+        invariant!(self.is_occupied(slot), "slot {slot} is not occupied");
+        let value = self.data[slot.to_usize()].as_mut_ptr();
+        self.unlink(slot);
+        unsafe { core::ptr::drop_in_place(value) };
+    }
+
+    /// Release a slot whose element has been moved out or is about to be
+    /// dropped.
+    fn unlink(&mut self, slot: S) {
+        self.detach(slot);
+        self.release(slot);
+    }
+
+    /// Unlink an element from the list, without releasing its slot.
+    fn detach(&mut self, slot: S) {
+        let prev = self.vec_prev[slot.to_usize()];
+        let next = self.vec_next[slot.to_usize()];
+        if prev != S::NUL {
+            invariant_eq!(self.vec_next[prev.to_usize()], slot);
+            self.vec_next[prev.to_usize()] = next;
         }
-        if next != NUL {
+        if next != S::NUL {
             if !self.is_empty() {
-                debug_assert_eq!(self.vec_prev[next as usize], slot);
+                invariant_eq!(self.vec_prev[next.to_usize()], slot);
             }
-            self.vec_prev[next as usize] = prev;
+            self.vec_prev[next.to_usize()] = prev;
         }
         if self.tail == slot {
             self.tail = prev;
@@ -214,230 +1063,2759 @@ impl<D: Sized> Slab<D> {
         if self.head == slot {
             self.head = next;
         }
-        self.vec_prev[slot as usize] = NUL;
-        self.vec_next[slot as usize] = self.free_head;
-        if self.free_head != NUL {
-            self.vec_prev[self.free_head as usize] = slot;
+        invariant!(!self.is_empty());
+        self.len = S::from_usize(self.len() - 1);
+        #[cfg(feature = "generational")]
+        {
+            self.generations[slot.to_usize()] = self.generations[slot.to_usize()].wrapping_add(1);
         }
-        self.free_head = slot;
-        debug_assert!(self.len > 0);
-        self.len -= 1;
         #[cfg(not(feature = "releasefast"))]
         {
             self.bitmap_unset(slot);
         }
+        #[cfg(feature = "changelog")]
+        self.changelog.record(changelog::Op::Remove, slot);
+    }
+
+    /// Add a slot whose element has been detached to the free list.
+    fn release(&mut self, slot: S) {
+        self.set_free_prev(slot, S::NUL);
+        self.vec_next[slot.to_usize()] = self.free_head;
+        if self.free_head != S::NUL {
+            self.set_free_prev(self.free_head, slot);
+        }
+        self.free_head = slot;
+    }
+
+    /// Remove an element from the list given its slot, but don't drop it
+    /// yet: it is kept, along with its slot, until `flush_deferred()` is
+    /// called.
+    ///
+    /// This is useful when dropping elements is not possible at the time
+    /// they are removed, for example in a signal handler, or while readers
+    /// may still be using them. As far as other methods are concerned, the
+    /// element is removed, but its slot can't be reused until it is
+    /// dropped. Elements that are still pending are dropped along with the
+    /// list.
+    pub fn remove_deferred(&mut self, slot: S) -> Result<(), Error> {
+        self.get(slot)?;
+        #[cfg(feature = "releasefast")]
+        {
+            if self.is_known_free(slot) {
+                return Err(Error::InvalidSlot);
+            }
+        }
+        self.detach(slot);
+        // Pending slots are tagged like free slots, but are not part of the
+        // free list.
+        self.vec_prev[slot.to_usize()] = S::FREE;
+        self.vec_next[slot.to_usize()] = self.deferred_head;
+        self.deferred_head = slot;
+        self.deferred_len = S::from_usize(self.deferred_len() + 1);
         Ok(())
     }
 
+    /// Drop the elements removed with `remove_deferred()`, release their
+    /// slots, and return how many elements were dropped.
+    ///
+    /// If dropping an element panics, the remaining ones are still dropped.
+    pub fn flush_deferred(&mut self) -> usize {
+        struct Guard<'a, D, S: SlotType>(&'a mut Slab<D, S>);
+
+        impl<D, S: SlotType> Drop for Guard<'_, D, S> {
+            fn drop(&mut self) {
+                self.0.flush_deferred();
+            }
+        }
+
+        let guard = Guard(self);
+        let mut count = 0;
+        while guard.0.deferred_head != S::NUL {
+            let list = &mut *guard.0;
+            let slot = list.deferred_head;
+            list.deferred_head = list.vec_next[slot.to_usize()];
+            list.deferred_len = S::from_usize(list.deferred_len() - 1);
+            let value = unsafe { list.data[slot.to_usize()].assume_init_read() };
+            list.data[slot.to_usize()] = MaybeUninit::uninit();
+            list.release(slot);
+            count += 1;
+            drop(value);
+        }
+        core::mem::forget(guard);
+        count
+    }
+
+    /// Return the number of elements removed with `remove_deferred()` that
+    /// have not been dropped yet.
+    pub fn deferred_len(&self) -> usize {
+        self.deferred_len.to_usize()
+    }
+
+    // Return true if a slot holds an element removed with
+    // `remove_deferred()`. With the `tagged` feature, such slots look free,
+    // so the list of pending elements has to be searched.
+    fn is_deferred(&self, slot: S) -> bool {
+        #[cfg(not(feature = "tagged"))]
+        {
+            self.vec_prev[slot.to_usize()] == S::FREE
+        }
+        #[cfg(feature = "tagged")]
+        {
+            let mut current = self.deferred_head;
+            while current != S::NUL {
+                if current == slot {
+                    return true;
+                }
+                current = self.vec_next[current.to_usize()];
+            }
+            false
+        }
+    }
+
     /// Remove and return the tail element of the list.
+    ///
+    /// This runs a constant number of steps, and never allocates.
+    #[inline]
     pub fn pop_back(&mut self) -> Option<D> {
         let slot = self.tail;
-        if slot == NUL {
+        if slot == S::NUL {
             return None;
         }
-        let value = unsafe { self.data[slot as usize].assume_init_read() };
-        self.data[slot as usize] = MaybeUninit::uninit();
-        let prev = self.vec_prev[slot as usize];
-        debug_assert_eq!(self.vec_next[slot as usize], NUL);
-        if prev != NUL {
-            debug_assert_eq!(self.vec_next[prev as usize], slot);
-            self.vec_next[prev as usize] = NUL;
+        let value = unsafe { self.data[slot.to_usize()].assume_init_read() };
+        self.data[slot.to_usize()] = MaybeUninit::uninit();
+        let prev = self.vec_prev[slot.to_usize()];
+        invariant_eq!(self.vec_next[slot.to_usize()], S::NUL);
+        if prev != S::NUL {
+            invariant_eq!(self.vec_next[prev.to_usize()], slot);
+            self.vec_next[prev.to_usize()] = S::NUL;
         }
         self.tail = prev;
         if self.head == slot {
-            self.head = NUL;
+            self.head = S::NUL;
         }
-        self.vec_prev[slot as usize] = NUL;
-        self.vec_next[slot as usize] = self.free_head;
-        if self.free_head != NUL {
-            self.vec_prev[self.free_head as usize] = slot;
+        self.set_free_prev(slot, S::NUL);
+        self.vec_next[slot.to_usize()] = self.free_head;
+        if self.free_head != S::NUL {
+            self.set_free_prev(self.free_head, slot);
         }
         self.free_head = slot;
-        debug_assert!(self.len > 0);
-        self.len -= 1;
-        #[cfg(not(feature = "releasefast"))]
+        invariant!(!self.is_empty());
+        self.len = S::from_usize(self.len() - 1);
+        #[cfg(feature = "generational")]
+        {
+            self.generations[slot.to_usize()] = self.generations[slot.to_usize()].wrapping_add(1);
+        }
+        #[cfg(not(feature = "releasefast"))]
         {
             self.bitmap_unset(slot);
         }
+        #[cfg(feature = "changelog")]
+        self.changelog.record(changelog::Op::Pop, slot);
+        #[cfg(feature = "latency_stats")]
+        self.latency.record_pop(slot, self.latency.now());
         Some(value)
     }
 
     /// Remove and return a reference to the tail element of the list.
     pub fn pop_back_ref(&mut self) -> Option<&D> {
         let slot = self.tail;
-        if slot == NUL {
+        if slot == S::NUL {
             return None;
         }
-        let value = unsafe { self.data[slot as usize].assume_init_ref() };
-        let prev = self.vec_prev[slot as usize];
-        debug_assert_eq!(self.vec_next[slot as usize], NUL);
-        if prev != NUL {
-            debug_assert_eq!(self.vec_next[prev as usize], slot);
-            self.vec_next[prev as usize] = NUL;
+        let prev = self.vec_prev[slot.to_usize()];
+        invariant_eq!(self.vec_next[slot.to_usize()], S::NUL);
+        if prev != S::NUL {
+            invariant_eq!(self.vec_next[prev.to_usize()], slot);
+            self.vec_next[prev.to_usize()] = S::NUL;
         }
         self.tail = prev;
         if self.head == slot {
-            self.head = NUL;
+            self.head = S::NUL;
         }
-        self.vec_prev[slot as usize] = NUL;
-        self.vec_next[slot as usize] = self.free_head;
-        if self.free_head != NUL {
-            self.vec_prev[self.free_head as usize] = slot;
+        self.set_free_prev(slot, S::NUL);
+        self.vec_next[slot.to_usize()] = self.free_head;
+        if self.free_head != S::NUL {
+            self.set_free_prev(self.free_head, slot);
         }
         self.free_head = slot;
-        debug_assert!(self.len > 0);
-        self.len -= 1;
+        invariant!(!self.is_empty());
+        self.len = S::from_usize(self.len() - 1);
+        #[cfg(feature = "generational")]
+        {
+            self.generations[slot.to_usize()] = self.generations[slot.to_usize()].wrapping_add(1);
+        }
+        #[cfg(not(feature = "releasefast"))]
+        {
+            self.bitmap_unset(slot);
+        }
+        #[cfg(feature = "changelog")]
+        self.changelog.record(changelog::Op::Pop, slot);
+        #[cfg(feature = "latency_stats")]
+        self.latency.record_pop(slot, self.latency.now());
+        let value = unsafe { self.data[slot.to_usize()].assume_init_ref() };
         Some(value)
     }
 
     /// Remove and return a mutable reference to the tail element of the list.
     pub fn pop_back_ref_mut(&mut self) -> Option<&mut D> {
         let slot = self.tail;
-        if slot == NUL {
+        if slot == S::NUL {
             return None;
         }
-        let value = unsafe { self.data[slot as usize].assume_init_mut() };
-        let prev = self.vec_prev[slot as usize];
-        debug_assert_eq!(self.vec_next[slot as usize], NUL);
-        if prev != NUL {
-            debug_assert_eq!(self.vec_next[prev as usize], slot);
-            self.vec_next[prev as usize] = NUL;
+        let prev = self.vec_prev[slot.to_usize()];
+        invariant_eq!(self.vec_next[slot.to_usize()], S::NUL);
+        if prev != S::NUL {
+            invariant_eq!(self.vec_next[prev.to_usize()], slot);
+            self.vec_next[prev.to_usize()] = S::NUL;
         }
         self.tail = prev;
         if self.head == slot {
-            self.head = NUL;
+            self.head = S::NUL;
         }
-        self.vec_prev[slot as usize] = NUL;
-        self.vec_next[slot as usize] = self.free_head;
-        if self.free_head != NUL {
-            self.vec_prev[self.free_head as usize] = slot;
+        self.set_free_prev(slot, S::NUL);
+        self.vec_next[slot.to_usize()] = self.free_head;
+        if self.free_head != S::NUL {
+            self.set_free_prev(self.free_head, slot);
         }
         self.free_head = slot;
-        debug_assert!(self.len > 0);
-        self.len -= 1;
+        invariant!(!self.is_empty());
+        self.len = S::from_usize(self.len() - 1);
+        #[cfg(feature = "generational")]
+        {
+            self.generations[slot.to_usize()] = self.generations[slot.to_usize()].wrapping_add(1);
+        }
+        #[cfg(not(feature = "releasefast"))]
+        {
+            self.bitmap_unset(slot);
+        }
+        #[cfg(feature = "changelog")]
+        self.changelog.record(changelog::Op::Pop, slot);
+        #[cfg(feature = "latency_stats")]
+        self.latency.record_pop(slot, self.latency.now());
+        let value = unsafe { self.data[slot.to_usize()].assume_init_mut() };
         Some(value)
     }
 
-    /// Iterate over the list.
-    pub fn iter(&self) -> SlabIterator<D> {
-        SlabIterator {
-            list: self,
-            slot: None,
+    /// Remove and return the tail element of the list, only if it satisfies
+    /// the predicate.
+    pub fn pop_back_if(&mut self, pred: impl FnOnce(&D) -> bool) -> Option<D> {
+        let slot = self.tail;
+        if slot == S::NUL {
+            return None;
         }
+        if !pred(unsafe { self.data[slot.to_usize()].assume_init_ref() }) {
+            return None;
+        }
+        self.pop_back()
     }
 
-    /// Check if the slot contains an element.
-    #[cfg(not(feature = "releasefast"))]
-    pub fn contains_slot(&self, slot: Slot) -> bool {
-        if slot as usize >= self.capacity() {
-            return false;
+    /// Remove and drop tail elements as long as they satisfy the predicate,
+    /// and return how many elements were removed.
+    pub fn evict_while(&mut self, mut pred: impl FnMut(&D) -> bool) -> usize {
+        let mut count = 0;
+        while self.pop_back_if(&mut pred).is_some() {
+            count += 1;
         }
-        self.bitmap_get(slot)
+        count
     }
 
-    #[cfg(not(feature = "releasefast"))]
-    #[inline]
-    fn bitmap_get(&self, slot: Slot) -> bool {
-        (self.bitmap[slot as usize / 8] & (1 << (slot & 7))) != 0
+    /// Remove and drop at most `max_items` tail elements, as long as they
+    /// satisfy the predicate.
+    /// Return `Sweep::Pending` if the tail still satisfies the predicate
+    /// afterwards, so that the sweep can be resumed by another call.
+    pub fn evict_some(&mut self, max_items: usize, mut pred: impl FnMut(&D) -> bool) -> Sweep {
+        let mut count = 0;
+        while count < max_items {
+            if self.pop_back_if(&mut pred).is_none() {
+                return Sweep::Done(count);
+            }
+            count += 1;
+        }
+        let slot = self.tail;
+        if slot != S::NUL && pred(unsafe { self.data[slot.to_usize()].assume_init_ref() }) {
+            Sweep::Pending(count)
+        } else {
+            Sweep::Done(count)
+        }
     }
 
-    #[cfg(not(feature = "releasefast"))]
-    #[inline]
-    fn bitmap_set(&mut self, slot: Slot) {
-        self.bitmap[slot as usize / 8] |= 1 << (slot & 7);
+    /// Remove and drop tail elements until the list contains at most `len`
+    /// elements.
+    pub fn truncate(&mut self, len: usize) {
+        while self.len() > len {
+            self.pop_back();
+        }
     }
 
-    #[cfg(not(feature = "releasefast"))]
-    #[inline]
-    fn bitmap_unset(&mut self, slot: Slot) {
-        self.bitmap[slot as usize / 8] &= !(1 << (slot & 7));
+    /// Remove up to `n` elements from the tail of the list, and return an
+    /// iterator over them, from the tail towards the head.
+    ///
+    /// The elements are unlinked and their slots are released in a single
+    /// step, so this is cheaper than calling `pop_back()` `n` times.
+    /// Elements that are not consumed by the iterator are dropped when the
+    /// iterator is dropped.
+    pub fn drain_back(&mut self, n: usize) -> DrainBack<'_, D, S> {
+        let n = n.min(self.len());
+        if n == 0 {
+            return DrainBack {
+                list: self,
+                slot: S::NUL,
+                remaining: 0,
+            };
+        }
+        // The segment is added to the free list in reverse order, so that
+        // the free list can be walked from the tail of the segment, the same
+        // way the elements are yielded.
+        let last = self.tail;
+        let mut first = S::NUL;
+        let mut slot = last;
+        #[cfg(feature = "latency_stats")]
+        let now = self.latency.now();
+        for _ in 0..n {
+            let prev = self.vec_prev[slot.to_usize()];
+            self.set_free_prev(slot, first);
+            self.vec_next[slot.to_usize()] = prev;
+            #[cfg(not(feature = "releasefast"))]
+            {
+                self.bitmap_unset(slot);
+            }
+            #[cfg(feature = "generational")]
+            {
+                self.generations[slot.to_usize()] =
+                    self.generations[slot.to_usize()].wrapping_add(1);
+            }
+            #[cfg(feature = "changelog")]
+            self.changelog.record(changelog::Op::Pop, slot);
+            #[cfg(feature = "latency_stats")]
+            self.latency.record_pop(slot, now);
+            first = slot;
+            slot = prev;
+        }
+        if slot != S::NUL {
+            self.vec_next[slot.to_usize()] = S::NUL;
+        } else {
+            self.head = S::NUL;
+        }
+        self.tail = slot;
+        self.vec_next[first.to_usize()] = self.free_head;
+        if self.free_head != S::NUL {
+            self.set_free_prev(self.free_head, first);
+        }
+        self.free_head = last;
+        self.len = S::from_usize(self.len() - n);
+        DrainBack {
+            list: self,
+            slot: last,
+            remaining: n,
+        }
     }
-}
 
-impl<D> Drop for Slab<D> {
-    fn drop(&mut self) {
+    /// Remove up to `out.len()` elements from the tail of the list, and move
+    /// them to the beginning of `out`, from the tail towards the head.
+    /// Return the number of elements written; the rest of `out` is left
+    /// untouched.
+    ///
+    /// This releases the slots in a single step, like `drain_back()`, so
+    /// that batches can be processed without a call per element. The
+    /// caller becomes responsible for dropping the written elements.
+    pub fn pop_back_into(&mut self, out: &mut [MaybeUninit<D>]) -> usize {
+        let n = out.len().min(self.len());
+        for (dst, value) in out.iter_mut().zip(self.drain_back(n)) {
+            dst.write(value);
+        }
+        n
+    }
+
+    /// Remove up to `N` elements from the tail of the list, and return them
+    /// in an array, from the tail towards the head, along with their count.
+    /// Only that many elements of the array, starting from the first one,
+    /// are initialized.
+    pub fn pop_back_chunk<const N: usize>(&mut self) -> ([MaybeUninit<D>; N], usize) {
+        let mut chunk = core::array::from_fn(|_| MaybeUninit::uninit());
+        let n = self.pop_back_into(&mut chunk);
+        (chunk, n)
+    }
+
+    /// Visit the elements from the head to the tail, along with their slots,
+    /// and remove the ones for which the function returns `Visit::Remove`.
+    /// Return the number of removed elements.
+    ///
+    /// Elements are removed as the traversal goes, without collecting their
+    /// slots first.
+    pub fn for_each_removable(&mut self, mut f: impl FnMut(S, &mut D) -> Visit) -> usize {
+        let mut count = 0;
         let mut slot = self.head;
-        while slot != NUL {
-            let next = self.vec_next[slot as usize];
-            unsafe { self.data[slot as usize].assume_init_drop() };
+        while slot != S::NUL {
+            let next = self.vec_next[slot.to_usize()];
+            let value = unsafe { self.data[slot.to_usize()].assume_init_mut() };
+            if f(slot, value) == Visit::Remove {
+                unsafe { self.drop_unchecked(slot) };
+                count += 1;
+            }
             slot = next;
         }
+        count
     }
-}
 
-impl<D> core::ops::Index<Slot> for Slab<D> {
-    type Output = D;
+    /// Return the distance of an element from the head of the list, the head
+    /// itself being at position 0. This is O(n).
+    pub fn position_of(&self, slot: S) -> Result<usize, Error> {
+        self.get(slot)?;
+        let mut current = self.head;
+        let mut position = 0;
+        while current != slot {
+            invariant_ne!(current, S::NUL);
+            current = self.vec_next[current.to_usize()];
+            position += 1;
+        }
+        Ok(position)
+    }
 
-    fn index(&self, slot: Slot) -> &Self::Output {
-        unsafe { self.data[slot as usize].assume_init_ref() }
+    /// Return the slot of the element at position `n` from the head of the
+    /// list, the head itself being at position 0. This is O(n).
+    pub fn nth_slot(&self, n: usize) -> Option<S> {
+        if n >= self.len() {
+            return None;
+        }
+        if n < self.len() / 2 {
+            let mut slot = self.head;
+            for _ in 0..n {
+                slot = self.vec_next[slot.to_usize()];
+            }
+            Some(slot)
+        } else {
+            let mut slot = self.tail;
+            for _ in 0..(self.len() - 1 - n) {
+                slot = self.vec_prev[slot.to_usize()];
+            }
+            Some(slot)
+        }
+    }
+
+    /// Reverse the order of the list, so that the tail becomes the head.
+    /// Slots remain valid, and elements are not moved. This is O(n).
+    pub fn reverse(&mut self) {
+        let mut slot = self.head;
+        while slot != S::NUL {
+            let next = self.vec_next[slot.to_usize()];
+            core::mem::swap(
+                &mut self.vec_next[slot.to_usize()],
+                &mut self.vec_prev[slot.to_usize()],
+            );
+            slot = next;
+        }
+        core::mem::swap(&mut self.head, &mut self.tail);
     }
-}
 
-impl<D> core::ops::IndexMut<Slot> for Slab<D> {
-    fn index_mut(&mut self, slot: Slot) -> &mut Self::Output {
-        unsafe { self.data[slot as usize].assume_init_mut() }
+    /// Sort the list with a comparator function, so that iterating from the
+    /// head yields the elements in ascending order.
+    ///
+    /// The sort is stable, and relinks the nodes instead of moving the
+    /// elements: slots remain valid. This is O(n log n), and doesn't
+    /// allocate.
+    pub fn sort_by<F>(&mut self, mut cmp: F)
+    where
+        F: FnMut(&D, &D) -> Ordering,
+    {
+        if self.len() < 2 {
+            return;
+        }
+        // Bottom-up merge sort over the `vec_next` links only; the `vec_prev`
+        // links are rebuilt afterwards.
+        let mut list = self.head;
+        let mut width = 1;
+        loop {
+            let mut p = list;
+            let mut tail = S::NUL;
+            let mut merges = 0;
+            list = S::NUL;
+            while p != S::NUL {
+                merges += 1;
+                let mut q = p;
+                let mut p_size = 0;
+                while p_size < width && q != S::NUL {
+                    p_size += 1;
+                    q = self.vec_next[q.to_usize()];
+                }
+                let mut q_size = width;
+                while p_size > 0 || (q_size > 0 && q != S::NUL) {
+                    let take_p = if p_size == 0 {
+                        false
+                    } else if q_size == 0 || q == S::NUL {
+                        true
+                    } else {
+                        let (a, b) = unsafe {
+                            (
+                                self.data[p.to_usize()].assume_init_ref(),
+                                self.data[q.to_usize()].assume_init_ref(),
+                            )
+                        };
+                        cmp(a, b) != Ordering::Greater
+                    };
+                    let slot = if take_p {
+                        let slot = p;
+                        p = self.vec_next[p.to_usize()];
+                        p_size -= 1;
+                        slot
+                    } else {
+                        let slot = q;
+                        q = self.vec_next[q.to_usize()];
+                        q_size -= 1;
+                        slot
+                    };
+                    if tail == S::NUL {
+                        list = slot;
+                    } else {
+                        self.vec_next[tail.to_usize()] = slot;
+                    }
+                    tail = slot;
+                }
+                p = q;
+            }
+            self.vec_next[tail.to_usize()] = S::NUL;
+            if merges <= 1 {
+                break;
+            }
+            width *= 2;
+        }
+        self.head = list;
+        let mut prev = S::NUL;
+        let mut slot = list;
+        while slot != S::NUL {
+            self.vec_prev[slot.to_usize()] = prev;
+            prev = slot;
+            slot = self.vec_next[slot.to_usize()];
+        }
+        self.tail = prev;
     }
-}
 
-pub struct SlabIterator<'a, D> {
-    list: &'a Slab<D>,
-    slot: Option<Slot>,
-}
+    /// Remove consecutive elements that map to the same key, keeping the
+    /// first one of each run in traversal order (from the head).
+    /// Return the number of removed elements.
+    pub fn dedup_by_key<K, F>(&mut self, mut key: F) -> usize
+    where
+        K: PartialEq,
+        F: FnMut(&D) -> K,
+    {
+        let mut slot = self.head;
+        if slot == S::NUL {
+            return 0;
+        }
+        let mut current = key(unsafe { self.data[slot.to_usize()].assume_init_ref() });
+        slot = self.vec_next[slot.to_usize()];
+        let mut count = 0;
+        while slot != S::NUL {
+            let next = self.vec_next[slot.to_usize()];
+            let k = key(unsafe { self.data[slot.to_usize()].assume_init_ref() });
+            if k == current {
+                unsafe { self.drop_unchecked(slot) };
+                count += 1;
+            } else {
+                current = k;
+            }
+            slot = next;
+        }
+        count
+    }
 
-impl<'a, D> Iterator for SlabIterator<'a, D> {
-    type Item = &'a D;
+    /// Move the tail element to the head of the list, and return its slot.
+    /// The element is not moved in memory. This is O(1).
+    pub fn rotate_back_to_front(&mut self) -> Option<S> {
+        let slot = self.tail;
+        if slot == S::NUL {
+            return None;
+        }
+        if slot != self.head {
+            let prev = self.vec_prev[slot.to_usize()];
+            self.vec_next[prev.to_usize()] = S::NUL;
+            self.tail = prev;
+            self.vec_prev[slot.to_usize()] = S::NUL;
+            self.vec_next[slot.to_usize()] = self.head;
+            self.vec_prev[self.head.to_usize()] = slot;
+            self.head = slot;
+        }
+        Some(slot)
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let slot = self.slot.unwrap_or(self.list.head);
-        if slot == NUL {
+    /// Move the head element to the tail of the list, and return its slot.
+    /// The element is not moved in memory. This is O(1).
+    pub fn rotate_front_to_back(&mut self) -> Option<S> {
+        let slot = self.head;
+        if slot == S::NUL {
             return None;
         }
-        let res = unsafe { self.list.data[slot as usize].assume_init_ref() };
-        self.slot = Some(self.list.vec_next[slot as usize]);
-        Some(res)
+        if slot != self.tail {
+            let next = self.vec_next[slot.to_usize()];
+            self.vec_prev[next.to_usize()] = S::NUL;
+            self.head = next;
+            self.vec_next[slot.to_usize()] = S::NUL;
+            self.vec_prev[slot.to_usize()] = self.tail;
+            self.vec_next[self.tail.to_usize()] = slot;
+            self.tail = slot;
+        }
+        Some(slot)
     }
-}
 
-impl<D> ExactSizeIterator for SlabIterator<'_, D> {
-    fn len(&self) -> usize {
-        self.list.len()
+    /// Return the slot of the element with the minimum key, along with the
+    /// element, or `None` if the list is empty. If several elements are
+    /// minimal, the one closest to the head is returned.
+    pub fn min_by_key<K: Ord>(&self, mut f: impl FnMut(&D) -> K) -> Option<(S, &D)> {
+        self.iter_slots()
+            .map(|slot| (slot, unsafe { self.get_unchecked(slot) }))
+            .min_by_key(|(_, value)| f(value))
     }
-}
 
-impl<'a, D> DoubleEndedIterator for SlabIterator<'a, D> {
-    fn next_back(&mut self) -> Option<&'a D> {
-        let slot = self.slot.unwrap_or(self.list.tail);
-        if slot == NUL {
+    /// Return the slot of the element with the maximum key, along with the
+    /// element, or `None` if the list is empty. If several elements are
+    /// maximal, the one closest to the tail is returned.
+    pub fn max_by_key<K: Ord>(&self, mut f: impl FnMut(&D) -> K) -> Option<(S, &D)> {
+        self.iter_slots()
+            .map(|slot| (slot, unsafe { self.get_unchecked(slot) }))
+            .max_by_key(|(_, value)| f(value))
+    }
+
+    /// Return the slot of the minimum element according to a comparator
+    /// function, along with the element, or `None` if the list is empty.
+    /// If several elements are minimal, the one closest to the head is
+    /// returned.
+    pub fn min_by(&self, mut cmp: impl FnMut(&D, &D) -> Ordering) -> Option<(S, &D)> {
+        self.iter_slots()
+            .map(|slot| (slot, unsafe { self.get_unchecked(slot) }))
+            .min_by(|(_, a), (_, b)| cmp(a, b))
+    }
+
+    /// Return the slot of the maximum element according to a comparator
+    /// function, along with the element, or `None` if the list is empty.
+    /// If several elements are maximal, the one closest to the tail is
+    /// returned.
+    pub fn max_by(&self, mut cmp: impl FnMut(&D, &D) -> Ordering) -> Option<(S, &D)> {
+        self.iter_slots()
+            .map(|slot| (slot, unsafe { self.get_unchecked(slot) }))
+            .max_by(|(_, a), (_, b)| cmp(a, b))
+    }
+
+    /// Return a copy of the elements in a vector, from the head to the tail.
+    pub fn to_vec(&self) -> Vec<D>
+    where
+        D: Clone,
+    {
+        self.iter().cloned().collect()
+    }
+
+    /// Return a copy of the elements along with their slots in a vector,
+    /// from the head to the tail.
+    pub fn to_vec_with_slots(&self) -> Vec<(S, D)>
+    where
+        D: Clone,
+    {
+        self.iter_slots()
+            .map(|slot| (slot, unsafe { self.get_unchecked(slot) }.clone()))
+            .collect()
+    }
+
+    /// Consume the list, and return its elements in a vector, from the head
+    /// to the tail.
+    pub fn into_vec(mut self) -> Vec<D> {
+        let mut values = Vec::with_capacity(self.len());
+        let mut slot = self.head;
+        while slot != S::NUL {
+            values.push(unsafe { self.data[slot.to_usize()].assume_init_read() });
+            slot = self.vec_next[slot.to_usize()];
+        }
+        // The elements have been moved out; don't let `drop()` see them.
+        self.head = S::NUL;
+        self.tail = S::NUL;
+        self.len = S::from_usize(0);
+        values
+    }
+
+    /// Return a copy of the list, with the same capacity, in which the
+    /// elements occupy slots `0..len()` in traversal order, along with the
+    /// mapping from the current slots to the new ones.
+    /// Return `Error::OutOfMemory` if the copy can't be allocated.
+    pub fn clone_compacted(&self) -> Result<(Self, SlotRemap<S>), Error>
+    where
+        D: Clone,
+    {
+        let mut compacted = Self::with_slot_type(self.capacity())?;
+        let mut new_slots = try_vec(self.capacity())?;
+        new_slots.resize(self.capacity(), S::NUL);
+        let mut slot = self.head;
+        while slot != S::NUL {
+            let value = unsafe { self.data[slot.to_usize()].assume_init_ref() };
+            new_slots[slot.to_usize()] = unsafe { compacted.push_front_unchecked(value.clone()) };
+            slot = self.vec_next[slot.to_usize()];
+        }
+        compacted.reverse();
+        Ok((compacted, SlotRemap { new_slots }))
+    }
+
+    /// Exchange the contents of two lists, in O(1).
+    ///
+    /// The lists don't need to have the same capacity. Slots follow their
+    /// elements: a slot previously returned by `self` is now valid for
+    /// `other`, and vice versa.
+    pub fn swap_contents(&mut self, other: &mut Self) {
+        core::mem::swap(self, other)
+    }
+
+    /// Remove all the elements, from the tail to the head.
+    ///
+    /// Slots are released the same way `pop_back()` releases them, so that
+    /// the most recently used slots are reused first. With the
+    /// `generational` feature, generations are not reset: versioned slots
+    /// obtained before the list was cleared remain stale.
+    ///
+    /// If dropping an element panics, the remaining elements are still
+    /// dropped, and the list is left empty.
+    ///
+    /// Elements are dropped in place. Elements removed with
+    /// `remove_deferred()` are not dropped; see `flush_deferred()`.
+    pub fn clear(&mut self) {
+        struct Guard<'a, D, S: SlotType>(&'a mut Slab<D, S>);
+
+        impl<D, S: SlotType> Drop for Guard<'_, D, S> {
+            fn drop(&mut self) {
+                self.0.clear();
+            }
+        }
+
+        let guard = Guard(self);
+        while guard.0.tail != S::NUL {
+            unsafe { guard.0.drop_unchecked(guard.0.tail) };
+        }
+        core::mem::forget(guard);
+    }
+
+    /// Remove all the elements, from the tail to the head, handing each of
+    /// them to a function along with the slot it was stored in.
+    /// The slot is already free when the function is called.
+    ///
+    /// If the function panics, the remaining elements are dropped, and the
+    /// list is left empty.
+    pub fn clear_with(&mut self, mut f: impl FnMut(S, D)) {
+        struct Guard<'a, D, S: SlotType>(&'a mut Slab<D, S>);
+
+        impl<D, S: SlotType> Drop for Guard<'_, D, S> {
+            fn drop(&mut self) {
+                while self.0.pop_back().is_some() {}
+            }
+        }
+
+        let guard = Guard(self);
+        loop {
+            let slot = guard.0.tail;
+            match guard.0.pop_back() {
+                Some(value) => f(slot, value),
+                None => break,
+            }
+        }
+    }
+
+    /// Set the function called with the slots of the remaining elements
+    /// when the list is dropped while not empty.
+    ///
+    /// Reports are only made in debug builds. Without a hook, they are
+    /// printed to the standard error if the `std` feature is enabled.
+    ///
+    /// With the `realtime` feature, reports don't allocate: the hook may be
+    /// called several times, with at most 64 slots each time, and reports
+    /// printed to the standard error only include the number of elements.
+    #[cfg(feature = "leak_diagnostics")]
+    pub fn set_leak_hook(&mut self, hook: fn(&[S])) {
+        self.leak_hook = Some(hook);
+    }
+
+    #[cfg(all(
+        feature = "leak_diagnostics",
+        debug_assertions,
+        not(feature = "realtime")
+    ))]
+    fn report_leaks(&self) {
+        if self.is_empty() {
+            return;
+        }
+        let mut slots = Vec::with_capacity(self.len());
+        let mut slot = self.head;
+        while slot != S::NUL {
+            slots.push(slot);
+            slot = self.vec_next[slot.to_usize()];
+        }
+        match self.leak_hook {
+            Some(hook) => hook(&slots),
+            #[cfg(feature = "std")]
+            None => std::eprintln!(
+                "slabigator: slab dropped with {} occupied slots: {:?}",
+                slots.len(),
+                slots
+            ),
+            #[cfg(not(feature = "std"))]
+            None => {}
+        }
+    }
+
+    // With `realtime`, the slots are reported in batches kept on the stack,
+    // so that dropping a list never allocates.
+    #[cfg(all(feature = "leak_diagnostics", debug_assertions, feature = "realtime"))]
+    fn report_leaks(&self) {
+        if self.is_empty() {
+            return;
+        }
+        let Some(hook) = self.leak_hook else {
+            #[cfg(feature = "std")]
+            std::eprintln!(
+                "slabigator: slab dropped with {} occupied slots",
+                self.len()
+            );
+            return;
+        };
+        let mut batch = [S::NUL; LEAK_BATCH];
+        let mut len = 0;
+        let mut slot = self.head;
+        while slot != S::NUL {
+            batch[len] = slot;
+            len += 1;
+            slot = self.vec_next[slot.to_usize()];
+            if len == LEAK_BATCH || slot == S::NUL {
+                hook(&batch[..len]);
+                len = 0;
+            }
+        }
+    }
+
+    /// Move an element to the beginning of the list, in O(1).
+    /// Its slot doesn't change.
+    /// If the crate is compiled with the `releasefast` feature (which is not the
+    /// case by default), `move_to_front()` should never be called on a slot index
+    /// that was not set.
+    ///
+    /// This runs a constant number of steps, and never allocates.
+    #[inline]
+    pub fn move_to_front(&mut self, slot: S) -> Result<(), Error> {
+        if !self.check_slot(slot) {
+            return Err(Error::InvalidSlot);
+        }
+        if self.head == slot {
+            return Ok(());
+        }
+        let prev = self.vec_prev[slot.to_usize()];
+        let next = self.vec_next[slot.to_usize()];
+        invariant_ne!(prev, S::NUL);
+        invariant_eq!(self.vec_next[prev.to_usize()], slot);
+        self.vec_next[prev.to_usize()] = next;
+        if next != S::NUL {
+            invariant_eq!(self.vec_prev[next.to_usize()], slot);
+            self.vec_prev[next.to_usize()] = prev;
+        } else {
+            invariant_eq!(self.tail, slot);
+            self.tail = prev;
+        }
+        self.vec_prev[slot.to_usize()] = S::NUL;
+        self.vec_next[slot.to_usize()] = self.head;
+        self.vec_prev[self.head.to_usize()] = slot;
+        self.head = slot;
+        Ok(())
+    }
+
+    /// Exchange the elements stored in two slots, without changing the
+    /// order of the slots in the list.
+    /// The elements are moved, so this can't be used on a pinned list.
+    pub fn swap_data(&mut self, a: S, b: S) -> Result<(), Error> {
+        self.get(a)?;
+        self.get(b)?;
+        self.data.swap(a.to_usize(), b.to_usize());
+        Ok(())
+    }
+
+    /// Iterate over the list.
+    ///
+    /// The iterator borrows the list, so the list can't be modified while it
+    /// is being iterated over:
+    ///
+    /// ```compile_fail
+    /// let mut slab = slabigator::Slab::with_capacity(2).unwrap();
+    /// let slot = slab.push_front(1).unwrap();
+    /// for _ in slab.iter() {
+    ///     slab.remove(slot).unwrap();
+    /// }
+    /// ```
+    ///
+    /// To remove elements during a traversal, collect their slots first.
+    pub fn iter(&self) -> SlabIterator<'_, D, S> {
+        SlabIterator {
+            list: self,
+            front: self.head,
+            back: self.tail,
+            remaining: self.len(),
+        }
+    }
+
+    /// Take a snapshot of the slots of the list, in traversal order, that
+    /// can be walked while the list is being modified.
+    ///
+    /// Slots whose element has been removed in the meantime are skipped.
+    /// A slot reused by a new element is still returned; use versioned
+    /// slots to tell them apart.
+    pub fn robust_iter(&self) -> RobustIter<S> {
+        RobustIter {
+            slots: self.iter_slots().collect::<Vec<_>>().into_iter(),
+        }
+    }
+
+    /// Iterate over the slots of the elements, from the head to the tail,
+    /// without accessing the elements themselves.
+    pub fn iter_slots(&self) -> SlotIter<'_, D, S> {
+        SlotIter {
+            list: self,
+            front: self.head,
+            back: self.tail,
+            remaining: self.len(),
+        }
+    }
+
+    /// Iterate over the free slots, in the order they will be used by
+    /// `push_front()`.
+    pub fn free_slot_iter(&self) -> FreeSlotIter<'_, D, S> {
+        FreeSlotIter {
+            list: self,
+            slot: self.free_head,
+            remaining: self.free(),
+        }
+    }
+
+    /// Summarize how free and occupied slots are laid out in storage order.
+    ///
+    /// This is O(n), unless the crate is compiled with `releasefast` and
+    /// without `generational` or `tagged`, in which case it is O(n^2), as
+    /// `is_occupied()` is.
+    pub fn fragmentation_report(&self) -> FragmentationReport {
+        let mut report = FragmentationReport::default();
+        let mut run = 0;
+        let mut occupied = false;
+        for index in 0..self.capacity() {
+            let slot = S::from_usize(index);
+            let is_occupied = self.is_occupied(slot);
+            if index == 0 || is_occupied != occupied {
+                report.add_run(occupied, run);
+                occupied = is_occupied;
+                run = 0;
+            }
+            run += 1;
+            if is_occupied {
+                report.high_water_mark = index + 1;
+            }
+        }
+        report.add_run(occupied, run);
+        report.free_slots = self.free();
+        report
+    }
+
+    /// Return a slot chosen uniformly at random among the occupied ones, or
+    /// `None` if the list is empty.
+    ///
+    /// `random` must return uniformly distributed values in `0..bound` when
+    /// called with `bound`. Slots are drawn until an occupied one is found,
+    /// so this is O(1) expected time as long as the list is not mostly
+    /// empty; after `capacity()` misses, the list is walked instead.
+    pub fn sample(&self, mut random: impl FnMut(usize) -> usize) -> Option<S> {
+        if self.is_empty() {
             return None;
         }
-        let res = unsafe { self.list.data[slot as usize].assume_init_ref() };
-        self.slot = Some(self.list.vec_prev[slot as usize]);
-        Some(res)
+        // With `releasefast` and without `generational` nor `tagged`,
+        // occupancy checks are O(n), so walking the list is cheaper.
+        #[cfg(any(
+            not(feature = "releasefast"),
+            feature = "generational",
+            feature = "tagged"
+        ))]
+        {
+            let capacity = self.capacity();
+            for _ in 0..capacity {
+                let slot = S::from_usize(random(capacity));
+                if self.is_occupied(slot) {
+                    return Some(slot);
+                }
+            }
+        }
+        self.iter_slots().nth(random(self.len()))
     }
-}
 
-impl<'a, D> IntoIterator for &'a Slab<D> {
-    type IntoIter = SlabIterator<'a, D>;
-    type Item = &'a D;
+    /// Check if the slot contains an element.
+    ///
+    /// This is the same as `is_occupied()`, and O(n) if the crate is
+    /// compiled with `releasefast` and without `generational` or `tagged`.
+    pub fn contains_slot(&self, slot: S) -> bool {
+        self.is_occupied(slot)
+    }
 
-    fn into_iter(self) -> Self::IntoIter {
-        self.iter()
+    /// Check if the slot contains an element, in all build modes.
+    ///
+    /// This is the same as `contains_slot()`, and O(1), unless the crate is
+    /// compiled with `releasefast` and without `generational` or `tagged`.
+    /// In that case, there is no occupancy information, so the links are
+    /// followed back to the beginning of either the list or the free list,
+    /// which is O(n).
+    pub fn is_occupied(&self, slot: S) -> bool {
+        if slot.to_usize() >= self.capacity() {
+            return false;
+        }
+        #[cfg(any(not(feature = "releasefast"), feature = "tagged"))]
+        {
+            self.bitmap_get(slot)
+        }
+        #[cfg(all(
+            feature = "releasefast",
+            not(feature = "tagged"),
+            feature = "generational"
+        ))]
+        {
+            self.generations[slot.to_usize()] & 1 == 1
+        }
+        #[cfg(all(
+            feature = "releasefast",
+            not(feature = "tagged"),
+            not(feature = "generational")
+        ))]
+        {
+            let mut first = slot;
+            loop {
+                let prev = self.vec_prev[first.to_usize()];
+                if prev == S::NUL || prev == S::FREE {
+                    return first == self.head;
+                }
+                first = prev;
+            }
+        }
     }
-}
 
-#[test]
-fn test() {
-    let mut slab = Slab::with_capacity(3).unwrap();
-    let a = slab.push_front(Box::pin(1)).unwrap();
-    let b = slab.push_front(Box::pin(2)).unwrap();
-    slab.push_front(Box::pin(3)).unwrap();
-    assert_eq!(slab.len(), 3);
-    assert!(slab.push_front(Box::pin(4)).is_err());
-    slab.remove(a).unwrap();
-    slab.remove(b).unwrap();
-    assert_eq!(slab.len(), 1);
-    let cv = slab.pop_back().unwrap();
-    assert_eq!(3, *cv);
+    /// Prepend an element to the beginning of the list, and return a
+    /// versioned slot that can be used to safely access it later.
+    #[cfg(feature = "generational")]
+    pub fn push_front_versioned(&mut self, value: D) -> Result<VersionedSlot<S>, Error> {
+        let slot = self.push_front(value)?;
+        Ok(self.versioned_unchecked(slot))
+    }
+
+    /// Return the versioned slot of an element given its slot number.
+    #[cfg(feature = "generational")]
+    pub fn versioned(&self, slot: S) -> Result<VersionedSlot<S>, Error> {
+        if !self.contains_slot(slot) {
+            return Err(Error::InvalidSlot);
+        }
+        Ok(self.versioned_unchecked(slot))
+    }
+
+    /// Check if a versioned slot still references an element.
+    #[cfg(feature = "generational")]
+    pub fn contains_versioned(&self, key: VersionedSlot<S>) -> bool {
+        // Generations are odd while a slot is occupied, and even while it is free.
+        key.generation & 1 == 1
+            && (key.slot.to_usize()) < self.capacity()
+            && self.generations[key.slot.to_usize()] == key.generation
+    }
+
+    /// Return an element given its versioned slot.
+    #[cfg(feature = "generational")]
+    pub fn get_versioned(&self, key: VersionedSlot<S>) -> Result<&D, Error> {
+        if !self.contains_versioned(key) {
+            return Err(Error::InvalidSlot);
+        }
+        self.get(key.slot)
+    }
+
+    /// Return a mutable reference to an element given its versioned slot.
+    #[cfg(feature = "generational")]
+    pub fn get_versioned_mut(&mut self, key: VersionedSlot<S>) -> Result<&mut D, Error> {
+        if !self.contains_versioned(key) {
+            return Err(Error::InvalidSlot);
+        }
+        self.get_mut(key.slot)
+    }
+
+    /// Remove an element from the list given its versioned slot.
+    /// Unlike `remove()`, this is always safe, even with the `releasefast`
+    /// feature, since stale versioned slots are rejected.
+    #[cfg(feature = "generational")]
+    pub fn remove_versioned(&mut self, key: VersionedSlot<S>) -> Result<(), Error> {
+        self.take_versioned(key).map(drop)
+    }
+
+    /// Remove an element from the list given its versioned slot, and return it.
+    #[cfg(feature = "generational")]
+    pub(crate) fn take_versioned(&mut self, key: VersionedSlot<S>) -> Result<D, Error> {
+        if !self.contains_versioned(key) {
+            return Err(Error::InvalidSlot);
+        }
+        self.take(key.slot)
+    }
+
+    /// Return the slot of the element referenced by a weak handle, or `None`
+    /// if that element has been removed.
+    #[cfg(feature = "generational")]
+    pub fn upgrade(&self, weak: WeakSlot<S>) -> Option<S> {
+        Some(weak.0.slot).filter(|_| self.contains_versioned(weak.0))
+    }
+
+    /// Return the element referenced by a weak handle, or `None` if that
+    /// element has been removed.
+    #[cfg(feature = "generational")]
+    pub fn get_weak(&self, weak: WeakSlot<S>) -> Option<&D> {
+        self.get_versioned(weak.0).ok()
+    }
+
+    /// Return a mutable reference to the element referenced by a weak
+    /// handle, or `None` if that element has been removed.
+    #[cfg(feature = "generational")]
+    pub fn get_weak_mut(&mut self, weak: WeakSlot<S>) -> Option<&mut D> {
+        self.get_versioned_mut(weak.0).ok()
+    }
+
+    #[cfg(feature = "generational")]
+    #[inline]
+    fn versioned_unchecked(&self, slot: S) -> VersionedSlot<S> {
+        VersionedSlot {
+            slot,
+            generation: self.generations[slot.to_usize()],
+        }
+    }
+
+    // Return true if a slot is lower than the capacity and, unless the
+    // crate is compiled with `releasefast`, contains an element.
+    //
+    // With the `tagged` feature, `vec_prev` has an extra entry past the
+    // capacity, tagged as free, that out-of-range slots read instead of
+    // their own link. Both conditions are then checked by a single
+    // comparison on one load, with no separate bounds check, and that load
+    // is the backward link that operations changing links need anyway.
+    #[inline]
+    fn check_slot(&self, slot: S) -> bool {
+        #[cfg(all(feature = "tagged", not(feature = "releasefast")))]
+        {
+            let index = slot.to_usize().min(self.capacity());
+            invariant!(index < self.vec_prev.len());
+            unsafe { *self.vec_prev.get_unchecked(index) != S::FREE }
+        }
+        #[cfg(all(not(feature = "tagged"), not(feature = "releasefast")))]
+        {
+            slot.to_usize() < self.capacity() && self.bitmap_get(slot)
+        }
+        #[cfg(feature = "releasefast")]
+        {
+            slot.to_usize() < self.capacity()
+        }
+    }
+
+    #[cfg(not(any(feature = "releasefast", feature = "tagged")))]
+    #[inline]
+    fn bitmap_get(&self, slot: S) -> bool {
+        (self.bitmap[slot.to_usize() / 8] & (1 << (slot.to_usize() & 7))) != 0
+    }
+
+    #[cfg(not(any(feature = "releasefast", feature = "tagged")))]
+    #[inline]
+    fn bitmap_set(&mut self, slot: S) {
+        self.bitmap[slot.to_usize() / 8] |= 1 << (slot.to_usize() & 7);
+    }
+
+    #[cfg(not(any(feature = "releasefast", feature = "tagged")))]
+    #[inline]
+    fn bitmap_unset(&mut self, slot: S) {
+        self.bitmap[slot.to_usize() / 8] &= !(1 << (slot.to_usize() & 7));
+    }
+
+    // With the `tagged` feature, free slots are recognized by their backward
+    // link, which is maintained by `set_free_prev()`.
+    #[cfg(feature = "tagged")]
+    #[inline]
+    fn bitmap_get(&self, slot: S) -> bool {
+        self.vec_prev[slot.to_usize()] != S::FREE
+    }
+
+    #[cfg(all(feature = "tagged", not(feature = "releasefast")))]
+    #[inline]
+    fn bitmap_set(&mut self, _slot: S) {}
+
+    #[cfg(all(feature = "tagged", not(feature = "releasefast")))]
+    #[inline]
+    fn bitmap_unset(&mut self, _slot: S) {}
+
+    // Set the backward link of a slot of the free list. With the `tagged`
+    // feature, the free list is only singly linked, and free slots are
+    // tagged instead.
+    #[inline]
+    fn set_free_prev(&mut self, slot: S, prev: S) {
+        #[cfg(not(feature = "tagged"))]
+        {
+            self.vec_prev[slot.to_usize()] = prev;
+        }
+        #[cfg(feature = "tagged")]
+        {
+            let _ = prev;
+            self.vec_prev[slot.to_usize()] = S::FREE;
+        }
+    }
+
+    // Return the slot preceding a free slot in the free list.
+    // With the `tagged` feature, this is O(n) unless it is the first one.
+    #[inline]
+    fn free_prev(&self, slot: S) -> S {
+        #[cfg(not(feature = "tagged"))]
+        {
+            self.vec_prev[slot.to_usize()]
+        }
+        #[cfg(feature = "tagged")]
+        {
+            let mut prev = S::NUL;
+            let mut current = self.free_head;
+            while current != slot {
+                invariant_ne!(current, S::NUL);
+                prev = current;
+                current = self.vec_next[current.to_usize()];
+            }
+            prev
+        }
+    }
+}
+
+impl<D, S: SlotType> Drop for Slab<D, S> {
+    fn drop(&mut self) {
+        #[cfg(all(feature = "leak_diagnostics", debug_assertions))]
+        self.report_leaks();
+
+        // If a pending element panics when dropped, the live elements are
+        // still dropped.
+        struct Guard<'a, D, S: SlotType>(&'a mut Slab<D, S>);
+
+        impl<D, S: SlotType> Drop for Guard<'_, D, S> {
+            fn drop(&mut self) {
+                self.0.clear();
+            }
+        }
+
+        let guard = Guard(self);
+        guard.0.flush_deferred();
+    }
+}
+
+// Elements are structurally pinned, see `get_pin_mut()`.
+impl<D: Unpin, S: SlotType> Unpin for Slab<D, S> {}
+
+// Only print a bounded summary, rather than the links of every slot.
+#[cfg(feature = "realtime")]
+impl<D, S: SlotType> core::fmt::Debug for Slab<D, S> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Slab")
+            .field("len", &self.len)
+            .field("capacity", &self.capacity())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Return an element given its slot.
+///
+/// Panics if the slot doesn't contain an element, in all build modes. The
+/// slot is checked with `is_occupied()`, so this is O(n) if the crate is
+/// compiled with `releasefast` and without `generational` or `tagged`.
+impl<D, S: SlotType> core::ops::Index<S> for Slab<D, S> {
+    type Output = D;
+
+    #[track_caller]
+    fn index(&self, slot: S) -> &Self::Output {
+        match self.get_checked(slot) {
+            Ok(value) => value,
+            Err(_) => panic!("slot {slot} doesn't contain an element"),
+        }
+    }
+}
+
+/// Return a mutable reference to an element given its slot.
+///
+/// Panics if the slot doesn't contain an element, in all build modes.
+impl<D, S: SlotType> core::ops::IndexMut<S> for Slab<D, S> {
+    #[track_caller]
+    fn index_mut(&mut self, slot: S) -> &mut Self::Output {
+        match self.get_mut_checked(slot) {
+            Ok(value) => value,
+            Err(_) => panic!("slot {slot} doesn't contain an element"),
+        }
+    }
+}
+
+#[cfg(feature = "generational")]
+impl<D, S: SlotType> Slab<D, S> {
+    #[track_caller]
+    fn assert_versioned(&self, key: VersionedSlot<S>) {
+        let found = match self.generations.get(key.slot.to_usize()) {
+            Some(&generation) => generation,
+            None => panic!("slot {} out of range", key.slot),
+        };
+        if key.generation & 1 == 0 || found != key.generation {
+            panic!(
+                "stale versioned slot {}: expected generation {}, found {}",
+                key.slot, key.generation, found
+            );
+        }
+    }
+}
+
+/// Access an element given its versioned slot.
+/// Panics if the element has been removed.
+#[cfg(feature = "generational")]
+impl<D, S: SlotType> core::ops::Index<VersionedSlot<S>> for Slab<D, S> {
+    type Output = D;
+
+    #[track_caller]
+    fn index(&self, key: VersionedSlot<S>) -> &Self::Output {
+        self.assert_versioned(key);
+        unsafe { self.data[key.slot.to_usize()].assume_init_ref() }
+    }
+}
+
+#[cfg(feature = "generational")]
+impl<D, S: SlotType> core::ops::IndexMut<VersionedSlot<S>> for Slab<D, S> {
+    #[track_caller]
+    fn index_mut(&mut self, key: VersionedSlot<S>) -> &mut Self::Output {
+        self.assert_versioned(key);
+        unsafe { self.data[key.slot.to_usize()].assume_init_mut() }
+    }
+}
+
+/// A list with a capacity of `N` elements, that implements `Default`.
+///
+/// This makes it possible to embed a list in a structure deriving
+/// `Default`. It dereferences to the list.
+#[derive(Debug)]
+pub struct DefaultCapacity<D, const N: usize>(Slab<D>);
+
+impl<D, const N: usize> DefaultCapacity<D, N> {
+    /// Return the inner list.
+    pub fn into_inner(self) -> Slab<D> {
+        self.0
+    }
+}
+
+/// Fails to compile if `N` is larger than `Slab::max_capacity()`. Like the
+/// standard collections, aborts if the list can't be allocated.
+impl<D, const N: usize> Default for DefaultCapacity<D, N> {
+    fn default() -> Self {
+        const { assert!(N <= Slab::<D>::max_capacity(), "capacity is too large") };
+        match Slab::with_capacity(N) {
+            Ok(list) => Self(list),
+            Err(_) => alloc::alloc::handle_alloc_error(
+                core::alloc::Layout::array::<D>(N).unwrap_or(core::alloc::Layout::new::<D>()),
+            ),
+        }
+    }
+}
+
+impl<D, const N: usize> core::ops::Deref for DefaultCapacity<D, N> {
+    type Target = Slab<D>;
+
+    fn deref(&self) -> &Slab<D> {
+        &self.0
+    }
+}
+
+impl<D, const N: usize> core::ops::DerefMut for DefaultCapacity<D, N> {
+    fn deref_mut(&mut self) -> &mut Slab<D> {
+        &mut self.0
+    }
+}
+
+impl<D, const N: usize> From<DefaultCapacity<D, N>> for Slab<D> {
+    fn from(list: DefaultCapacity<D, N>) -> Self {
+        list.0
+    }
+}
+
+/// An iterator over the elements of a list, created by `Slab::iter()`.
+pub struct SlabIterator<'a, D, S: SlotType = Slot> {
+    list: &'a Slab<D, S>,
+    front: S,
+    back: S,
+    remaining: usize,
+}
+
+/// Hint the CPU that the cache line containing `x` will soon be read.
+#[cfg(feature = "prefetch")]
+#[inline(always)]
+fn prefetch<T>(x: &T) {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        #[cfg(target_arch = "x86")]
+        use core::arch::x86::{_mm_prefetch, _MM_HINT_T0};
+        #[cfg(target_arch = "x86_64")]
+        use core::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+
+        unsafe { _mm_prefetch::<_MM_HINT_T0>(x as *const T as *const i8) };
+    }
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+    {
+        let _ = x;
+    }
+}
+
+impl<'a, D, S: SlotType> Iterator for SlabIterator<'a, D, S> {
+    type Item = &'a D;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let slot = self.front;
+        let res = unsafe { self.list.data[slot.to_usize()].assume_init_ref() };
+        let next = self.list.vec_next[slot.to_usize()];
+        #[cfg(feature = "prefetch")]
+        {
+            if next != S::NUL {
+                prefetch(&self.list.vec_next[next.to_usize()]);
+                prefetch(&self.list.data[next.to_usize()]);
+            }
+        }
+        self.front = next;
+        self.remaining -= 1;
+        Some(res)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<D, S: SlotType> ExactSizeIterator for SlabIterator<'_, D, S> {}
+
+impl<'a, D, S: SlotType> DoubleEndedIterator for SlabIterator<'a, D, S> {
+    fn next_back(&mut self) -> Option<&'a D> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let slot = self.back;
+        let res = unsafe { self.list.data[slot.to_usize()].assume_init_ref() };
+        let prev = self.list.vec_prev[slot.to_usize()];
+        #[cfg(feature = "prefetch")]
+        {
+            if prev != S::NUL {
+                prefetch(&self.list.vec_prev[prev.to_usize()]);
+                prefetch(&self.list.data[prev.to_usize()]);
+            }
+        }
+        self.back = prev;
+        self.remaining -= 1;
+        Some(res)
+    }
+}
+
+/// An iterator over the slots of a list, created by `Slab::iter_slots()`.
+#[derive(Debug)]
+pub struct SlotIter<'a, D, S: SlotType = Slot> {
+    list: &'a Slab<D, S>,
+    front: S,
+    back: S,
+    remaining: usize,
+}
+
+impl<D, S: SlotType> Iterator for SlotIter<'_, D, S> {
+    type Item = S;
+
+    fn next(&mut self) -> Option<S> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let slot = self.front;
+        self.front = self.list.vec_next[slot.to_usize()];
+        self.remaining -= 1;
+        Some(slot)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<D, S: SlotType> DoubleEndedIterator for SlotIter<'_, D, S> {
+    fn next_back(&mut self) -> Option<S> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let slot = self.back;
+        self.back = self.list.vec_prev[slot.to_usize()];
+        self.remaining -= 1;
+        Some(slot)
+    }
+}
+
+impl<D, S: SlotType> ExactSizeIterator for SlotIter<'_, D, S> {}
+
+/// An iterator over the free slots of a list, created by
+/// `Slab::free_slot_iter()`.
+#[derive(Debug)]
+pub struct FreeSlotIter<'a, D, S: SlotType = Slot> {
+    list: &'a Slab<D, S>,
+    slot: S,
+    remaining: usize,
+}
+
+impl<D, S: SlotType> Iterator for FreeSlotIter<'_, D, S> {
+    type Item = S;
+
+    fn next(&mut self) -> Option<S> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let slot = self.slot;
+        self.slot = self.list.vec_next[slot.to_usize()];
+        self.remaining -= 1;
+        Some(slot)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<D, S: SlotType> ExactSizeIterator for FreeSlotIter<'_, D, S> {}
+
+/// A snapshot of the slots of a list, created by `Slab::robust_iter()`.
+///
+/// It doesn't borrow the list, so elements can be removed between calls
+/// to `next()`:
+///
+/// ```
+/// let mut slab = slabigator::Slab::with_capacity(3).unwrap();
+/// slab.push_front_many([1, 2, 3]).unwrap();
+/// let mut slots = slab.robust_iter();
+/// while let Some(slot) = slots.next(&slab) {
+///     if slab[slot] % 2 == 1 {
+///         slab.remove(slot).unwrap();
+///     }
+/// }
+/// assert_eq!(slab.iter().copied().collect::<Vec<_>>(), [2]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct RobustIter<S: SlotType = Slot> {
+    slots: alloc::vec::IntoIter<S>,
+}
+
+impl<S: SlotType> RobustIter<S> {
+    /// Return the next slot of the snapshot that still contains an element.
+    pub fn next<D>(&mut self, list: &Slab<D, S>) -> Option<S> {
+        self.slots.find(|&slot| list.is_occupied(slot))
+    }
+}
+
+/// An iterator over elements removed from the tail of a list.
+/// Created by `Slab::drain_back()`.
+pub struct DrainBack<'a, D, S: SlotType = Slot> {
+    list: &'a mut Slab<D, S>,
+    slot: S,
+    remaining: usize,
+}
+
+impl<D, S: SlotType> Iterator for DrainBack<'_, D, S> {
+    type Item = D;
+
+    fn next(&mut self) -> Option<D> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let slot = self.slot;
+        let value = unsafe { self.list.data[slot.to_usize()].assume_init_read() };
+        self.slot = self.list.vec_next[slot.to_usize()];
+        self.remaining -= 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<D, S: SlotType> ExactSizeIterator for DrainBack<'_, D, S> {}
+
+impl<D, S: SlotType> Drop for DrainBack<'_, D, S> {
+    fn drop(&mut self) {
+        self.for_each(drop);
+    }
+}
+
+impl<'a, D, S: SlotType> IntoIterator for &'a Slab<D, S> {
+    type IntoIter = SlabIterator<'a, D, S>;
+    type Item = &'a D;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Prepend every element of the iterator to the list, in order.
+/// Panics if the list becomes full; see `Slab::try_extend()`.
+impl<D, S: SlotType> Extend<D> for Slab<D, S> {
+    fn extend<I: IntoIterator<Item = D>>(&mut self, iter: I) {
+        for value in iter {
+            self.push_front(value).expect("list is full");
+        }
+    }
+}
+
+/// Prepend a copy of every element of the iterator to the list, in order.
+/// Panics if the list becomes full; see `Slab::try_extend()`.
+impl<'a, D: Copy + 'a, S: SlotType> Extend<&'a D> for Slab<D, S> {
+    fn extend<I: IntoIterator<Item = &'a D>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+#[test]
+fn test() {
+    let mut slab = Slab::with_capacity(3).unwrap();
+    let a = slab.push_front(Box::pin(1)).unwrap();
+    let b = slab.push_front(Box::pin(2)).unwrap();
+    slab.push_front(Box::pin(3)).unwrap();
+    assert_eq!(slab.len(), 3);
+    assert!(slab.push_front(Box::pin(4)).is_err());
+    slab.remove(a).unwrap();
+    slab.remove(b).unwrap();
+    assert_eq!(slab.len(), 1);
+    let cv = slab.pop_back().unwrap();
+    assert_eq!(3, *cv);
+}
+
+#[test]
+fn test_max_capacity() {
+    const MAX: usize = Slab::<()>::max_capacity();
+    assert_eq!(MAX as u128, (Slot::MAX as u128 - 1).min(usize::MAX as u128));
+    if let Some(too_large) = MAX.checked_add(1) {
+        assert_eq!(
+            Slab::<()>::with_capacity(too_large).err(),
+            Some(Error::TooLarge)
+        );
+    }
+
+    fn check_boundary<S: SlotType>() {
+        assert_eq!(
+            Slab::<(), S>::max_capacity() as u128,
+            (S::NUL.to_usize() as u128 - 1).min(usize::MAX as u128)
+        );
+        if let Some(too_large) = S::MAX_CAPACITY.checked_add(1) {
+            assert_eq!(
+                Slab::<(), S>::with_slot_type(too_large).err(),
+                Some(Error::TooLarge)
+            );
+        }
+    }
+    check_boundary::<u16>();
+    check_boundary::<u32>();
+    check_boundary::<u64>();
+    check_boundary::<usize>();
+
+    // The largest `u16` list can be allocated, and its last slot is valid.
+    let mut slab = Slab::<u8, u16>::with_slot_type(u16::MAX as usize - 1).unwrap();
+    let last = u16::MAX - 2;
+    assert_eq!(slab.insert_at(u16::MAX - 1, 0), Err(Error::InvalidSlot));
+    slab.insert_at(last, 1).unwrap();
+    assert_eq!(slab.get(last), Ok(&1));
+    assert_eq!(slab.pop_back(), Some(1));
+
+    // Storage whose size overflows, or that can't be allocated.
+    assert_eq!(
+        Slab::<[u8; 1 << 20], u64>::with_slot_type(1 << 50).err(),
+        Some(Error::TooLarge)
+    );
+    #[cfg(target_pointer_width = "64")]
+    assert_eq!(
+        Slab::<u8, u64>::with_slot_type(1 << 59).err(),
+        Some(Error::OutOfMemory)
+    );
+}
+
+#[test]
+fn test_default_capacity() {
+    #[derive(Default)]
+    struct Queues {
+        jobs: DefaultCapacity<u32, 3>,
+        empty: DefaultCapacity<u32, 0>,
+    }
+
+    let mut queues = Queues::default();
+    assert_eq!(queues.jobs.capacity(), 3);
+    assert!(queues.empty.push_front(1).is_err());
+    queues.jobs.push_front(1).unwrap();
+    let jobs: Slab<u32> = queues.jobs.into();
+    assert_eq!(jobs.len(), 1);
+}
+
+#[cfg(feature = "realtime")]
+#[test]
+fn test_realtime_debug() {
+    let mut slab = Slab::with_capacity(1000).unwrap();
+    slab.push_front(1).unwrap();
+    assert_eq!(format!("{slab:?}"), "Slab { len: 1, capacity: 1000, .. }");
+}
+
+#[cfg(not(any(feature = "slot_u64", feature = "slot_usize")))]
+#[test]
+fn test_header_layout() {
+    use core::mem::{offset_of, size_of};
+
+    let hot_end = offset_of!(Slab<u64>, data) + size_of::<Box<[MaybeUninit<u64>]>>();
+    assert!(hot_end <= 64);
+}
+
+#[test]
+fn test_slot_type() {
+    assert_eq!(Slab::<u8, u16>::max_capacity(), 65534);
+    assert!(Slab::<u8, u16>::with_slot_type(65535).is_err());
+
+    let mut small = Slab::<_, u16>::with_slot_type(3).unwrap();
+    let mut large = Slab::<_, u64>::with_slot_type(3).unwrap();
+    let a: u16 = small.push_front('a').unwrap();
+    let b: u64 = large.push_front('b').unwrap();
+    small.push_front('c').unwrap();
+    large.push_front_many(['d', 'e']).unwrap();
+    assert_eq!(small[a], 'a');
+    assert_eq!(large.get(b), Ok(&'b'));
+    small.remove(a).unwrap();
+    #[cfg(not(feature = "releasefast"))]
+    assert!(small.get(a).is_err());
+    assert_eq!(large.pop_back(), Some('b'));
+    assert_eq!(small.iter().copied().collect::<Vec<_>>(), ['c']);
+    assert_eq!(large.iter().rev().copied().collect::<Vec<_>>(), ['d', 'e']);
+    assert_eq!(large.iter_slots().collect::<Vec<u64>>(), [2, 1]);
+    assert!(core::mem::size_of::<Slab<u8, u16>>() < core::mem::size_of::<Slab<u8, u64>>());
+}
+
+#[test]
+fn test_move_to_front() {
+    let mut slab = Slab::with_capacity(3).unwrap();
+    let a = slab.push_front(1).unwrap();
+    let b = slab.push_front(2).unwrap();
+    let c = slab.push_front(3).unwrap();
+    slab.move_to_front(a).unwrap();
+    assert_eq!(slab.iter().copied().collect::<Vec<_>>(), [1, 3, 2]);
+    slab.move_to_front(c).unwrap();
+    assert_eq!(slab.iter().copied().collect::<Vec<_>>(), [3, 1, 2]);
+    slab.move_to_front(c).unwrap();
+    assert_eq!(slab.iter().rev().copied().collect::<Vec<_>>(), [2, 1, 3]);
+    slab.remove(b).unwrap();
+    #[cfg(not(feature = "releasefast"))]
+    assert!(slab.move_to_front(b).is_err());
+    assert_eq!(slab.pop_back(), Some(1));
+    assert_eq!(slab.pop_back(), Some(3));
+}
+
+#[test]
+fn test_min_max_by() {
+    let mut slab = Slab::with_capacity(4).unwrap();
+    assert_eq!(slab.min_by_key(|&x: &(u32, char)| x.0), None);
+    let a = slab.push_front((3, 'a')).unwrap();
+    let b = slab.push_front((1, 'b')).unwrap();
+    let c = slab.push_front((3, 'c')).unwrap();
+    let d = slab.push_front((1, 'd')).unwrap();
+    assert_eq!(slab.min_by_key(|x| x.0), Some((d, &(1, 'd'))));
+    assert_eq!(slab.max_by_key(|x| x.0), Some((a, &(3, 'a'))));
+    assert_eq!(slab.min_by(|x, y| x.0.cmp(&y.0)), Some((d, &(1, 'd'))));
+    assert_eq!(slab.max_by(|x, y| x.1.cmp(&y.1)), Some((d, &(1, 'd'))));
+    slab.remove(d).unwrap();
+    assert_eq!(slab.min_by_key(|x| x.0).map(|(slot, _)| slot), Some(b));
+    assert_eq!(
+        slab.max_by(|x, y| x.1.cmp(&y.1)).map(|(slot, _)| slot),
+        Some(c)
+    );
+}
+
+#[test]
+fn test_update() {
+    let mut slab = Slab::with_capacity(2).unwrap();
+    let a = slab.push_front(vec![1]).unwrap();
+    assert_eq!(slab.update(a, |v| v.push(2)), Ok(()));
+    assert_eq!(slab.map_slot(a, |v| v.len()), Ok(2));
+    assert_eq!(slab.update(2, |v| v.pop()), Err(Error::InvalidSlot));
+    slab.remove(a).unwrap();
+    #[cfg(not(feature = "releasefast"))]
+    assert_eq!(slab.map_slot(a, |v| v.len()), Err(Error::InvalidSlot));
+}
+
+#[test]
+fn test_get_or_insert_at() {
+    let mut slab = Slab::with_capacity(3).unwrap();
+    *slab.get_or_insert_at(2, || 10).unwrap() += 1;
+    *slab.get_or_insert_at(2, || 20).unwrap() += 1;
+    assert_eq!(slab.get(2), Ok(&12));
+    assert_eq!(slab.get_or_insert_at(0, || 30), Ok(&mut 30));
+    assert_eq!(slab.get_or_insert_at(3, || 40), Err(Error::InvalidSlot));
+    assert_eq!(slab.iter().copied().collect::<Vec<_>>(), [30, 12]);
+    assert_eq!(slab.push_front(50), Ok(1));
+    assert!(slab.is_full());
+}
+
+#[test]
+fn test_for_each_removable() {
+    let mut slab = Slab::with_capacity(6).unwrap();
+    let slots = slab.push_front_many([1, 2, 3, 4, 5, 6]).unwrap();
+    let mut visited = Vec::new();
+    let removed = slab.for_each_removable(|slot, value| {
+        visited.push(slot);
+        *value *= 10;
+        if *value % 20 == 0 {
+            Visit::Remove
+        } else {
+            Visit::Keep
+        }
+    });
+    assert_eq!(removed, 3);
+    assert_eq!(visited, slots.iter().rev().copied().collect::<Vec<_>>());
+    assert_eq!(slab.iter().copied().collect::<Vec<_>>(), [50, 30, 10]);
+    assert_eq!(slab.for_each_removable(|_, _| Visit::Remove), 3);
+    assert!(slab.is_empty());
+    assert_eq!(slab.free_slot_iter().count(), 6);
+}
+
+#[test]
+fn test_error_categories() {
+    assert!(Error::Full.is_capacity() && Error::Full.is_retryable());
+    assert!(Error::TooLarge.is_capacity() && Error::TooLarge.is_fatal());
+    assert!(Error::InvalidSlot.is_usage_bug() && Error::InvalidSlot.is_fatal());
+    assert!(Error::Empty.is_retryable() && !Error::Empty.is_capacity());
+    assert!(Error::Borrowed.is_retryable() && !Error::Borrowed.is_usage_bug());
+    assert!(Error::OutOfMemory.is_capacity() && Error::OutOfMemory.is_fatal());
+    assert!(Error::Misaligned.is_usage_bug() && !Error::Misaligned.is_capacity());
+
+    #[cfg(feature = "std")]
+    {
+        let err = std::io::Error::from(Error::Full);
+        assert_eq!(err.kind(), std::io::ErrorKind::WouldBlock);
+        assert_eq!(err.to_string(), "Full");
+        assert_eq!(
+            std::io::ErrorKind::from(Error::InvalidSlot),
+            std::io::ErrorKind::NotFound
+        );
+    }
+}
+
+#[cfg(not(feature = "releasefast"))]
+#[test]
+fn test_remove_if_present() {
+    let mut slab = Slab::with_capacity(2).unwrap();
+    let a = slab.push_front('a').unwrap();
+    let b = slab.push_front('b').unwrap();
+    assert_eq!(slab.pop_back(), Some('a'));
+    assert_eq!(slab.remove_if_present(a), None);
+    assert_eq!(slab.remove_if_present(b), Some('b'));
+    assert_eq!(slab.remove_if_present(b), None);
+    assert_eq!(slab.remove_if_present(2), None);
+    assert!(slab.is_empty());
+}
+
+#[cfg(feature = "generational")]
+#[test]
+fn test_versioned_wire() {
+    let mut slab = Slab::<_, u32>::with_slot_type(3).unwrap();
+    slab.push_front(1).unwrap();
+    let key = slab.push_front_versioned(2).unwrap();
+    let bytes = key.to_le_bytes();
+    assert_eq!(bytes, [1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0]);
+    assert_eq!(VersionedSlot::from_le_bytes(bytes), Ok(key));
+    let wide = VersionedSlot::<u64>::from_le_bytes(bytes).unwrap();
+    assert_eq!((wide.slot(), wide.generation()), (1, 1));
+
+    let packed = key.to_u64();
+    assert_eq!(packed, 1 << 32 | 1);
+    assert_eq!(VersionedSlot::from_u64(packed), Ok(key));
+    assert_eq!(
+        VersionedSlot::from_u64(2 << 32 | 1),
+        Err(Error::InvalidSlot)
+    );
+    assert_eq!(
+        VersionedSlot::<u16>::from_le_bytes([0xff, 0xff, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0]),
+        Err(Error::InvalidSlot)
+    );
+
+    slab.remove_versioned(key).unwrap();
+    let stale = VersionedSlot::from_le_bytes(bytes).unwrap();
+    assert!(!slab.contains_versioned(stale));
+}
+
+#[test]
+fn test_remove_deferred() {
+    use std::rc::Rc;
+
+    let counter = Rc::new(());
+    let mut slab = Slab::with_capacity(3).unwrap();
+    let a = slab.push_front(counter.clone()).unwrap();
+    let b = slab.push_front(counter.clone()).unwrap();
+    let c = slab.push_front(counter.clone()).unwrap();
+    slab.remove_deferred(b).unwrap();
+    slab.remove_deferred(a).unwrap();
+    assert_eq!(slab.remove_deferred(a), Err(Error::InvalidSlot));
+    assert_eq!(slab.remove(b), Err(Error::InvalidSlot));
+    #[cfg(not(feature = "releasefast"))]
+    assert!(slab.get(b).is_err());
+    assert!(!slab.is_occupied(a));
+    assert_eq!(slab.iter_slots().collect::<Vec<_>>(), [c]);
+    assert_eq!(slab.len(), 1);
+    assert_eq!(slab.deferred_len(), 2);
+    assert_eq!(Rc::strong_count(&counter), 4);
+
+    // Pending slots are not reused until the elements are dropped.
+    assert_eq!(slab.free(), 0);
+    assert!(slab.is_full());
+    assert_eq!(slab.push_front(counter.clone()), Err(Error::Full));
+    assert_eq!(slab.insert_at(a, counter.clone()), Err(Error::InvalidSlot));
+    assert!(slab.get_or_insert_at(b, || counter.clone()).is_err());
+
+    assert_eq!(slab.flush_deferred(), 2);
+    assert_eq!(slab.flush_deferred(), 0);
+    assert_eq!(Rc::strong_count(&counter), 2);
+    assert_eq!(slab.free(), 2);
+    assert_eq!(slab.free_slot_iter().count(), 2);
+    slab.insert_at(b, counter.clone()).unwrap();
+    slab.push_front(counter.clone()).unwrap();
+    assert!(slab.is_full());
+
+    slab.remove_deferred(c).unwrap();
+    slab.clear();
+    assert_eq!(Rc::strong_count(&counter), 2);
+    drop(slab);
+    assert_eq!(Rc::strong_count(&counter), 1);
+}
+
+#[test]
+fn test_swap_data() {
+    let mut slab = Slab::with_capacity(3).unwrap();
+    let a = slab.push_front('a').unwrap();
+    let b = slab.push_front('b').unwrap();
+    let c = slab.push_front('c').unwrap();
+    slab.swap_data(a, c).unwrap();
+    assert_eq!(slab.iter_slots().collect::<Vec<_>>(), [c, b, a]);
+    assert_eq!(slab.iter().copied().collect::<Vec<_>>(), ['a', 'b', 'c']);
+    assert_eq!(slab[c], 'a');
+    slab.swap_data(b, b).unwrap();
+    assert_eq!(slab[b], 'b');
+    assert_eq!(slab.swap_data(a, 3), Err(Error::InvalidSlot));
+    slab.remove(b).unwrap();
+    #[cfg(not(feature = "releasefast"))]
+    assert_eq!(slab.swap_data(a, b), Err(Error::InvalidSlot));
+}
+
+#[test]
+fn test_pop_back_if() {
+    let mut slab = Slab::with_capacity(4).unwrap();
+    for timestamp in [10, 20, 30, 40] {
+        slab.push_front(timestamp).unwrap();
+    }
+    assert_eq!(slab.pop_back_if(|&t| t < 10), None);
+    assert_eq!(slab.pop_back_if(|&t| t < 15), Some(10));
+    assert_eq!(slab.evict_while(|&t| t < 35), 2);
+    assert_eq!(slab.evict_while(|&t| t < 100), 1);
+    assert_eq!(slab.pop_back_if(|_| true), None);
+}
+
+#[test]
+fn test_evict_some() {
+    let mut slab = Slab::with_capacity(5).unwrap();
+    for t in [10, 20, 30, 40, 50] {
+        slab.push_front(t).unwrap();
+    }
+    assert_eq!(slab.evict_some(2, |&t| t < 45), Sweep::Pending(2));
+    assert_eq!(slab.evict_some(2, |&t| t < 45), Sweep::Done(2));
+    assert_eq!(slab.evict_some(2, |&t| t < 45), Sweep::Done(0));
+    assert_eq!(slab.evict_some(0, |&t| t < 100), Sweep::Pending(0));
+    assert_eq!(slab.evict_some(1, |&t| t < 100), Sweep::Done(1));
+    assert!(slab.is_empty());
+}
+
+#[test]
+fn test_truncate() {
+    let mut slab = Slab::with_capacity(4).unwrap();
+    for i in 0..4 {
+        slab.push_front(i).unwrap();
+    }
+    slab.truncate(5);
+    assert_eq!(slab.len(), 4);
+    slab.truncate(1);
+    assert_eq!(slab.iter().copied().collect::<Vec<_>>(), [3]);
+    slab.truncate(0);
+    assert!(slab.is_empty());
+}
+
+#[test]
+fn test_push_front_many() {
+    let mut slab = Slab::with_capacity(3).unwrap();
+    let [a, b] = slab.push_front_many(["a", "b"]).unwrap();
+    assert_eq!(slab[a], "a");
+    assert_eq!(slab[b], "b");
+    assert_eq!(slab.push_front_many(["c", "d"]), Err(Error::Full));
+    assert_eq!(slab.len(), 2);
+    assert_eq!(slab.iter().copied().collect::<Vec<_>>(), ["b", "a"]);
+}
+
+#[test]
+fn test_get_many() {
+    let mut slab = Slab::with_capacity(3).unwrap();
+    let [a, b, c] = slab.push_front_many([1, 2, 3]).unwrap();
+    assert_eq!(slab.get_many([c, a]), Ok([&3, &1]));
+    let results = slab.get_many_iter([b, NUL]).collect::<Vec<_>>();
+    assert_eq!(results, [Ok(&2), Err(Error::InvalidSlot)]);
+    #[cfg(not(feature = "releasefast"))]
+    {
+        slab.remove(b).unwrap();
+        assert_eq!(slab.get_many([a, b]), Err(Error::InvalidSlot));
+    }
+}
+
+#[test]
+fn test_positions() {
+    let mut slab = Slab::with_capacity(5).unwrap();
+    let slots = slab.push_front_many([0, 1, 2, 3, 4]).unwrap();
+    for (i, &slot) in slots.iter().rev().enumerate() {
+        assert_eq!(slab.position_of(slot), Ok(i));
+        assert_eq!(slab.nth_slot(i), Some(slot));
+    }
+    assert_eq!(slab.nth_slot(5), None);
+    slab.remove(slots[2]).unwrap();
+    assert_eq!(slab.position_of(slots[1]), Ok(2));
+    assert_eq!(slab.nth_slot(3), Some(slots[0]));
+    #[cfg(not(feature = "releasefast"))]
+    assert_eq!(slab.position_of(slots[2]), Err(Error::InvalidSlot));
+}
+
+#[test]
+fn test_reverse() {
+    let mut slab = Slab::with_capacity(4).unwrap();
+    slab.reverse();
+    assert!(slab.is_empty());
+    let slots = slab.push_front_many([1, 2, 3]).unwrap();
+    slab.reverse();
+    assert_eq!(slab.iter().copied().collect::<Vec<_>>(), [1, 2, 3]);
+    assert_eq!(slab.pop_back(), Some(3));
+    slab.push_front(0).unwrap();
+    assert_eq!(slab.iter().rev().copied().collect::<Vec<_>>(), [2, 1, 0]);
+    slab.remove(slots[1]).unwrap();
+    assert_eq!(slab.iter().copied().collect::<Vec<_>>(), [0, 1]);
+}
+
+#[test]
+fn test_sort_by() {
+    use rand::prelude::*;
+
+    let mut rng = rand::thread_rng();
+    for len in 0..40 {
+        let mut slab = Slab::with_capacity(40).unwrap();
+        let mut model = Vec::new();
+        for i in 0..len {
+            let value = (rng.gen_range(0..10), i);
+            model.push((slab.push_front(value).unwrap(), value));
+        }
+        slab.sort_by(|a, b| a.0.cmp(&b.0));
+        model.reverse();
+        model.sort_by_key(|&(_, value)| value.0);
+        assert_eq!(
+            slab.iter().copied().collect::<Vec<_>>(),
+            model.iter().map(|&(_, value)| value).collect::<Vec<_>>()
+        );
+        assert_eq!(slab.iter().rev().count(), len);
+        for &(slot, value) in &model {
+            assert_eq!(slab.get(slot), Ok(&value));
+        }
+        assert_eq!(slab.pop_back(), model.last().map(|&(_, value)| value));
+    }
+}
+
+#[test]
+fn test_dedup_by_key() {
+    let mut slab = Slab::with_capacity(8).unwrap();
+    assert_eq!(slab.dedup_by_key(|&x: &i32| x), 0);
+    let slots = slab.push_front_many([3, 3, 2, 1, 1, 1, 3, 2]).unwrap();
+    assert_eq!(slab.dedup_by_key(|&x| x), 3);
+    assert_eq!(slab.len(), 5);
+    assert_eq!(slab.iter().copied().collect::<Vec<_>>(), [2, 3, 1, 2, 3]);
+    assert_eq!(slab.get(slots[7]), Ok(&2));
+    #[cfg(not(feature = "releasefast"))]
+    assert_eq!(slab.get(slots[4]), Err(Error::InvalidSlot));
+    assert_eq!(slab.dedup_by_key(|&x| x % 2), 1);
+    assert_eq!(slab.iter().copied().collect::<Vec<_>>(), [2, 3, 2, 3]);
+}
+
+#[test]
+fn test_rotate() {
+    let mut slab = Slab::with_capacity(3).unwrap();
+    assert_eq!(slab.rotate_back_to_front(), None);
+    assert_eq!(slab.rotate_front_to_back(), None);
+    let a = slab.push_front('a').unwrap();
+    assert_eq!(slab.rotate_back_to_front(), Some(a));
+    let [b, c] = slab.push_front_many(['b', 'c']).unwrap();
+    assert_eq!(slab.rotate_back_to_front(), Some(a));
+    assert_eq!(slab.iter().copied().collect::<String>(), "acb");
+    assert_eq!(slab.rotate_front_to_back(), Some(a));
+    assert_eq!(slab.rotate_front_to_back(), Some(c));
+    assert_eq!(slab.iter().copied().collect::<String>(), "bac");
+    assert_eq!(slab.iter().rev().copied().collect::<String>(), "cab");
+    assert_eq!(slab.pop_back(), Some('c'));
+    assert_eq!(slab.get(b), Ok(&'b'));
+}
+
+#[cfg(feature = "generational")]
+#[test]
+fn test_weak_slot() {
+    let mut slab = Slab::with_capacity(1).unwrap();
+    let key = slab.push_front_versioned(1).unwrap();
+    let weak = key.downgrade();
+    assert_eq!(slab.upgrade(weak), Some(key.slot()));
+    *slab.get_weak_mut(weak).unwrap() += 1;
+    assert_eq!(slab.get_weak(weak), Some(&2));
+    slab.remove_versioned(key).unwrap();
+    let other = slab.push_front(3).unwrap();
+    assert_eq!(other, key.slot());
+    assert_eq!(slab.upgrade(weak), None);
+    assert_eq!(slab.get_weak(weak), None);
+}
+
+#[cfg(feature = "generational")]
+#[test]
+#[should_panic(expected = "stale versioned slot 0: expected generation 1, found 3")]
+fn test_index_versioned() {
+    let mut slab = Slab::with_capacity(1).unwrap();
+    let key = slab.push_front_versioned(1).unwrap();
+    slab[key] += 1;
+    assert_eq!(slab[key], 2);
+    slab.remove_versioned(key).unwrap();
+    slab.push_front(3).unwrap();
+    let _ = slab[key];
+}
+
+#[test]
+#[should_panic(expected = "slot 1 doesn't contain an element")]
+fn test_index_freed_slot() {
+    let mut slab = Slab::with_capacity(3).unwrap();
+    let [a, b] = slab.push_front_many([1, 2]).unwrap();
+    slab[a] += 10;
+    assert_eq!(slab[a], 11);
+    slab.remove(b).unwrap();
+    let _ = slab[b];
+}
+
+#[test]
+fn test_is_occupied() {
+    let mut slab = Slab::with_capacity(4).unwrap();
+    assert!(!slab.is_occupied(0));
+    let [a, b, c] = slab.push_front_many([1, 2, 3]).unwrap();
+    slab.remove(b).unwrap();
+    assert_eq!(slab.pop_back(), Some(1));
+    assert!(!slab.is_occupied(a));
+    assert!(!slab.is_occupied(b));
+    assert!(slab.is_occupied(c));
+    assert!(!slab.is_occupied(4));
+    slab.push_front(4).unwrap();
+    assert_eq!(
+        (0..4).filter(|&slot| slab.is_occupied(slot)).count(),
+        slab.len()
+    );
+}
+
+#[test]
+fn test_check_slot() {
+    let mut slab = Slab::<u32>::with_capacity(0).unwrap();
+    assert_eq!(slab.get(0), Err(Error::InvalidSlot));
+    assert_eq!(slab.move_to_front(Slot::MAX - 1), Err(Error::InvalidSlot));
+
+    let mut slab = Slab::<_, u16>::with_slot_type(2).unwrap();
+    let [a, b] = slab.push_front_many([1, 2]).unwrap();
+    for slot in [2, 3, u16::MAX - 1, u16::MAX] {
+        assert_eq!(slab.get(slot), Err(Error::InvalidSlot));
+        assert_eq!(slab.get_mut(slot), Err(Error::InvalidSlot));
+        assert_eq!(slab.move_to_front(slot), Err(Error::InvalidSlot));
+        assert_eq!(slab.remove(slot), Err(Error::InvalidSlot));
+    }
+    slab.remove(a).unwrap();
+    #[cfg(not(feature = "releasefast"))]
+    {
+        assert_eq!(slab.get(a), Err(Error::InvalidSlot));
+        assert_eq!(slab.move_to_front(a), Err(Error::InvalidSlot));
+    }
+    assert_eq!(slab.get(b), Ok(&2));
+
+    let parts = slab.into_raw_parts();
+    assert_eq!(parts.prev.len(), 2);
+    let slab = unsafe { Slab::from_raw_parts_checked(parts) }.unwrap();
+    assert_eq!(slab.get(2), Err(Error::InvalidSlot));
+    assert_eq!(slab.get(b), Ok(&2));
+}
+
+#[test]
+fn test_unchecked() {
+    let mut slab = Slab::with_capacity(2).unwrap();
+    let a = unsafe { slab.push_front_unchecked(1) };
+    let b = unsafe { slab.push_front_unchecked(2) };
+    assert!(slab.is_full());
+    unsafe {
+        *slab.get_unchecked_mut(a) += 10;
+        assert_eq!(slab.get_unchecked(a), &11);
+        assert_eq!(slab.remove_unchecked(b), 2);
+    }
+    assert!(!slab.is_occupied(b));
+    assert_eq!(slab.iter().copied().collect::<Vec<_>>(), [11]);
+}
+
+#[test]
+fn test_to_vec() {
+    let mut slab = Slab::with_capacity(4).unwrap();
+    let [a, b, c] = slab.push_front_many(["a", "b", "c"]).unwrap();
+    slab.remove(b).unwrap();
+    assert_eq!(slab.to_vec(), ["c", "a"]);
+    assert_eq!(slab.to_vec_with_slots(), [(c, "c"), (a, "a")]);
+    assert_eq!(slab.len(), 2);
+}
+
+#[test]
+fn test_extend() {
+    let mut slab = Slab::with_capacity(4).unwrap();
+    slab.extend([1, 2]);
+    slab.extend([3, 4].iter());
+    assert_eq!(slab.to_vec(), [4, 3, 2, 1]);
+    assert!(std::panic::catch_unwind(move || slab.extend(&[5])).is_err());
+
+    let mut slab = Slab::with_capacity(3).unwrap();
+    slab.try_extend([1, 2]).unwrap();
+    let mut rest = [3, 4, 5].into_iter();
+    assert_eq!(slab.try_extend(&mut rest), Err(Error::Full));
+    assert_eq!(slab.to_vec(), [3, 2, 1]);
+    assert_eq!(rest.collect::<Vec<_>>(), [5]);
+    assert_eq!(slab.try_extend([]), Ok(()));
+}
+
+#[test]
+fn test_into_vec() {
+    use std::rc::Rc;
+
+    let value = Rc::new(());
+    let mut slab = Slab::with_capacity(4).unwrap();
+    let [_, b, _] = slab
+        .push_front_many([0, 1, 2].map(|i| (i, value.clone())))
+        .unwrap();
+    slab.remove(b).unwrap();
+    let values = slab.into_vec();
+    assert_eq!(values.iter().map(|v| v.0).collect::<Vec<_>>(), [2, 0]);
+    assert_eq!(Rc::strong_count(&value), 3);
+    drop(values);
+    assert_eq!(Rc::strong_count(&value), 1);
+}
+
+#[test]
+fn test_clone_compacted() {
+    let mut slab = Slab::with_capacity(5).unwrap();
+    let slots = slab.push_front_many(['a', 'b', 'c', 'd', 'e']).unwrap();
+    slab.remove(slots[3]).unwrap();
+    slab.remove(slots[0]).unwrap();
+    slab.move_to_front(slots[1]).unwrap();
+    let (compacted, remap) = slab.clone_compacted().unwrap();
+    assert_eq!(compacted.capacity(), 5);
+    assert_eq!(
+        compacted.iter().collect::<Vec<_>>(),
+        slab.iter().collect::<Vec<_>>()
+    );
+    for (new, old) in [(0, slots[1]), (1, slots[4]), (2, slots[2])] {
+        assert_eq!(remap.map(old), Some(new));
+        assert_eq!(compacted.get(new), slab.get(old));
+    }
+    assert_eq!(remap.map(slots[0]), None);
+    assert_eq!(remap.map(5), None);
+    assert_eq!(remap.len(), 3);
+    assert_eq!(
+        remap.iter().collect::<Vec<_>>(),
+        [(slots[1], 0), (slots[2], 2), (slots[4], 1)]
+    );
+    let mut index = [slots[4], slots[3]];
+    assert!(remap.apply(&mut index[0]));
+    assert!(!remap.apply(&mut index[1]));
+    assert_eq!(index, [1, slots[3]]);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_slot_remap_serde() {
+    let mut slab = Slab::with_capacity(4).unwrap();
+    let [a, b, c] = slab.push_front_many([1, 2, 3]).unwrap();
+    slab.remove(b).unwrap();
+    let (_, remap) = slab.clone_compacted().unwrap();
+    let json = serde_json::to_string(&remap).unwrap();
+    let decoded: SlotRemap = serde_json::from_str(&json).unwrap();
+    assert_eq!(decoded, remap);
+    assert_eq!(decoded.map(c), Some(0));
+    assert_eq!(decoded.map(a), Some(1));
+    assert_eq!(decoded.map(b), None);
+}
+
+#[test]
+fn test_peek_next_free() {
+    let mut slab = Slab::with_capacity(2).unwrap();
+    for _ in 0..2 {
+        let next = slab.peek_next_free().unwrap();
+        assert_eq!(slab.push_front(()), Ok(next));
+    }
+    assert_eq!(slab.peek_next_free(), None);
+    slab.pop_back().unwrap();
+    let next = slab.peek_next_free().unwrap();
+    assert_eq!(slab.push_front(()), Ok(next));
+}
+
+#[test]
+fn test_insert_at() {
+    let mut slab = Slab::with_capacity(4).unwrap();
+    slab.insert_at(2, 'c').unwrap();
+    slab.insert_at(0, 'a').unwrap();
+    slab.insert_at(3, 'd').unwrap();
+    assert_eq!(slab.insert_at(2, 'x'), Err(Error::InvalidSlot));
+    assert_eq!(slab.insert_at(4, 'x'), Err(Error::InvalidSlot));
+    assert_eq!(slab.iter().collect::<String>(), "dac");
+    assert_eq!(slab.push_front('b'), Ok(1));
+    assert!(slab.is_full());
+    slab.remove(0).unwrap();
+    slab.remove(3).unwrap();
+    slab.insert_at(3, 'e').unwrap();
+    assert_eq!(slab.push_front('f'), Ok(0));
+    assert_eq!(slab.iter().collect::<String>(), "febc");
+    assert_eq!(slab.iter().rev().collect::<String>(), "cbef");
+}
+
+#[test]
+fn test_swap_contents() {
+    let mut front = Slab::with_capacity(2).unwrap();
+    let mut back = Slab::with_capacity(3).unwrap();
+    let a = front.push_front("a").unwrap();
+    back.push_front_many(["x", "y", "z"]).unwrap();
+    front.swap_contents(&mut back);
+    assert_eq!(front.capacity(), 3);
+    assert_eq!(front.iter().copied().collect::<Vec<_>>(), ["z", "y", "x"]);
+    assert_eq!(back.get(a), Ok(&"a"));
+    assert_eq!(back.push_front("b"), Ok(1));
+}
+
+#[test]
+fn test_clear_panic_safety() {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::rc::Rc;
+
+    struct Bomb(#[allow(dead_code)] Rc<()>, bool);
+
+    impl Drop for Bomb {
+        fn drop(&mut self) {
+            if self.1 {
+                panic!("boom");
+            }
+        }
+    }
+
+    let counter = Rc::new(());
+    let mut slab = Slab::with_capacity(4).unwrap();
+    for explode in [false, true, false, false] {
+        slab.push_front(Bomb(counter.clone(), explode)).unwrap();
+    }
+    assert!(catch_unwind(AssertUnwindSafe(|| slab.clear())).is_err());
+    assert!(slab.is_empty());
+    assert_eq!(Rc::strong_count(&counter), 1);
+    slab.push_front(Bomb(counter.clone(), false)).unwrap();
+    assert_eq!(slab.iter().count(), 1);
+
+    slab.push_front(Bomb(counter.clone(), true)).unwrap();
+    slab.push_front(Bomb(counter.clone(), false)).unwrap();
+    assert!(catch_unwind(AssertUnwindSafe(|| drop(slab))).is_err());
+    assert_eq!(Rc::strong_count(&counter), 1);
+
+    let mut slab = Slab::with_capacity(4).unwrap();
+    let pending = slab.push_front(Bomb(counter.clone(), true)).unwrap();
+    slab.push_front(Bomb(counter.clone(), false)).unwrap();
+    slab.remove_deferred(pending).unwrap();
+    assert!(catch_unwind(AssertUnwindSafe(|| drop(slab))).is_err());
+    assert_eq!(Rc::strong_count(&counter), 1);
+}
+
+#[test]
+fn test_get_with_neighbors() {
+    let mut slab = Slab::with_capacity(4).unwrap();
+    let [a, b, c] = slab.push_front_many([1, 2, 3]).unwrap();
+    assert_eq!(slab.get_with_neighbors(b), Ok((Some(&3), &2, Some(&1))));
+    assert_eq!(slab.get_with_neighbors(c), Ok((None, &3, Some(&2))));
+    slab.remove(b).unwrap();
+    assert_eq!(slab.get_with_neighbors(a), Ok((Some(&3), &1, None)));
+    assert_eq!(slab.get_with_neighbors(4), Err(Error::InvalidSlot));
+}
+
+#[test]
+fn test_fragmentation_report() {
+    let mut slab = Slab::with_capacity(8).unwrap();
+    assert_eq!(slab.free_slot_iter().len(), 8);
+    assert!(slab.fragmentation_report().is_compact());
+    let slots: Vec<_> = (0..6).map(|i| slab.push_front(i).unwrap()).collect();
+    assert!(slab.fragmentation_report().is_compact());
+    slab.remove(slots[1]).unwrap();
+    slab.remove(slots[2]).unwrap();
+    slab.remove(slots[4]).unwrap();
+    assert_eq!(slab.free_slot_iter().collect::<Vec<_>>(), [4, 2, 1, 6, 7]);
+    let report = slab.fragmentation_report();
+    assert_eq!(
+        report,
+        FragmentationReport {
+            free_slots: 5,
+            free_runs: 3,
+            longest_free_run: 2,
+            occupied_runs: 3,
+            longest_occupied_run: 1,
+            high_water_mark: 6,
+        }
+    );
+    assert!(!report.is_compact());
+    slab.clear();
+    assert!(slab.fragmentation_report().is_compact());
+}
+
+#[test]
+fn test_as_ptr() {
+    let mut slab = Slab::with_capacity(3).unwrap();
+    let a = slab.push_front(1).unwrap();
+    let ptr = slab.as_mut_ptr(a).unwrap();
+    let b = slab.push_front(2).unwrap();
+    slab.move_to_front(a).unwrap();
+    unsafe { *ptr += 10 };
+    assert_eq!(slab.as_ptr(a), Ok(ptr as *const _));
+    assert_eq!(slab[a], 11);
+    slab.remove(b).unwrap();
+    assert_eq!(unsafe { *ptr }, 11);
+    slab.remove(a).unwrap();
+    #[cfg(not(feature = "releasefast"))]
+    assert_eq!(slab.as_ptr(a), Err(Error::InvalidSlot));
+}
+
+#[test]
+fn test_pinned() {
+    use std::cell::Cell;
+    use std::marker::PhantomPinned;
+
+    // Records its address when pinned, and checks that it didn't move
+    // when dropped.
+    struct Anchored {
+        addr: Cell<usize>,
+        _pin: PhantomPinned,
+    }
+
+    impl Drop for Anchored {
+        fn drop(&mut self) {
+            let addr = self.addr.get();
+            assert!(addr == 0 || addr == self as *const Self as usize);
+        }
+    }
+
+    let anchored = || Anchored {
+        addr: Cell::new(0),
+        _pin: PhantomPinned,
+    };
+    let mut slab = Box::pin(Slab::with_capacity(3).unwrap());
+    let a = slab.as_mut().push_front_pinned(anchored()).unwrap();
+    let b = slab.as_mut().push_front_pinned(anchored()).unwrap();
+    for slot in [a, b] {
+        let value = slab.as_mut().get_pin_mut(slot).unwrap();
+        value.addr.set(&*value as *const Anchored as usize);
+    }
+    slab.as_mut().push_front_pinned(anchored()).unwrap();
+    let addr = slab.get(a).unwrap().addr.get();
+    assert_eq!(addr, slab.get(a).unwrap() as *const Anchored as usize);
+    slab.as_mut().remove_pinned(a).unwrap();
+    #[cfg(not(feature = "releasefast"))]
+    assert!(slab.as_mut().get_pin_mut(a).is_err());
+    drop(slab);
+}
+
+#[cfg(all(feature = "leak_diagnostics", debug_assertions))]
+#[test]
+fn test_leak_hook() {
+    use std::sync::Mutex;
+
+    static LEAKED: Mutex<Vec<Slot>> = Mutex::new(Vec::new());
+
+    let mut slab = Slab::with_capacity(3).unwrap();
+    slab.set_leak_hook(|slots| LEAKED.lock().unwrap().extend_from_slice(slots));
+    let [a, b, c] = slab.push_front_many([1, 2, 3]).unwrap();
+    slab.remove(b).unwrap();
+    drop(slab);
+    assert_eq!(*LEAKED.lock().unwrap(), [c, a]);
+
+    let mut slab = Slab::with_capacity(1).unwrap();
+    slab.set_leak_hook(|_| panic!("no leaks expected"));
+    slab.push_front(()).unwrap();
+    slab.clear();
+
+    // Without allocating, large reports are split into batches.
+    #[cfg(feature = "realtime")]
+    {
+        static BATCHES: Mutex<Vec<usize>> = Mutex::new(Vec::new());
+
+        let mut slab = Slab::with_capacity(150).unwrap();
+        slab.set_leak_hook(|slots| BATCHES.lock().unwrap().push(slots.len()));
+        slab.try_extend(0..150).unwrap();
+        drop(slab);
+        assert_eq!(*BATCHES.lock().unwrap(), [64, 64, 22]);
+    }
+}
+
+#[test]
+fn test_clear_with() {
+    let mut slab = Slab::with_capacity(3).unwrap();
+    let [a, b, c] = slab.push_front_many(["a", "b", "c"]).unwrap();
+    slab.remove(b).unwrap();
+    let mut removed = Vec::new();
+    slab.clear_with(|slot, value| removed.push((slot, value)));
+    assert_eq!(removed, [(a, "a"), (c, "c")]);
+    assert!(slab.is_empty());
+    assert_eq!(slab.free(), 3);
+}
+
+#[test]
+fn test_clear() {
+    let mut empty = Slab::<u32>::with_capacity(0).unwrap();
+    assert!(empty.is_full());
+    assert_eq!(empty.push_front(1), Err(Error::Full));
+    assert!(!empty.is_occupied(0));
+    empty.clear();
+    assert_eq!(empty.capacity(), 0);
+
+    let mut slab = Slab::with_capacity(3).unwrap();
+    for round in 0..3 {
+        let slots = slab.push_front_many([round; 3]).unwrap();
+        assert!(slab.is_full());
+        slab.clear();
+        assert!(slab.is_empty());
+        assert_eq!(slab.free(), 3);
+        assert_eq!(slab.iter().count(), 0);
+        for slot in slots {
+            assert!(!slab.is_occupied(slot));
+        }
+    }
+    let a = slab.push_front(1).unwrap();
+    slab.clear();
+    assert_eq!(slab.push_front(2), Ok(a));
+    assert_eq!(slab.pop_back(), Some(2));
+}
+
+#[cfg(feature = "generational")]
+#[test]
+fn test_clear_generations() {
+    let mut slab = Slab::with_capacity(2).unwrap();
+    let key = slab.push_front_versioned(1).unwrap();
+    slab.clear();
+    let other = slab.push_front_versioned(2).unwrap();
+    assert_eq!(other.slot(), key.slot());
+    assert!(!slab.contains_versioned(key));
+    assert_eq!(slab.get_versioned(other), Ok(&2));
+}
+
+#[test]
+fn test_robust_iter() {
+    let mut slab = Slab::with_capacity(5).unwrap();
+    let [a, b, c, d] = slab.push_front_many([1, 2, 3, 4]).unwrap();
+    let mut slots = slab.robust_iter();
+    assert_eq!(slots.next(&slab), Some(d));
+    slab.remove(c).unwrap();
+    slab.remove(d).unwrap();
+    slab.push_front(5).unwrap();
+    assert_eq!(slots.next(&slab), Some(b));
+    slab.remove(a).unwrap();
+    assert_eq!(slots.next(&slab), None);
+    assert_eq!(slab.iter().copied().collect::<Vec<_>>(), [5, 2]);
+}
+
+#[test]
+fn test_iter_slots() {
+    let mut slab = Slab::with_capacity(4).unwrap();
+    assert_eq!(slab.iter_slots().next(), None);
+    let [a, b, c, d] = slab.push_front_many([1, 2, 3, 4]).unwrap();
+    slab.remove(b).unwrap();
+    let mut slots = slab.iter_slots();
+    assert_eq!(slots.len(), 3);
+    assert_eq!(slots.next(), Some(d));
+    assert_eq!(slots.next_back(), Some(a));
+    assert_eq!(slots.next(), Some(c));
+    assert_eq!(slots.next_back(), None);
+    let odd = slab
+        .iter_slots()
+        .filter(|&slot| slab[slot] % 2 == 1)
+        .collect::<Vec<_>>();
+    for slot in odd {
+        slab.remove(slot).unwrap();
+    }
+    assert_eq!(slab.iter().copied().collect::<Vec<_>>(), [4]);
+}
+
+#[test]
+fn test_sample() {
+    use rand::prelude::*;
+
+    let mut rng = rand::thread_rng();
+    let mut slab = Slab::with_capacity(64).unwrap();
+    assert_eq!(slab.sample(|bound| rng.gen_range(0..bound)), None);
+    let slots = (0..64)
+        .map(|i| slab.push_front(i).unwrap())
+        .collect::<Vec<_>>();
+    for &slot in slots.iter().filter(|&&slot| slot % 8 != 0) {
+        slab.remove(slot).unwrap();
+    }
+    let mut seen = [false; 64];
+    for _ in 0..1000 {
+        let slot = slab.sample(|bound| rng.gen_range(0..bound)).unwrap();
+        assert!(slab.is_occupied(slot));
+        seen[slot.to_usize()] = true;
+    }
+    assert_eq!(seen.iter().filter(|&&seen| seen).count(), 8);
+}
+
+#[test]
+fn test_push_front_evicting() {
+    let mut slab = Slab::with_capacity(2).unwrap();
+    let (a, evicted) = slab.push_front_evicting("a").unwrap();
+    assert_eq!(evicted, None);
+    slab.push_front_evicting("b").unwrap();
+    let (c, evicted) = slab.push_front_evicting("c").unwrap();
+    assert_eq!(evicted, Some("a"));
+    assert_eq!(c, a);
+    assert_eq!(slab.iter().copied().collect::<Vec<_>>(), ["c", "b"]);
+
+    let mut empty = Slab::with_capacity(0).unwrap();
+    assert_eq!(empty.push_front_evicting("a"), Err(Error::Full));
+}
+
+#[cfg(any(debug_assertions, feature = "strict"))]
+#[test]
+#[should_panic(expected = "is not occupied")]
+fn test_double_remove_detected() {
+    let mut slab = Slab::with_capacity(2).unwrap();
+    let a = slab.push_front(1).unwrap();
+    slab.push_front(2).unwrap();
+    unsafe {
+        slab.remove_unchecked(a);
+        slab.remove_unchecked(a);
+    }
+}
+
+#[test]
+fn test_double_remove_fuzz() {
+    use std::collections::VecDeque;
+
+    use rand::{prelude::*, rngs::StdRng};
+
+    let mut rng = StdRng::seed_from_u64(0x5eed);
+    let capacity = rng.gen_range(1..=50);
+    let mut slab = Slab::with_capacity(capacity).unwrap();
+    let mut model = VecDeque::with_capacity(capacity);
+    for c in 0..100_000u64 {
+        match rng.gen_range(0..=3) {
+            0 => {
+                if let Ok(slot) = slab.push_front(c) {
+                    model.push_front((slot, c));
+                }
+            }
+            1 => {
+                let Some((slot, value)) = model.pop_back() else {
+                    continue;
+                };
+                assert_eq!(slab.pop_back(), Some(value));
+                assert_eq!(slab.remove(slot), Err(Error::InvalidSlot));
+            }
+            2 => {
+                if model.is_empty() {
+                    continue;
+                }
+                let (slot, _) = model.remove(rng.gen_range(0..model.len())).unwrap();
+                slab.remove(slot).unwrap();
+                assert_eq!(slab.remove(slot), Err(Error::InvalidSlot));
+                assert_eq!(slab.take(slot), Err(Error::InvalidSlot));
+            }
+            3 => {
+                let Some((slot, value)) = model.pop_back() else {
+                    continue;
+                };
+                assert_eq!(slab.pop_back_ref(), Some(&value));
+                assert_eq!(slab.remove(slot), Err(Error::InvalidSlot));
+                #[cfg(not(feature = "releasefast"))]
+                assert_eq!(slab.get(slot), Err(Error::InvalidSlot));
+            }
+            _ => unreachable!(),
+        }
+        assert_eq!(slab.len(), model.len());
+        assert!(slab.iter().eq(model.iter().map(|(_, value)| value)));
+        assert_eq!(slab.free_slot_iter().count(), slab.free());
+    }
+}
+
+#[test]
+fn test_drain_back() {
+    let mut slab = Slab::with_capacity(5).unwrap();
+    let slots = (0..5)
+        .map(|i| slab.push_front(Box::new(i)).unwrap())
+        .collect::<Vec<_>>();
+    let drained = slab.drain_back(2).map(|x| *x).collect::<Vec<_>>();
+    assert_eq!(drained, [0, 1]);
+    assert_eq!(slab.len(), 3);
+    #[cfg(not(feature = "releasefast"))]
+    assert!(!slab.contains_slot(slots[1]));
+    slab.drain_back(1);
+    assert_eq!(slab.iter().map(|x| **x).collect::<Vec<_>>(), [4, 3]);
+    assert_eq!(slab.push_front(Box::new(5)), Ok(slots[2]));
+    assert_eq!(slab.drain_back(10).count(), 3);
+    assert!(slab.is_empty());
+    assert_eq!(slab.drain_back(1).next(), None);
+    for i in 0..5 {
+        slab.push_front(Box::new(i)).unwrap();
+    }
+    assert!(slab.is_full());
+}
+
+#[test]
+fn test_pop_back_chunk() {
+    let mut slab = Slab::with_capacity(5).unwrap();
+    for i in 0..5 {
+        slab.push_front(Box::new(i)).unwrap();
+    }
+    let (chunk, n) = slab.pop_back_chunk::<3>();
+    assert_eq!(n, 3);
+    let values: Vec<_> = chunk[..n]
+        .iter()
+        .map(|x| *unsafe { x.assume_init_read() })
+        .collect();
+    assert_eq!(values, [0, 1, 2]);
+    assert_eq!(slab.len(), 2);
+
+    let mut out = [const { MaybeUninit::uninit() }; 4];
+    assert_eq!(slab.pop_back_into(&mut out), 2);
+    assert_eq!(unsafe { out[1].assume_init_read() }, Box::new(4));
+    drop(unsafe { out[0].assume_init_read() });
+    assert!(slab.is_empty());
+    assert_eq!(slab.pop_back_into(&mut out), 0);
+    slab.push_front(Box::new(5)).unwrap();
+    assert_eq!(slab.len(), 1);
+}
+
+#[test]
+fn test_iter_len() {
+    let mut slab = Slab::with_capacity(5).unwrap();
+    slab.push_front_many([1, 2, 3, 4, 5]).unwrap();
+    let mut it = slab.iter();
+    assert_eq!(it.len(), 5);
+    assert_eq!(it.next(), Some(&5));
+    assert_eq!(it.next_back(), Some(&1));
+    assert_eq!(it.len(), 3);
+    assert_eq!(it.rev().copied().collect::<Vec<_>>(), [2, 3, 4]);
+
+    let mut it = slab.iter();
+    it.next();
+    it.next_back();
+    it.next();
+    it.next_back();
+    assert_eq!(it.next_back(), Some(&3));
+    assert_eq!(it.next(), None);
+    assert_eq!(it.next_back(), None);
+
+    let mut drain = slab.drain_back(3);
+    assert_eq!(drain.len(), 3);
+    drain.next();
+    assert_eq!(drain.size_hint(), (2, Some(2)));
 }
 
 #[test]
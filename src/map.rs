@@ -0,0 +1,333 @@
+//! A fixed-capacity hash map backed by the slab.
+
+use alloc::vec::Vec;
+use core::hash::{BuildHasher, Hash, Hasher};
+
+use crate::{Error, Slab, Slot, SlotType, NUL};
+
+/// The hasher used by default by `SlabMap`.
+#[cfg(feature = "std")]
+pub type DefaultHashBuilder = std::collections::hash_map::RandomState;
+
+/// The hasher used by default by `SlabMap`.
+#[cfg(not(feature = "std"))]
+pub type DefaultHashBuilder = core::hash::BuildHasherDefault<FnvHasher>;
+
+/// A simple FNV-1a hasher, used by default in `no_std` environments.
+///
+/// It is fast, but not resistant to collision attacks: use a keyed hasher
+/// via `SlabMap::with_capacity_and_hasher()` if keys are attacker-controlled.
+#[derive(Debug, Clone, Copy)]
+pub struct FnvHasher(u64);
+
+impl Default for FnvHasher {
+    fn default() -> Self {
+        FnvHasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for FnvHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = (self.0 ^ b as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+/// A hash map with a fixed capacity, that doesn't do dynamic allocations.
+///
+/// Entries are stored in a slab, and indexed by an open-addressing table
+/// allocated upfront. Insertions, lookups and removals are O(1), and
+/// iteration follows the insertion order, from the oldest entry to the
+/// most recent one. `touch()` can be used to maintain a LRU order instead.
+#[derive(Debug)]
+pub struct SlabMap<K, V, S = DefaultHashBuilder> {
+    slab: Slab<(K, V)>,
+    hashes: Vec<u64>,
+    buckets: Vec<Slot>,
+    hash_builder: S,
+}
+
+impl<K: Hash + Eq, V> SlabMap<K, V, DefaultHashBuilder> {
+    /// Create a new map with the given capacity.
+    pub fn with_capacity(capacity: usize) -> Result<Self, Error> {
+        Self::with_capacity_and_hasher(capacity, DefaultHashBuilder::default())
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> SlabMap<K, V, S> {
+    /// Create a new map with the given capacity and hasher.
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Result<Self, Error> {
+        let slab = Slab::with_capacity(capacity)?;
+        // Keep the load factor below 1/2 so that probe sequences stay short.
+        let bucket_count = capacity
+            .checked_mul(2)
+            .and_then(usize::checked_next_power_of_two)
+            .ok_or(Error::TooLarge)?;
+        Ok(Self {
+            slab,
+            hashes: alloc::vec![0; capacity],
+            buckets: alloc::vec![NUL; bucket_count],
+            hash_builder,
+        })
+    }
+
+    /// Return the capacity of the map.
+    pub fn capacity(&self) -> usize {
+        self.slab.capacity()
+    }
+
+    /// Return the number of entries.
+    pub fn len(&self) -> usize {
+        self.slab.len()
+    }
+
+    /// Return true if the map is empty.
+    pub fn is_empty(&self) -> bool {
+        self.slab.is_empty()
+    }
+
+    /// Return true if the map is full.
+    pub fn is_full(&self) -> bool {
+        self.slab.is_full()
+    }
+
+    /// Insert a key-value pair.
+    /// If the key was already present, its value is replaced, the entry
+    /// keeps its position, and the previous value is returned.
+    /// Return `Error::Full` if the key is new and the map is full.
+    pub fn insert(&mut self, key: K, value: V) -> Result<Option<V>, Error> {
+        self.insert_full(key, value).map(|(_, previous)| previous)
+    }
+
+    /// Insert a key-value pair, and return the slot of the entry along with
+    /// the previous value, if the key was already present.
+    /// The slot remains valid until the entry is removed.
+    pub fn insert_full(&mut self, key: K, value: V) -> Result<(Slot, Option<V>), Error> {
+        let hash = self.hash_builder.hash_one(&key);
+        if let Some((_, slot)) = self.find(&key, hash) {
+            let previous =
+                core::mem::replace(&mut unsafe { self.slab.get_unchecked_mut(slot) }.1, value);
+            return Ok((slot, Some(previous)));
+        }
+        let slot = self.slab.push_front((key, value))?;
+        self.hashes[slot.to_usize()] = hash;
+        let mut bucket = self.home(hash);
+        while self.buckets[bucket] != NUL {
+            bucket = (bucket + 1) & self.mask();
+        }
+        self.buckets[bucket] = slot;
+        Ok((slot, None))
+    }
+
+    /// Return a reference to the value associated with a key.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let slot = self.get_slot(key)?;
+        Some(&unsafe { self.slab.get_unchecked(slot) }.1)
+    }
+
+    /// Return a mutable reference to the value associated with a key.
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let slot = self.get_slot(key)?;
+        Some(&mut unsafe { self.slab.get_unchecked_mut(slot) }.1)
+    }
+
+    /// Mark an entry as the most recent one, and return its value.
+    pub fn touch(&mut self, key: &K) -> Option<&mut V> {
+        let slot = self.get_slot(key)?;
+        self.slab.move_to_front(slot).ok()?;
+        Some(&mut unsafe { self.slab.get_unchecked_mut(slot) }.1)
+    }
+
+    /// Return true if the map contains the given key.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get_slot(key).is_some()
+    }
+
+    /// Remove a key from the map, and return its value.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    /// Remove a key from the map, and return the stored key and value.
+    pub fn remove_entry(&mut self, key: &K) -> Option<(K, V)> {
+        let hash = self.hash_builder.hash_one(key);
+        let (bucket, slot) = self.find(key, hash)?;
+        self.unindex(bucket);
+        self.slab.take_checked(slot).ok()
+    }
+
+    /// Return the entry stored in a slot.
+    /// If the crate is compiled with the `releasefast` feature, this should
+    /// never be called on a slot that doesn't contain an entry.
+    pub fn get_by_slot(&self, slot: Slot) -> Result<(&K, &V), Error> {
+        let (key, value) = self.slab.get_checked(slot)?;
+        Ok((key, value))
+    }
+
+    /// Remove the entry stored in a slot, and return its key and value.
+    /// If the crate is compiled with the `releasefast` feature, this should
+    /// never be called on a slot that doesn't contain an entry.
+    pub fn remove_by_slot(&mut self, slot: Slot) -> Result<(K, V), Error> {
+        self.slab.get_checked(slot)?;
+        let bucket = self.bucket_of(slot);
+        self.unindex(bucket);
+        self.slab.take_checked(slot)
+    }
+
+    /// Return the oldest entry.
+    pub fn oldest(&self) -> Option<(&K, &V)> {
+        self.iter().next()
+    }
+
+    /// Remove and return the oldest entry.
+    pub fn pop_oldest(&mut self) -> Option<(K, V)> {
+        let slot = self.slab.tail;
+        if slot == NUL {
+            return None;
+        }
+        let bucket = self.bucket_of(slot);
+        self.unindex(bucket);
+        self.slab.pop_back()
+    }
+
+    /// Remove all the entries.
+    pub fn clear(&mut self) {
+        while self.slab.pop_back().is_some() {}
+        self.buckets.fill(NUL);
+    }
+
+    /// Iterate over the entries, from the oldest to the most recent one.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> + '_ {
+        self.slab.iter().rev().map(|(key, value)| (key, value))
+    }
+
+    /// Iterate over the keys, from the oldest to the most recent one.
+    pub fn keys(&self) -> impl Iterator<Item = &K> + '_ {
+        self.iter().map(|(key, _)| key)
+    }
+
+    /// Iterate over the values, from the oldest to the most recent one.
+    pub fn values(&self) -> impl Iterator<Item = &V> + '_ {
+        self.iter().map(|(_, value)| value)
+    }
+
+    /// Return the slot storing the entry for a key.
+    pub fn get_slot(&self, key: &K) -> Option<Slot> {
+        let hash = self.hash_builder.hash_one(key);
+        self.find(key, hash).map(|(_, slot)| slot)
+    }
+
+    #[inline]
+    fn mask(&self) -> usize {
+        self.buckets.len() - 1
+    }
+
+    #[inline]
+    fn home(&self, hash: u64) -> usize {
+        hash as usize & self.mask()
+    }
+
+    fn find(&self, key: &K, hash: u64) -> Option<(usize, Slot)> {
+        let mut bucket = self.home(hash);
+        loop {
+            let slot = self.buckets[bucket];
+            if slot == NUL {
+                return None;
+            }
+            if self.hashes[slot.to_usize()] == hash
+                && unsafe { self.slab.get_unchecked(slot) }.0 == *key
+            {
+                return Some((bucket, slot));
+            }
+            bucket = (bucket + 1) & self.mask();
+        }
+    }
+
+    fn bucket_of(&self, slot: Slot) -> usize {
+        let mut bucket = self.home(self.hashes[slot.to_usize()]);
+        while self.buckets[bucket] != slot {
+            invariant_ne!(self.buckets[bucket], NUL);
+            bucket = (bucket + 1) & self.mask();
+        }
+        bucket
+    }
+
+    // Empty a bucket, shifting the following entries back so that no
+    // tombstones are needed.
+    fn unindex(&mut self, mut hole: usize) {
+        let mask = self.mask();
+        self.buckets[hole] = NUL;
+        let mut bucket = hole;
+        loop {
+            bucket = (bucket + 1) & mask;
+            let slot = self.buckets[bucket];
+            if slot == NUL {
+                break;
+            }
+            let home = self.home(self.hashes[slot.to_usize()]);
+            // Entries whose home is cyclically in (hole, bucket] must stay.
+            if bucket.wrapping_sub(home) & mask < bucket.wrapping_sub(hole) & mask {
+                continue;
+            }
+            self.buckets[hole] = slot;
+            self.buckets[bucket] = NUL;
+            hole = bucket;
+        }
+    }
+}
+
+#[test]
+fn test_map() {
+    use rand::prelude::*;
+    use std::collections::HashMap;
+
+    let mut rng = rand::thread_rng();
+    let capacity = 50;
+    let mut map = SlabMap::with_capacity(capacity).unwrap();
+    let mut model = HashMap::new();
+    for i in 0..100_000u32 {
+        let key = rng.gen_range(0..100u32);
+        match rng.gen_range(0..3) {
+            0 => match map.insert(key, i) {
+                Ok(previous) => assert_eq!(previous, model.insert(key, i)),
+                Err(_) => assert!(model.len() == capacity && !model.contains_key(&key)),
+            },
+            1 => assert_eq!(map.remove(&key), model.remove(&key)),
+            _ => {
+                if let Some((key, _)) = map.pop_oldest() {
+                    model.remove(&key).unwrap();
+                }
+            }
+        }
+        assert_eq!(map.len(), model.len());
+        assert_eq!(map.get(&key), model.get(&key));
+    }
+    for (key, value) in map.iter() {
+        assert_eq!(model.get(key), Some(value));
+    }
+}
+
+#[test]
+fn test_map_order() {
+    let mut map = SlabMap::with_capacity(3).unwrap();
+    map.insert("a", 1).unwrap();
+    map.insert("b", 2).unwrap();
+    map.insert("c", 3).unwrap();
+    assert_eq!(map.insert("d", 4), Err(Error::Full));
+    assert_eq!(map.insert("a", 10), Ok(Some(1)));
+    assert_eq!(map.keys().copied().collect::<Vec<_>>(), ["a", "b", "c"]);
+    assert_eq!(map.touch(&"b"), Some(&mut 2));
+    assert_eq!(map.keys().copied().collect::<Vec<_>>(), ["a", "c", "b"]);
+    assert_eq!(map.oldest(), Some((&"a", &10)));
+    assert_eq!(map.pop_oldest(), Some(("a", 10)));
+    *map.get_mut(&"c").unwrap() += 1;
+    assert_eq!(map.values().copied().collect::<Vec<_>>(), [4, 2]);
+    map.clear();
+    assert!(map.is_empty());
+    assert_eq!(map.get(&"b"), None);
+}
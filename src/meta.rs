@@ -0,0 +1,214 @@
+//! A slab storing user metadata alongside every element.
+
+use alloc::vec::Vec;
+use core::ops::Sub;
+
+use crate::{Error, Slab, Slot, SlotType};
+
+/// A slab that stores an extra `M` value (a timestamp, flags...) for every
+/// element, in a separate array.
+///
+/// Metadata is set when an element is added, and is dropped along with the
+/// element when it is removed, so that both can never get out of sync.
+/// Keeping it out of the elements makes scanning it cache-friendly.
+#[derive(Debug)]
+pub struct MetaSlab<D, M, S: SlotType = Slot> {
+    slab: Slab<D, S>,
+    meta: Vec<Option<M>>,
+}
+
+impl<D, M> MetaSlab<D, M> {
+    /// Create a new slab with the given capacity.
+    pub fn with_capacity(capacity: usize) -> Result<Self, Error> {
+        Self::with_slot_type(capacity)
+    }
+}
+
+impl<D, M, S: SlotType> MetaSlab<D, M, S> {
+    /// Create a new slab with the given capacity, using the slot type `S`.
+    pub fn with_slot_type(capacity: usize) -> Result<Self, Error> {
+        let slab = Slab::with_slot_type(capacity)?;
+        let mut meta = crate::try_vec(capacity)?;
+        meta.resize_with(capacity, || None);
+        Ok(Self { slab, meta })
+    }
+
+    /// Return the capacity of the slab.
+    pub fn capacity(&self) -> usize {
+        self.slab.capacity()
+    }
+
+    /// Return the number of elements.
+    pub fn len(&self) -> usize {
+        self.slab.len()
+    }
+
+    /// Return true if the slab is empty.
+    pub fn is_empty(&self) -> bool {
+        self.slab.is_empty()
+    }
+
+    /// Return true if the slab is full.
+    pub fn is_full(&self) -> bool {
+        self.slab.is_full()
+    }
+
+    /// Prepend an element and its metadata to the beginning of the list,
+    /// and return its slot.
+    pub fn push_front_with_meta(&mut self, value: D, meta: M) -> Result<S, Error> {
+        let slot = self.slab.push_front(value)?;
+        self.meta[slot.to_usize()] = Some(meta);
+        Ok(slot)
+    }
+
+    /// Return an element given its slot number.
+    pub fn get(&self, slot: S) -> Result<&D, Error> {
+        self.slab.get_checked(slot)
+    }
+
+    /// Return a mutable reference to an element given its slot number.
+    pub fn get_mut(&mut self, slot: S) -> Result<&mut D, Error> {
+        self.slab.get_mut_checked(slot)
+    }
+
+    /// Return the metadata of an element given its slot number.
+    pub fn meta(&self, slot: S) -> Result<&M, Error> {
+        self.slab.get_checked(slot)?;
+        self.meta[slot.to_usize()]
+            .as_ref()
+            .ok_or(Error::InvalidSlot)
+    }
+
+    /// Return a mutable reference to the metadata of an element.
+    pub fn meta_mut(&mut self, slot: S) -> Result<&mut M, Error> {
+        self.slab.get_checked(slot)?;
+        self.meta[slot.to_usize()]
+            .as_mut()
+            .ok_or(Error::InvalidSlot)
+    }
+
+    /// Replace the metadata of an element, and return the previous one.
+    pub fn set_meta(&mut self, slot: S, meta: M) -> Result<M, Error> {
+        self.meta_mut(slot)
+            .map(|current| core::mem::replace(current, meta))
+    }
+
+    /// Remove an element given its slot, and return it along with its
+    /// metadata.
+    pub fn remove(&mut self, slot: S) -> Result<(D, M), Error> {
+        let value = self.slab.take_checked(slot)?;
+        let meta = self.meta[slot.to_usize()]
+            .take()
+            .ok_or(Error::InvalidSlot)?;
+        Ok((value, meta))
+    }
+
+    /// Remove and return the tail element, along with its metadata.
+    pub fn pop_back(&mut self) -> Option<(D, M)> {
+        let slot = self.slab.tail;
+        let value = self.slab.pop_back()?;
+        let meta = self.meta[slot.to_usize()].take()?;
+        Some((value, meta))
+    }
+
+    /// Return how long an element has been in the slab, when its metadata is
+    /// the time it was added, for example an `Instant` or a number of ticks.
+    /// `now` must not be earlier than that time.
+    pub fn age<A>(&self, slot: S, now: M) -> Result<A, Error>
+    where
+        M: Copy + Sub<Output = A>,
+    {
+        self.meta(slot).map(|&added| now - added)
+    }
+
+    /// Return the slot and the age of the tail element, or `None` if the
+    /// slab is empty.
+    ///
+    /// If elements are added with the current time as metadata, the tail is
+    /// the oldest element, so that expired elements can be evicted with
+    /// `pop_back()` as long as this returns an age above a limit.
+    pub fn oldest<A>(&self, now: M) -> Option<(S, A)>
+    where
+        M: Copy + Sub<Output = A>,
+    {
+        let slot = self.slab.tail;
+        if slot == S::NUL {
+            return None;
+        }
+        self.age(slot, now).ok().map(|age| (slot, age))
+    }
+
+    /// Iterate over the `(slot, &element, &metadata)` tuples, from the head
+    /// to the tail of the list.
+    pub fn iter(&self) -> impl Iterator<Item = (S, &D, &M)> + '_ {
+        let mut slot = self.slab.head;
+        core::iter::from_fn(move || {
+            if slot == S::NUL {
+                return None;
+            }
+            let current = slot;
+            slot = self.slab.vec_next[current.to_usize()];
+            let meta = self.meta[current.to_usize()].as_ref()?;
+            Some((current, unsafe { self.slab.get_unchecked(current) }, meta))
+        })
+    }
+}
+
+#[test]
+fn test_meta() {
+    let mut slab = MetaSlab::with_capacity(2).unwrap();
+    let a = slab.push_front_with_meta("a", 10u64).unwrap();
+    let b = slab.push_front_with_meta("b", 20).unwrap();
+    assert_eq!(slab.push_front_with_meta("c", 30), Err(Error::Full));
+    assert_eq!(slab.meta(a), Ok(&10));
+    assert_eq!(slab.set_meta(b, 21), Ok(20));
+    *slab.meta_mut(a).unwrap() += 1;
+    assert_eq!(
+        slab.iter().collect::<Vec<_>>(),
+        [(b, &"b", &21), (a, &"a", &11)]
+    );
+    assert_eq!(slab.pop_back(), Some(("a", 11)));
+    assert!(slab.meta(a).is_err());
+    let c = slab.push_front_with_meta("c", 30).unwrap();
+    assert_eq!(slab.meta(c), Ok(&30));
+    assert_eq!(slab.remove(b), Ok(("b", 21)));
+    assert_eq!(slab.len(), 1);
+
+    let mut slab = MetaSlab::<_, _, u16>::with_slot_type(2).unwrap();
+    let a: u16 = slab.push_front_with_meta('a', 1u8).unwrap();
+    slab.push_front_with_meta('b', 2).unwrap();
+    assert_eq!(slab.oldest(3), Some((a, 2)));
+    assert_eq!(slab.pop_back(), Some(('a', 1)));
+    assert!(slab.meta(a).is_err());
+    assert_eq!(slab.pop_back(), Some(('b', 2)));
+    assert_eq!(slab.pop_back(), None);
+}
+
+#[test]
+fn test_meta_age() {
+    let mut slab = MetaSlab::with_capacity(4).unwrap();
+    assert_eq!(slab.oldest(0u64), None);
+    let a = slab.push_front_with_meta('a', 10u64).unwrap();
+    let b = slab.push_front_with_meta('b', 15).unwrap();
+    slab.push_front_with_meta('c', 30).unwrap();
+    assert_eq!(slab.age(b, 40), Ok(25));
+    assert_eq!(slab.oldest(40), Some((a, 30)));
+
+    while slab.oldest(40).is_some_and(|(_, age)| age > 20) {
+        slab.pop_back();
+    }
+    assert_eq!(slab.len(), 1);
+    assert_eq!(slab.age(a, 40), Err(Error::InvalidSlot));
+
+    #[cfg(feature = "std")]
+    {
+        use std::time::{Duration, Instant};
+
+        let mut slab = MetaSlab::with_capacity(1).unwrap();
+        let start = Instant::now();
+        let slot = slab.push_front_with_meta((), start).unwrap();
+        let now = start + Duration::from_secs(3);
+        assert_eq!(slab.age(slot, now), Ok(Duration::from_secs(3)));
+        assert_eq!(slab.oldest(now), Some((slot, Duration::from_secs(3))));
+    }
+}
@@ -0,0 +1,192 @@
+//! Differential tests of the list against `std::collections::LinkedList`.
+//!
+//! Every operation is mirrored on a reference list of `(value, slot)`
+//! pairs, and the order, the slots and the elements of both lists are
+//! compared after every step. Elements removed with `remove_deferred()`
+//! are tracked separately, until they are flushed.
+
+use std::collections::LinkedList;
+
+use rand::{prelude::*, rngs::StdRng};
+
+use crate::{Error, Slab, Slot};
+
+struct Model {
+    slab: Slab<u64>,
+    list: LinkedList<(u64, Slot)>,
+    deferred: Vec<Slot>,
+}
+
+impl Model {
+    fn new(capacity: usize) -> Self {
+        Model {
+            slab: Slab::with_capacity(capacity).unwrap(),
+            list: LinkedList::new(),
+            deferred: Vec::new(),
+        }
+    }
+
+    // Remove the element at the given position of the reference list.
+    fn list_remove(&mut self, position: usize) -> (u64, Slot) {
+        let mut rest = self.list.split_off(position);
+        let entry = rest.pop_front().unwrap();
+        self.list.append(&mut rest);
+        entry
+    }
+
+    fn check(&self) {
+        assert_eq!(self.slab.len(), self.list.len());
+        assert_eq!(self.slab.is_empty(), self.list.is_empty());
+        assert_eq!(self.slab.deferred_len(), self.deferred.len());
+        assert_eq!(
+            self.slab.free(),
+            self.slab.capacity() - self.list.len() - self.deferred.len()
+        );
+        assert!(self
+            .slab
+            .iter()
+            .eq(self.list.iter().map(|(value, _)| value)));
+        assert!(self
+            .slab
+            .iter()
+            .rev()
+            .eq(self.list.iter().rev().map(|(value, _)| value)));
+        assert!(self
+            .slab
+            .iter_slots()
+            .eq(self.list.iter().map(|&(_, slot)| slot)));
+        for &(value, slot) in &self.list {
+            assert_eq!(self.slab.get(slot), Ok(&value));
+        }
+        assert_eq!(self.slab.free_slot_iter().count(), self.slab.free());
+        for slot in self.slab.free_slot_iter() {
+            assert!(!self.deferred.contains(&slot));
+        }
+        #[cfg(not(feature = "releasefast"))]
+        for &slot in &self.deferred {
+            assert_eq!(self.slab.get(slot), Err(Error::InvalidSlot));
+        }
+    }
+
+    fn step(&mut self, rng: &mut impl Rng, value: u64) {
+        match rng.gen_range(0..12) {
+            0 | 1 => match self.slab.push_front(value) {
+                Ok(slot) => self.list.push_front((value, slot)),
+                Err(e) => {
+                    assert_eq!(e, Error::Full);
+                    assert_eq!(self.list.len() + self.deferred.len(), self.slab.capacity());
+                }
+            },
+            2 => {
+                let expected = self.list.pop_back().map(|(value, _)| value);
+                assert_eq!(self.slab.pop_back(), expected);
+            }
+            3 | 4 => {
+                if self.list.is_empty() {
+                    return;
+                }
+                let position = rng.gen_range(0..self.list.len());
+                let (_, slot) = self.list_remove(position);
+                self.slab.remove(slot).unwrap();
+                #[cfg(not(feature = "releasefast"))]
+                assert_eq!(self.slab.remove(slot), Err(Error::InvalidSlot));
+            }
+            5 => {
+                if self.list.is_empty() {
+                    return;
+                }
+                let position = rng.gen_range(0..self.list.len());
+                let entry = self.list_remove(position);
+                self.list.push_front(entry);
+                self.slab.move_to_front(entry.1).unwrap();
+            }
+            6 => {
+                let expected = self.list.pop_back().map(|entry| {
+                    self.list.push_front(entry);
+                    entry.1
+                });
+                assert_eq!(self.slab.rotate_back_to_front(), expected);
+            }
+            7 => {
+                let expected = self.list.pop_front().map(|entry| {
+                    self.list.push_back(entry);
+                    entry.1
+                });
+                assert_eq!(self.slab.rotate_front_to_back(), expected);
+            }
+            8 => {
+                let position = rng.gen_range(0..=self.list.len());
+                let expected = self.list.iter().nth(position).map(|&(_, slot)| slot);
+                assert_eq!(self.slab.nth_slot(position), expected);
+                if let Some(slot) = expected {
+                    assert_eq!(self.slab.position_of(slot), Ok(position));
+                    *self.slab.get_mut(slot).unwrap() = value;
+                    self.list.iter_mut().nth(position).unwrap().0 = value;
+                }
+            }
+            9 => match rng.gen_range(0..3) {
+                0 => {
+                    self.slab.reverse();
+                    self.list = self.list.iter().rev().copied().collect();
+                }
+                1 => {
+                    self.slab.sort_by(|a, b| (a % 7).cmp(&(b % 7)));
+                    let mut entries: Vec<_> = self.list.iter().copied().collect();
+                    entries.sort_by_key(|&(value, _)| value % 7);
+                    self.list = entries.into_iter().collect();
+                }
+                _ => {
+                    let keep = value % 3;
+                    let expected = self.list.back().filter(|(value, _)| value % 3 == keep);
+                    let expected = expected.map(|&(value, _)| value);
+                    if expected.is_some() {
+                        self.list.pop_back();
+                    }
+                    assert_eq!(self.slab.pop_back_if(|value| value % 3 == keep), expected);
+                }
+            },
+            10 => {
+                let expected = self.list.pop_back();
+                let popped = self.slab.pop_back_ref().copied();
+                assert_eq!(popped, expected.map(|(value, _)| value));
+                #[cfg(not(feature = "releasefast"))]
+                if let Some((_, slot)) = expected {
+                    assert_eq!(self.slab.remove(slot), Err(Error::InvalidSlot));
+                }
+            }
+            11 => {
+                if self.list.is_empty() || rng.gen_bool(0.2) {
+                    assert_eq!(self.slab.flush_deferred(), self.deferred.len());
+                    self.deferred.clear();
+                    return;
+                }
+                let position = rng.gen_range(0..self.list.len());
+                let (_, slot) = self.list_remove(position);
+                self.slab.remove_deferred(slot).unwrap();
+                #[cfg(not(feature = "releasefast"))]
+                assert_eq!(self.slab.remove_deferred(slot), Err(Error::InvalidSlot));
+                self.deferred.push(slot);
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[test]
+fn test_model_linked_list() {
+    for seed in 0..20 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let capacity = rng.gen_range(0..=40);
+        let mut model = Model::new(capacity);
+        for value in 0..5_000 {
+            model.step(&mut rng, value);
+            model.check();
+        }
+        model.slab.clear();
+        model.list.clear();
+        model.check();
+        assert_eq!(model.slab.flush_deferred(), model.deferred.len());
+        model.deferred.clear();
+        model.check();
+    }
+}
@@ -0,0 +1,232 @@
+//! A slab whose elements can belong to several lists at once.
+
+use alloc::vec::Vec;
+
+use crate::{Error, Slab, Slot, SlotType, NUL};
+
+#[derive(Debug, Clone, Copy)]
+struct Link {
+    prev: Slot,
+    next: Slot,
+}
+
+const UNLINKED: Link = Link {
+    prev: NUL,
+    next: NUL,
+};
+
+/// A slab where every element can be linked into up to `LISTS` independent
+/// lists, each with its own order.
+///
+/// Elements are stored once, with `insert()`, and are then added to and
+/// removed from individual lists using their slot. Adding, removing and
+/// popping from a list are all O(1).
+///
+/// List identifiers are in the `0..LISTS` range; methods panic if they are
+/// given an identifier outside of that range.
+#[derive(Debug)]
+pub struct MultiListSlab<D, const LISTS: usize> {
+    slab: Slab<D>,
+    links: Vec<[Link; LISTS]>,
+    heads: [Slot; LISTS],
+    tails: [Slot; LISTS],
+    lens: [usize; LISTS],
+}
+
+impl<D, const LISTS: usize> MultiListSlab<D, LISTS> {
+    /// Create a new slab with the given capacity.
+    pub fn with_capacity(capacity: usize) -> Result<Self, Error> {
+        Ok(Self {
+            slab: Slab::with_capacity(capacity)?,
+            links: alloc::vec![[UNLINKED; LISTS]; capacity],
+            heads: [NUL; LISTS],
+            tails: [NUL; LISTS],
+            lens: [0; LISTS],
+        })
+    }
+
+    /// Return the capacity of the slab.
+    pub fn capacity(&self) -> usize {
+        self.slab.capacity()
+    }
+
+    /// Return the number of stored elements.
+    pub fn len(&self) -> usize {
+        self.slab.len()
+    }
+
+    /// Return true if no elements are stored.
+    pub fn is_empty(&self) -> bool {
+        self.slab.is_empty()
+    }
+
+    /// Return true if the slab is full.
+    pub fn is_full(&self) -> bool {
+        self.slab.is_full()
+    }
+
+    /// Return the number of elements in the given list.
+    pub fn len_of(&self, list_id: usize) -> usize {
+        self.lens[list_id]
+    }
+
+    /// Store an element, without adding it to any list.
+    pub fn insert(&mut self, value: D) -> Result<Slot, Error> {
+        self.slab.push_front(value)
+    }
+
+    /// Return an element given its slot number.
+    pub fn get(&self, slot: Slot) -> Result<&D, Error> {
+        self.slab.get_checked(slot)
+    }
+
+    /// Return a mutable reference to an element given its slot number.
+    pub fn get_mut(&mut self, slot: Slot) -> Result<&mut D, Error> {
+        self.slab.get_mut_checked(slot)
+    }
+
+    /// Remove an element from all the lists it belongs to, and return it.
+    pub fn remove(&mut self, slot: Slot) -> Result<D, Error> {
+        if !self.slab.contains_slot(slot) {
+            return Err(Error::InvalidSlot);
+        }
+        for list_id in 0..LISTS {
+            if self.contains_in(list_id, slot) {
+                self.unlink(list_id, slot);
+            }
+        }
+        self.slab.take_checked(slot)
+    }
+
+    /// Check if an element belongs to the given list.
+    pub fn contains_in(&self, list_id: usize, slot: Slot) -> bool {
+        if !self.slab.contains_slot(slot) {
+            return false;
+        }
+        self.heads[list_id] == slot || self.links[slot.to_usize()][list_id].prev != NUL
+    }
+
+    /// Add an element to the beginning of the given list.
+    /// If the element already belongs to that list, it is moved to the beginning.
+    pub fn push_front_in(&mut self, list_id: usize, slot: Slot) -> Result<(), Error> {
+        if !self.slab.contains_slot(slot) {
+            return Err(Error::InvalidSlot);
+        }
+        if self.contains_in(list_id, slot) {
+            self.unlink(list_id, slot);
+        }
+        let head = self.heads[list_id];
+        self.links[slot.to_usize()][list_id] = Link {
+            prev: NUL,
+            next: head,
+        };
+        if head != NUL {
+            self.links[head.to_usize()][list_id].prev = slot;
+        } else {
+            self.tails[list_id] = slot;
+        }
+        self.heads[list_id] = slot;
+        self.lens[list_id] += 1;
+        Ok(())
+    }
+
+    /// Remove an element from the given list. The element remains stored.
+    pub fn remove_from(&mut self, list_id: usize, slot: Slot) -> Result<(), Error> {
+        if !self.contains_in(list_id, slot) {
+            return Err(Error::InvalidSlot);
+        }
+        self.unlink(list_id, slot);
+        Ok(())
+    }
+
+    /// Return the slot of the first element of the given list.
+    pub fn front_of(&self, list_id: usize) -> Option<Slot> {
+        Some(self.heads[list_id]).filter(|&slot| slot != NUL)
+    }
+
+    /// Return the slot of the last element of the given list.
+    pub fn back_of(&self, list_id: usize) -> Option<Slot> {
+        Some(self.tails[list_id]).filter(|&slot| slot != NUL)
+    }
+
+    /// Remove the last element of the given list from that list, and
+    /// return its slot. The element remains stored.
+    pub fn pop_back_from(&mut self, list_id: usize) -> Option<Slot> {
+        let slot = self.back_of(list_id)?;
+        self.unlink(list_id, slot);
+        Some(slot)
+    }
+
+    /// Iterate over the `(slot, &element)` pairs of the given list,
+    /// from its beginning to its end.
+    pub fn iter_in(&self, list_id: usize) -> impl Iterator<Item = (Slot, &D)> + '_ {
+        let mut slot = self.heads[list_id];
+        core::iter::from_fn(move || {
+            if slot == NUL {
+                return None;
+            }
+            let current = slot;
+            slot = self.links[current.to_usize()][list_id].next;
+            Some((current, unsafe { self.slab.get_unchecked(current) }))
+        })
+    }
+
+    fn unlink(&mut self, list_id: usize, slot: Slot) {
+        let Link { prev, next } = self.links[slot.to_usize()][list_id];
+        if prev != NUL {
+            invariant_eq!(self.links[prev.to_usize()][list_id].next, slot);
+            self.links[prev.to_usize()][list_id].next = next;
+        } else {
+            invariant_eq!(self.heads[list_id], slot);
+            self.heads[list_id] = next;
+        }
+        if next != NUL {
+            invariant_eq!(self.links[next.to_usize()][list_id].prev, slot);
+            self.links[next.to_usize()][list_id].prev = prev;
+        } else {
+            invariant_eq!(self.tails[list_id], slot);
+            self.tails[list_id] = prev;
+        }
+        self.links[slot.to_usize()][list_id] = UNLINKED;
+        invariant!(self.lens[list_id] > 0);
+        self.lens[list_id] -= 1;
+    }
+}
+
+#[test]
+fn test_multilist() {
+    const BY_DEADLINE: usize = 0;
+    const BY_PRIORITY: usize = 1;
+
+    let mut slab = MultiListSlab::<&str, 2>::with_capacity(3).unwrap();
+    let a = slab.insert("a").unwrap();
+    let b = slab.insert("b").unwrap();
+    let c = slab.insert("c").unwrap();
+    for slot in [a, b, c] {
+        slab.push_front_in(BY_DEADLINE, slot).unwrap();
+    }
+    for slot in [c, a] {
+        slab.push_front_in(BY_PRIORITY, slot).unwrap();
+    }
+    assert!(!slab.contains_in(BY_PRIORITY, b));
+    assert_eq!(slab.len_of(BY_PRIORITY), 2);
+    let order = |slab: &MultiListSlab<&'static str, 2>, list_id| {
+        slab.iter_in(list_id)
+            .map(|(_, &value)| value)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(order(&slab, BY_DEADLINE), ["c", "b", "a"]);
+    assert_eq!(order(&slab, BY_PRIORITY), ["a", "c"]);
+
+    slab.push_front_in(BY_DEADLINE, a).unwrap();
+    assert_eq!(order(&slab, BY_DEADLINE), ["a", "c", "b"]);
+    assert_eq!(slab.pop_back_from(BY_DEADLINE), Some(b));
+    assert!(slab.remove_from(BY_DEADLINE, b).is_err());
+    assert_eq!(slab.get(b), Ok(&"b"));
+
+    assert_eq!(slab.remove(c), Ok("c"));
+    assert_eq!(order(&slab, BY_DEADLINE), ["a"]);
+    assert_eq!(order(&slab, BY_PRIORITY), ["a"]);
+    assert_eq!(slab.front_of(BY_PRIORITY), slab.back_of(BY_PRIORITY));
+    assert_eq!(slab.len(), 2);
+}
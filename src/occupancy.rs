@@ -0,0 +1,131 @@
+//! A read-only view of the occupied slots of a slab.
+//!
+//! Slots are considered in storage order, not in list order. This is useful
+//! to build compact external indexes: `rank(k)` is the number of occupied
+//! slots lower than `k`, so occupied slots can be mapped to dense indices
+//! in `0..len()`, and `select(n)` maps them back.
+//!
+//! Occupancy is read from the bitmap of the slab, or from `is_occupied()`
+//! if there is no bitmap. Checking a slot is then O(n) if the crate is
+//! compiled with `releasefast` and without `generational` or `tagged`.
+
+use crate::{Slab, Slot, SlotType};
+
+/// A read-only view of the occupancy of the slots of a slab, as returned by
+/// `Slab::occupancy()`.
+#[derive(Debug, Clone, Copy)]
+pub struct OccupancyView<'a, D, S: SlotType = Slot> {
+    list: &'a Slab<D, S>,
+}
+
+impl<D, S: SlotType> Slab<D, S> {
+    /// Return a read-only view of the occupied slots.
+    pub fn occupancy(&self) -> OccupancyView<'_, D, S> {
+        OccupancyView { list: self }
+    }
+}
+
+impl<D, S: SlotType> OccupancyView<'_, D, S> {
+    /// Return the number of slots, occupied or not.
+    pub fn len(&self) -> usize {
+        self.list.capacity()
+    }
+
+    /// Return true if there are no slots.
+    pub fn is_empty(&self) -> bool {
+        self.list.capacity() == 0
+    }
+
+    /// Return the number of occupied slots.
+    pub fn count_ones(&self) -> usize {
+        self.list.len()
+    }
+
+    /// Return true if the slot contains an element.
+    /// Panics if the slot is out of range.
+    pub fn get(&self, slot: S) -> bool {
+        assert!(slot.to_usize() < self.len(), "slot out of range");
+        self.list.is_occupied(slot)
+    }
+
+    /// Return the number of occupied slots lower than `k`.
+    /// `k` is clamped to the number of slots.
+    pub fn rank(&self, k: usize) -> usize {
+        let k = k.min(self.len());
+        #[cfg(not(any(feature = "releasefast", feature = "tagged")))]
+        {
+            let bitmap = &self.list.bitmap;
+            let full: usize = bitmap[..k / 8]
+                .iter()
+                .map(|byte| byte.count_ones() as usize)
+                .sum();
+            let partial = match k % 8 {
+                0 => 0,
+                bits => (bitmap[k / 8] & ((1 << bits) - 1)).count_ones() as usize,
+            };
+            full + partial
+        }
+        #[cfg(any(feature = "releasefast", feature = "tagged"))]
+        {
+            (0..k).filter(|&slot| self.get(S::from_usize(slot))).count()
+        }
+    }
+
+    /// Return the `n`-th occupied slot (starting at 0), or `None` if fewer
+    /// than `n + 1` slots are occupied.
+    pub fn select(&self, n: usize) -> Option<S> {
+        self.iter_ones().nth(n)
+    }
+
+    /// Iterate over the occupied slots, in increasing order.
+    pub fn iter_ones(&self) -> impl Iterator<Item = S> + '_ {
+        (0..self.len())
+            .map(S::from_usize)
+            .filter(|&slot| self.get(slot))
+    }
+
+    /// Iterate over the occupancy of all the slots, in increasing order.
+    pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
+        (0..self.len()).map(|slot| self.get(S::from_usize(slot)))
+    }
+
+    /// Return the underlying bitmap, where bit `s % 8` of byte `s / 8` is
+    /// set if slot `s` is occupied. Unused bits of the last byte are zero.
+    #[cfg(not(any(feature = "releasefast", feature = "tagged")))]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.list.bitmap
+    }
+}
+
+#[test]
+fn test_occupancy() {
+    let mut slab = Slab::with_capacity(10).unwrap();
+    let slots: Vec<_> = (0..10).map(|i| slab.push_front(i).unwrap()).collect();
+    for &slot in &slots[..9] {
+        if slot % 3 != 0 {
+            slab.remove(slot).unwrap();
+        }
+    }
+    let view = slab.occupancy();
+    assert_eq!(view.len(), 10);
+    assert_eq!(view.count_ones(), 4);
+    assert_eq!(view.iter_ones().collect::<Vec<_>>(), [0, 3, 6, 9]);
+    assert!(view.get(6));
+    assert!(!view.get(8));
+    assert_eq!(view.rank(0), 0);
+    assert_eq!(view.rank(4), 2);
+    assert_eq!(view.rank(9), 3);
+    assert_eq!(view.rank(100), 4);
+    assert_eq!(view.select(3), Some(9));
+    assert_eq!(view.select(4), None);
+    assert_eq!(view.iter().filter(|&occupied| occupied).count(), 4);
+    #[cfg(not(any(feature = "releasefast", feature = "tagged")))]
+    assert_eq!(view.as_bytes(), [0b0100_1001, 0b10]);
+
+    let mut slab = Slab::<_, u16>::with_slot_type(3).unwrap();
+    let [a, b, _] = slab.push_front_many([1, 2, 3]).unwrap();
+    slab.remove(b).unwrap();
+    let view = slab.occupancy();
+    assert_eq!(view.iter_ones().collect::<Vec<u16>>(), [a, 2]);
+    assert_eq!(view.rank(2), 1);
+}
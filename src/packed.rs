@@ -0,0 +1,164 @@
+//! Versioned slots packed into a single `u32`.
+//!
+//! A `VersionedSlot` keeps the full slot number and a 32-bit generation.
+//! A `PackedSlot<G>` stores both in 32 bits instead: the slot number in the
+//! lower `32 - G` bits, and the lower `G` bits of the generation in the
+//! upper bits. More generation bits make it less likely for a stale handle
+//! to be accepted after its slot has been reused `2^G` times, at the cost of
+//! a smaller capacity.
+
+use crate::{Error, Slab, VersionedSlot};
+
+/// A versioned slot packed into a `u32`, with `GENERATION_BITS` bits of
+/// generation, and the remaining bits for the slot number.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PackedSlot<const GENERATION_BITS: u32>(u32);
+
+impl<const GENERATION_BITS: u32> PackedSlot<GENERATION_BITS> {
+    /// The number of bits used for the slot number.
+    pub const INDEX_BITS: u32 = {
+        assert!(
+            GENERATION_BITS > 0 && GENERATION_BITS < 32,
+            "the number of generation bits must be between 1 and 31"
+        );
+        32 - GENERATION_BITS
+    };
+
+    /// The largest capacity of a list whose slots can all be packed.
+    pub const MAX_CAPACITY: usize = 1 << Self::INDEX_BITS;
+
+    const INDEX_MASK: u32 = (1 << Self::INDEX_BITS) - 1;
+
+    /// Create a packed slot from its raw representation.
+    pub fn from_bits(bits: u32) -> Self {
+        PackedSlot(bits)
+    }
+
+    /// Return the raw representation of the packed slot.
+    pub fn to_bits(self) -> u32 {
+        self.0
+    }
+
+    /// Return the slot number.
+    pub fn slot(self) -> u32 {
+        self.0 & Self::INDEX_MASK
+    }
+
+    // Generations are odd while a slot is occupied, so their lowest bit is
+    // not stored.
+    fn truncated_generation(generation: u32) -> u32 {
+        (generation >> 1) & (u32::MAX >> Self::INDEX_BITS)
+    }
+}
+
+impl<D> Slab<D, u32> {
+    /// Create a new list with the given capacity, whose versioned slots
+    /// can all be packed with `GENERATION_BITS` bits of generation.
+    /// Return `Error::TooLarge` if the capacity doesn't fit in the remaining
+    /// bits.
+    pub fn with_packed_capacity<const GENERATION_BITS: u32>(
+        capacity: usize,
+    ) -> Result<Self, Error> {
+        if capacity > PackedSlot::<GENERATION_BITS>::MAX_CAPACITY {
+            return Err(Error::TooLarge);
+        }
+        Self::with_slot_type(capacity)
+    }
+
+    /// Prepend an element to the beginning of the list, and return its
+    /// packed versioned slot.
+    /// Return `Error::TooLarge` if the slot doesn't fit in the index bits,
+    /// in which case the element is not inserted.
+    pub fn push_front_packed<const GENERATION_BITS: u32>(
+        &mut self,
+        value: D,
+    ) -> Result<PackedSlot<GENERATION_BITS>, Error> {
+        match self.peek_next_free() {
+            None => return Err(Error::Full),
+            Some(slot) if slot as usize >= PackedSlot::<GENERATION_BITS>::MAX_CAPACITY => {
+                return Err(Error::TooLarge)
+            }
+            Some(_) => {}
+        }
+        let key = self.push_front_versioned(value)?;
+        self.pack(key)
+    }
+
+    /// Pack a versioned slot.
+    /// Return `Error::TooLarge` if the slot doesn't fit in the index bits.
+    pub fn pack<const GENERATION_BITS: u32>(
+        &self,
+        key: VersionedSlot<u32>,
+    ) -> Result<PackedSlot<GENERATION_BITS>, Error> {
+        if key.slot() as usize >= PackedSlot::<GENERATION_BITS>::MAX_CAPACITY {
+            return Err(Error::TooLarge);
+        }
+        let generation = PackedSlot::<GENERATION_BITS>::truncated_generation(key.generation());
+        Ok(PackedSlot(
+            generation << PackedSlot::<GENERATION_BITS>::INDEX_BITS | key.slot(),
+        ))
+    }
+
+    /// Return the versioned slot of the element referenced by a packed
+    /// slot, or `None` if that element has been removed.
+    ///
+    /// Since only part of the generation is stored, a packed slot whose
+    /// slot has been reused a multiple of `2^GENERATION_BITS` times is
+    /// accepted again.
+    pub fn unpack<const GENERATION_BITS: u32>(
+        &self,
+        packed: PackedSlot<GENERATION_BITS>,
+    ) -> Option<VersionedSlot<u32>> {
+        let slot = packed.slot();
+        let generation = *self.generations.get(slot as usize)?;
+        let stored = packed.0 >> PackedSlot::<GENERATION_BITS>::INDEX_BITS;
+        if generation & 1 == 0
+            || PackedSlot::<GENERATION_BITS>::truncated_generation(generation) != stored
+        {
+            return None;
+        }
+        Some(VersionedSlot { slot, generation })
+    }
+}
+
+#[test]
+fn test_packed_slot() {
+    assert_eq!(PackedSlot::<8>::MAX_CAPACITY, 1 << 24);
+    assert_eq!(
+        Slab::<u8, u32>::with_packed_capacity::<31>(3).err(),
+        Some(Error::TooLarge)
+    );
+
+    let mut slab = Slab::<_, u32>::with_packed_capacity::<30>(4).unwrap();
+    let a = slab.push_front_packed::<30>('a').unwrap();
+    let key = slab.unpack(a).unwrap();
+    assert_eq!(slab.get_versioned(key), Ok(&'a'));
+    assert_eq!(slab.pack(key), Ok(a));
+    assert_eq!(a.slot(), 0);
+
+    // With 30 generation bits, the handle only becomes valid again after
+    // 2^30 reuses of its slot.
+    slab.remove_versioned(key).unwrap();
+    assert_eq!(slab.unpack(a), None);
+    let b = slab.push_front_packed::<30>('b').unwrap();
+    assert_eq!(b.slot(), a.slot());
+    assert_ne!(a, b);
+    assert_eq!(slab.unpack(a), None);
+
+    // With a single generation bit, a handle is valid again after its slot
+    // has been reused twice.
+    let c = slab.pack::<1>(slab.unpack(b).unwrap()).unwrap();
+    let mut stale = c;
+    for _ in 0..2 {
+        slab.remove_versioned(slab.unpack(stale).unwrap()).unwrap();
+        stale = slab.push_front_packed::<1>('c').unwrap();
+    }
+    assert_eq!(stale, c);
+    assert!(slab.unpack(c).is_some());
+
+    let mut slab = Slab::<_, u32>::with_slot_type(3).unwrap();
+    slab.push_front(0).unwrap();
+    slab.push_front(1).unwrap();
+    assert_eq!(slab.push_front_packed::<31>(2), Err(Error::TooLarge));
+    assert_eq!(slab.len(), 2);
+}
@@ -0,0 +1,115 @@
+//! Arrays occupying whole cache lines.
+//!
+//! The occupancy bitmap and the generations are read by every validated
+//! access, while links are written by every insertion and removal. Small
+//! allocations made in a row are usually adjacent, so the end of an array
+//! of links could share a cache line with the beginning of the bitmap, and
+//! a writer updating links on another core would then keep evicting the
+//! bitmap from the cache of the readers. The arrays defined here start and
+//! end on cache line boundaries, so that they never share a line with
+//! another allocation.
+//!
+//! This costs memory: every padded array occupies a multiple of 128 bytes,
+//! so at least 128 bytes even for a list with a capacity of 1, and up to
+//! 127 bytes more than its contents otherwise. A list has at most two such
+//! arrays, the bitmap and the generations.
+
+use core::{
+    alloc::Layout,
+    ops::{Deref, DerefMut},
+    ptr::NonNull,
+};
+
+use crate::Error;
+
+// Some CPUs fetch cache lines in pairs, so this is twice the usual 64 bytes.
+const CACHE_LINE: usize = 128;
+
+/// Element types that are valid when all their bytes are zero.
+pub(crate) trait Zeroable: Copy {}
+
+impl Zeroable for u8 {}
+impl Zeroable for u32 {}
+
+pub(crate) struct PaddedSlice<T: Zeroable> {
+    ptr: NonNull<T>,
+    len: usize,
+}
+
+// A `PaddedSlice` owns its elements, like a `Box<[T]>`.
+unsafe impl<T: Zeroable + Send> Send for PaddedSlice<T> {}
+unsafe impl<T: Zeroable + Sync> Sync for PaddedSlice<T> {}
+
+impl<T: Zeroable> PaddedSlice<T> {
+    fn layout(len: usize) -> Option<Layout> {
+        let size = len
+            .checked_mul(core::mem::size_of::<T>())?
+            .checked_next_multiple_of(CACHE_LINE)?;
+        Layout::from_size_align(size, CACHE_LINE).ok()
+    }
+
+    /// Allocate a zeroed array.
+    /// Return `Error::TooLarge` if its size would overflow, and
+    /// `Error::OutOfMemory` if the allocation fails.
+    pub(crate) fn try_zeroed(len: usize) -> Result<Self, Error> {
+        let layout = Self::layout(len).ok_or(Error::TooLarge)?;
+        if layout.size() == 0 {
+            return Ok(PaddedSlice {
+                ptr: NonNull::dangling(),
+                len,
+            });
+        }
+        let ptr = unsafe { alloc::alloc::alloc_zeroed(layout) };
+        let ptr = NonNull::new(ptr.cast()).ok_or(Error::OutOfMemory)?;
+        Ok(PaddedSlice { ptr, len })
+    }
+}
+
+impl<T: Zeroable> Drop for PaddedSlice<T> {
+    fn drop(&mut self) {
+        let layout = Self::layout(self.len).expect("the layout was valid when allocated");
+        if layout.size() != 0 {
+            unsafe { alloc::alloc::dealloc(self.ptr.as_ptr().cast(), layout) };
+        }
+    }
+}
+
+impl<T: Zeroable> Deref for PaddedSlice<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { core::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<T: Zeroable> DerefMut for PaddedSlice<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { core::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<T: Zeroable + core::fmt::Debug> core::fmt::Debug for PaddedSlice<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+#[test]
+fn test_padded_slice() {
+    for len in [0, 1, 31, 32, 33, 1000] {
+        let mut padded = PaddedSlice::<u32>::try_zeroed(len).unwrap();
+        assert_eq!(padded.len(), len);
+        assert!(padded.iter().all(|&x| x == 0));
+        if len > 0 {
+            assert_eq!(padded.as_ptr() as usize % CACHE_LINE, 0);
+        }
+        for (i, x) in padded.iter_mut().enumerate() {
+            *x = i as u32;
+        }
+        assert!(padded.iter().enumerate().all(|(i, &x)| x == i as u32));
+    }
+    assert_eq!(
+        PaddedSlice::<u32>::try_zeroed(usize::MAX).err(),
+        Some(Error::TooLarge)
+    );
+}
@@ -0,0 +1,208 @@
+//! A pool of slabs, one per worker thread.
+
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use crate::{Error, Slab, Slot, SlotType};
+
+/// A handle to an element of a `ThreadLocalPool`, tagged with the shard
+/// that owns it, and with the generation of its slot.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Handle {
+    shard: usize,
+    slot: Slot,
+    generation: u32,
+}
+
+impl Handle {
+    /// Return the index of the shard owning the element.
+    pub fn shard(&self) -> usize {
+        self.shard
+    }
+
+    /// Return the slot of the element within its shard.
+    pub fn slot(&self) -> Slot {
+        self.slot
+    }
+}
+
+/// The shared side of a set of per-thread slabs.
+///
+/// Every worker thread owns a `LocalShard`, and inserts into it without any
+/// synchronization. Elements can be removed from any thread: removals of
+/// elements owned by another shard are sent to a queue of that shard, and
+/// applied by the owning thread the next time it inserts an element or calls
+/// `LocalShard::collect()`.
+///
+/// Every shard counts the removals from each of its slots, and handles
+/// record that count. A stale handle, whose element was already removed,
+/// is thus rejected, even if a queued removal is applied after its slot was
+/// reused. The counters wrap after 2^32 removals from the same slot.
+#[derive(Debug)]
+pub struct ThreadLocalPool<D> {
+    queues: Vec<Sender<Handle>>,
+    _marker: PhantomData<fn(D)>,
+}
+
+/// A slab owned by a single worker thread.
+#[derive(Debug)]
+pub struct LocalShard<D> {
+    id: usize,
+    slab: Slab<D>,
+    generations: Vec<u32>,
+    removed: Receiver<Handle>,
+}
+
+impl<D> ThreadLocalPool<D> {
+    /// Create a pool of `shards` slabs with the given capacity each.
+    /// Return the shared pool, and the shards to hand out to the worker
+    /// threads.
+    pub fn new(shards: usize, capacity: usize) -> Result<(Self, Vec<LocalShard<D>>), Error> {
+        let mut queues = Vec::with_capacity(shards);
+        let mut locals = Vec::with_capacity(shards);
+        for id in 0..shards {
+            let (sender, receiver) = mpsc::channel();
+            queues.push(sender);
+            locals.push(LocalShard {
+                id,
+                slab: Slab::with_capacity(capacity)?,
+                generations: alloc::vec![0; capacity],
+                removed: receiver,
+            });
+        }
+        let pool = Self {
+            queues,
+            _marker: PhantomData,
+        };
+        Ok((pool, locals))
+    }
+
+    /// Return the number of shards.
+    pub fn shards(&self) -> usize {
+        self.queues.len()
+    }
+
+    /// Queue the removal of an element for the shard owning it.
+    /// Return `Error::InvalidSlot` if that shard doesn't exist anymore.
+    pub fn remove(&self, handle: Handle) -> Result<(), Error> {
+        let queue = self.queues.get(handle.shard).ok_or(Error::InvalidSlot)?;
+        queue.send(handle).map_err(|_| Error::InvalidSlot)
+    }
+}
+
+impl<D> LocalShard<D> {
+    /// Return the index of this shard.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Return the number of elements in this shard, not counting pending
+    /// removals.
+    pub fn len(&self) -> usize {
+        self.slab.len()
+    }
+
+    /// Return true if this shard is empty, not counting pending removals.
+    pub fn is_empty(&self) -> bool {
+        self.slab.is_empty()
+    }
+
+    /// Apply the removals queued by other threads, and return their number.
+    pub fn collect(&mut self) -> usize {
+        let mut count = 0;
+        while let Ok(handle) = self.removed.try_recv() {
+            if self.remove_local(handle).is_ok() {
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Insert an element into this shard, after applying pending removals.
+    pub fn insert(&mut self, value: D) -> Result<Handle, Error> {
+        self.collect();
+        let slot = self.slab.push_front(value)?;
+        Ok(Handle {
+            shard: self.id,
+            slot,
+            generation: self.generations[slot.to_usize()],
+        })
+    }
+
+    /// Return an element owned by this shard.
+    pub fn get(&self, handle: Handle) -> Result<&D, Error> {
+        self.slot_of(handle)
+            .and_then(|slot| self.slab.get_checked(slot))
+    }
+
+    /// Return a mutable reference to an element owned by this shard.
+    pub fn get_mut(&mut self, handle: Handle) -> Result<&mut D, Error> {
+        let slot = self.slot_of(handle)?;
+        self.slab.get_mut_checked(slot)
+    }
+
+    /// Remove an element owned by this shard, or queue its removal if
+    /// another shard owns it.
+    pub fn remove(&mut self, pool: &ThreadLocalPool<D>, handle: Handle) -> Result<(), Error> {
+        if handle.shard == self.id {
+            self.remove_local(handle)
+        } else {
+            pool.remove(handle)
+        }
+    }
+
+    fn slot_of(&self, handle: Handle) -> Result<Slot, Error> {
+        if handle.shard != self.id
+            || self.generations.get(handle.slot.to_usize()) != Some(&handle.generation)
+        {
+            return Err(Error::InvalidSlot);
+        }
+        Ok(handle.slot)
+    }
+
+    fn remove_local(&mut self, handle: Handle) -> Result<(), Error> {
+        let slot = self.slot_of(handle)?;
+        self.slab.remove_checked(slot)?;
+        let generation = &mut self.generations[slot.to_usize()];
+        *generation = generation.wrapping_add(1);
+        Ok(())
+    }
+}
+
+#[test]
+fn test_thread_local_pool() {
+    let (pool, shards) = ThreadLocalPool::<u32>::new(2, 2).unwrap();
+    let [mut a, mut b]: [LocalShard<u32>; 2] = shards.try_into().unwrap();
+
+    let x = a.insert(1).unwrap();
+    let y = a.insert(2).unwrap();
+    assert!(a.insert(3).is_err());
+    assert_eq!(b.get(x), Err(Error::InvalidSlot));
+
+    std::thread::scope(|s| {
+        s.spawn(|| {
+            let z = b.insert(10).unwrap();
+            b.remove(&pool, x).unwrap();
+            b.remove(&pool, z).unwrap();
+            assert!(b.is_empty());
+        });
+    });
+
+    assert_eq!(a.len(), 2);
+    assert_eq!(a.collect(), 1);
+    assert_eq!(a.get(x), Err(Error::InvalidSlot));
+    assert_eq!(a.get(y), Ok(&2));
+
+    // A removal queued after the slot was reused leaves the new element.
+    let w = a.insert(3).unwrap();
+    assert_eq!(w.slot(), x.slot());
+    pool.remove(x).unwrap();
+    assert_eq!(a.collect(), 0);
+    assert_eq!(a.get(w), Ok(&3));
+    assert_eq!(a.remove(&pool, x), Err(Error::InvalidSlot));
+    assert_eq!(a.len(), 2);
+
+    drop(a);
+    assert!(pool.remove(y).is_err());
+}
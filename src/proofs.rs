@@ -0,0 +1,92 @@
+//! Kani proof harnesses for the unsafe core of the list.
+//!
+//! They check, for every sequence of a few operations on a small list, that
+//! the elements are only read from slots that have been initialized, and
+//! that validated accesses agree with a model of the occupied slots.
+//!
+//! Run them with `cargo kani -Z uninit-checks`.
+
+use crate::{Slab, Slot};
+
+const CAPACITY: usize = 3;
+const STEPS: usize = 4;
+
+// Apply a nondeterministic operation, and update the model accordingly.
+fn step(slab: &mut Slab<u8>, model: &mut [Option<u8>; CAPACITY]) {
+    let slot: Slot = kani::any();
+    match kani::any::<u8>() % 3 {
+        0 => {
+            let value = kani::any();
+            if let Ok(slot) = slab.push_front(value) {
+                assert!(model[slot as usize].is_none());
+                model[slot as usize] = Some(value);
+            } else {
+                assert!(model.iter().all(Option::is_some));
+            }
+        }
+        1 => {
+            if slab.remove(slot).is_ok() {
+                assert!(model[slot as usize].take().is_some());
+            } else {
+                assert!(!matches!(model.get(slot as usize), Some(Some(_))));
+            }
+        }
+        _ => {
+            let tail = slab.tail;
+            match slab.pop_back() {
+                Some(value) => assert_eq!(model[tail as usize].take(), Some(value)),
+                None => assert!(model.iter().all(Option::is_none)),
+            }
+        }
+    }
+}
+
+#[kani::proof]
+#[kani::unwind(5)]
+fn get_only_reads_initialized_slots() {
+    let mut slab = Slab::with_capacity(CAPACITY).unwrap();
+    let mut model = [None; CAPACITY];
+    for _ in 0..STEPS {
+        step(&mut slab, &mut model);
+    }
+    let slot: Slot = kani::any();
+    match model.get(slot as usize).copied().flatten() {
+        Some(value) => assert_eq!(slab.get(slot), Ok(&value)),
+        None => assert!(slab.get(slot).is_err()),
+    }
+    assert_eq!(slab.len(), model.iter().flatten().count());
+}
+
+#[kani::proof]
+#[kani::unwind(5)]
+fn iteration_only_reads_initialized_slots() {
+    let mut slab = Slab::with_capacity(CAPACITY).unwrap();
+    let mut model = [None; CAPACITY];
+    for _ in 0..STEPS {
+        step(&mut slab, &mut model);
+    }
+    for slot in slab.iter_slots() {
+        assert_eq!(slab.get(slot).ok(), model[slot as usize].as_ref());
+    }
+    assert_eq!(slab.iter().count(), slab.len());
+    assert_eq!(slab.free_slot_iter().count(), slab.free());
+}
+
+#[kani::proof]
+#[kani::unwind(5)]
+fn get_mut_writes_only_to_its_slot() {
+    let mut slab = Slab::with_capacity(CAPACITY).unwrap();
+    let mut model = [None; CAPACITY];
+    for _ in 0..STEPS {
+        step(&mut slab, &mut model);
+    }
+    let slot: Slot = kani::any();
+    let value = kani::any();
+    if let Ok(element) = slab.get_mut(slot) {
+        *element = value;
+        model[slot as usize] = Some(value);
+    }
+    for (slot, expected) in model.iter().enumerate() {
+        assert_eq!(slab.get(slot as Slot).ok(), expected.as_ref());
+    }
+}
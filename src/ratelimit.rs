@@ -0,0 +1,130 @@
+//! A per-client rate limiter that doesn't do dynamic allocations.
+
+use core::hash::{BuildHasher, Hash};
+
+use crate::{
+    map::{DefaultHashBuilder, SlabMap},
+    Error,
+};
+
+#[derive(Debug, Clone, Copy)]
+struct TokenBucket {
+    tokens: u32,
+    last_refill: u64,
+}
+
+/// A table of token buckets, one per client.
+///
+/// Every client starts with `burst` tokens, gets a new token every
+/// `refill_interval` time units, and spends one token per request.
+/// Time units are chosen by the caller (seconds, milliseconds, ticks...),
+/// and `now` is expected to be monotonic.
+///
+/// The table has a fixed capacity: when it is full, the least recently
+/// seen client is evicted to make room for a new one.
+#[derive(Debug)]
+pub struct BucketTable<K, S = DefaultHashBuilder> {
+    map: SlabMap<K, TokenBucket, S>,
+    burst: u32,
+    refill_interval: u64,
+}
+
+impl<K: Hash + Eq> BucketTable<K, DefaultHashBuilder> {
+    /// Create a new table tracking up to `capacity` clients.
+    pub fn with_capacity(capacity: usize, burst: u32, refill_interval: u64) -> Result<Self, Error> {
+        Self::with_capacity_and_hasher(capacity, burst, refill_interval, Default::default())
+    }
+}
+
+impl<K: Hash + Eq, S: BuildHasher> BucketTable<K, S> {
+    /// Create a new table tracking up to `capacity` clients, using the
+    /// given hasher to index them.
+    pub fn with_capacity_and_hasher(
+        capacity: usize,
+        burst: u32,
+        refill_interval: u64,
+        hash_builder: S,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            map: SlabMap::with_capacity_and_hasher(capacity, hash_builder)?,
+            burst,
+            refill_interval: refill_interval.max(1),
+        })
+    }
+
+    /// Return the maximum number of tracked clients.
+    pub fn capacity(&self) -> usize {
+        self.map.capacity()
+    }
+
+    /// Return the number of tracked clients.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Return true if no clients are tracked.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Record a request from a client, and return true if it is allowed.
+    pub fn check(&mut self, key: K, now: u64) -> bool {
+        let (burst, refill_interval) = (self.burst, self.refill_interval);
+        if let Some(bucket) = self.map.touch(&key) {
+            let refills = now.saturating_sub(bucket.last_refill) / refill_interval;
+            let tokens = (bucket.tokens as u64).saturating_add(refills);
+            if tokens >= burst as u64 {
+                bucket.tokens = burst;
+                bucket.last_refill = now;
+            } else {
+                bucket.tokens = tokens as u32;
+                bucket.last_refill += refills * refill_interval;
+            }
+            if bucket.tokens == 0 {
+                return false;
+            }
+            bucket.tokens -= 1;
+            return true;
+        }
+        if burst == 0 {
+            return false;
+        }
+        if self.map.is_full() {
+            self.map.pop_oldest();
+        }
+        let bucket = TokenBucket {
+            tokens: burst - 1,
+            last_refill: now,
+        };
+        self.map.insert(key, bucket).is_ok()
+    }
+
+    /// Return the number of tokens a client currently has, as of its last
+    /// request, or `None` if the client is not tracked.
+    pub fn tokens(&self, key: &K) -> Option<u32> {
+        self.map.get(key).map(|bucket| bucket.tokens)
+    }
+
+    /// Stop tracking a client.
+    pub fn remove(&mut self, key: &K) -> bool {
+        self.map.remove(key).is_some()
+    }
+}
+
+#[test]
+fn test_bucket_table() {
+    let mut table = BucketTable::with_capacity(2, 2, 10).unwrap();
+    assert!(table.check("a", 0));
+    assert!(table.check("a", 1));
+    assert!(!table.check("a", 2));
+    assert!(table.check("a", 12));
+    assert!(!table.check("a", 13));
+    assert_eq!(table.tokens(&"a"), Some(0));
+
+    assert!(table.check("b", 13));
+    assert!(table.check("a", 40));
+    assert!(table.check("c", 40));
+    assert_eq!(table.len(), 2);
+    assert_eq!(table.tokens(&"b"), None);
+    assert_eq!(table.tokens(&"a"), Some(1));
+}
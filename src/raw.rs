@@ -0,0 +1,367 @@
+//! Low-level access to the links of a slab.
+//!
+//! The list is made of two arrays of links indexed by slot: `next` points
+//! from the head towards the tail, and `prev` from the tail towards the head.
+//! For every occupied slot `s`:
+//!
+//! - `raw_next(s)` is `None` if and only if `s` is the tail,
+//! - `raw_prev(s)` is `None` if and only if `s` is the head,
+//! - if `raw_next(s)` is `Some(n)`, then `raw_prev(n)` is `Some(s)`.
+//!
+//! Following `raw_next()` from `raw_head()` visits every occupied slot
+//! exactly once, ending at `raw_tail()`. The read-only methods are always
+//! safe to call; their result is unspecified for free slots. The methods
+//! that change links are `unsafe`, and the caller must restore all these
+//! invariants before calling any other method of the slab.
+//!
+//! A slab can also be decomposed into its arrays with `into_raw_parts()`,
+//! and reassembled with `from_raw_parts()`, without copying the elements.
+
+use alloc::vec::Vec;
+use core::mem::MaybeUninit;
+
+use crate::{Error, Slab, Slot, SlotType};
+
+/// The components of a slab, as returned by `Slab::into_raw_parts()`.
+///
+/// `next` and `prev` hold the links of both the list and the list of free
+/// slots, which starts at `free_head`. `S::NUL` links are represented by the
+/// largest value of the slot type, such as `u32::MAX`. With the `tagged`
+/// feature, the `prev` link of free slots is that value minus one. With the
+/// `generational` feature, generations are odd for occupied slots and even
+/// for free slots.
+#[derive(Debug)]
+pub struct RawParts<D, S: SlotType = Slot> {
+    /// Links towards the tail, then through the free list.
+    pub next: Vec<S>,
+    /// Links towards the head, then backwards through the free list.
+    pub prev: Vec<S>,
+    /// The elements; only occupied slots are initialized.
+    pub data: Vec<MaybeUninit<D>>,
+    /// The head of the list, or `S::NUL` if the list is empty.
+    pub head: S,
+    /// The tail of the list, or `S::NUL` if the list is empty.
+    pub tail: S,
+    /// The first free slot, or `S::NUL` if the list is full.
+    pub free_head: S,
+    /// The number of elements.
+    pub len: usize,
+    /// The generation of every slot.
+    #[cfg(feature = "generational")]
+    pub generations: Vec<u32>,
+}
+
+#[inline]
+fn link<S: SlotType>(slot: S) -> Option<S> {
+    Some(slot).filter(|&slot| slot != S::NUL)
+}
+
+impl<D, S: SlotType> Slab<D, S> {
+    /// Return the slot of the head of the list.
+    pub fn raw_head(&self) -> Option<S> {
+        link(self.head)
+    }
+
+    /// Return the slot of the tail of the list.
+    pub fn raw_tail(&self) -> Option<S> {
+        link(self.tail)
+    }
+
+    /// Return the slot following `slot`, towards the tail.
+    /// Panics if `slot` is out of range.
+    pub fn raw_next(&self, slot: S) -> Option<S> {
+        link(self.vec_next[slot.to_usize()])
+    }
+
+    /// Return the slot preceding `slot`, towards the head.
+    /// Panics if `slot` is out of range.
+    pub fn raw_prev(&self, slot: S) -> Option<S> {
+        link(self.vec_prev[..self.capacity()][slot.to_usize()])
+    }
+
+    /// Make `b` follow `a`. Either of them can be `None`, to make the other
+    /// one the head (`a` is `None`) or the tail (`b` is `None`) of its chain.
+    ///
+    /// # Safety
+    ///
+    /// Both slots must be occupied, and the invariants documented in the
+    /// `raw` module must hold again before the slab is used otherwise.
+    pub unsafe fn raw_link(&mut self, a: Option<S>, b: Option<S>) {
+        if let Some(a) = a {
+            self.vec_next[a.to_usize()] = b.unwrap_or(S::NUL);
+        }
+        if let Some(b) = b {
+            self.vec_prev[b.to_usize()] = a.unwrap_or(S::NUL);
+        }
+    }
+
+    /// Set the head and the tail of the list.
+    ///
+    /// # Safety
+    ///
+    /// Both slots must be occupied, or both must be `None` if the list is
+    /// empty, and the invariants documented in the `raw` module must hold
+    /// again before the slab is used otherwise.
+    pub unsafe fn raw_set_ends(&mut self, head: Option<S>, tail: Option<S>) {
+        self.head = head.unwrap_or(S::NUL);
+        self.tail = tail.unwrap_or(S::NUL);
+    }
+
+    /// Decompose the slab into its components, without copying the
+    /// elements. The elements are not dropped, except the ones removed with
+    /// `remove_deferred()`, which are dropped first.
+    pub fn into_raw_parts(mut self) -> RawParts<D, S> {
+        self.flush_deferred();
+        // With the `tagged` feature, `vec_prev` has an extra entry.
+        let mut prev = core::mem::take(&mut self.vec_prev).into_vec();
+        prev.truncate(self.capacity());
+        let parts = RawParts {
+            next: core::mem::take(&mut self.vec_next).into_vec(),
+            prev,
+            data: core::mem::take(&mut self.data).into_vec(),
+            head: self.head,
+            tail: self.tail,
+            free_head: self.free_head,
+            len: self.len(),
+            #[cfg(feature = "generational")]
+            generations: self.generations.to_vec(),
+        };
+        // The elements have been moved out; don't let `drop()` see them.
+        self.head = S::NUL;
+        self.tail = S::NUL;
+        self.len = S::from_usize(0);
+        parts
+    }
+
+    /// Reassemble a slab from its components.
+    /// Vectors with a capacity larger than their length are shrunk, which
+    /// may reallocate them. Like the standard collections, this aborts if
+    /// the internal tables can't be allocated; `from_raw_parts_checked()`
+    /// returns `Error::OutOfMemory` instead.
+    ///
+    /// # Safety
+    ///
+    /// The components must describe a valid slab, as returned by
+    /// `into_raw_parts()` with the same crate features: all the arrays have
+    /// the same length, the list and the free list together cover every
+    /// slot exactly once, and the elements of occupied slots are
+    /// initialized. `from_raw_parts_checked()` verifies everything but the
+    /// last requirement.
+    pub unsafe fn from_raw_parts(parts: RawParts<D, S>) -> Self {
+        let capacity = parts.next.len();
+        match Self::try_from_raw_parts(parts) {
+            Ok(slab) => slab,
+            Err(_) => alloc::alloc::handle_alloc_error(
+                core::alloc::Layout::array::<S>(capacity)
+                    .unwrap_or(core::alloc::Layout::new::<S>()),
+            ),
+        }
+    }
+
+    // The tables are allocated before the components are consumed, so that
+    // they can be returned if an allocation fails.
+    unsafe fn try_from_raw_parts(parts: RawParts<D, S>) -> Result<Self, (Error, RawParts<D, S>)> {
+        // With the `tagged` feature, an entry is added to `prev`.
+        #[cfg(feature = "tagged")]
+        let mut parts = parts;
+        // Unused if no table needs to be allocated.
+        #[allow(unused_macros)]
+        macro_rules! try_alloc {
+            ($e:expr) => {
+                match $e {
+                    Ok(table) => table,
+                    Err(e) => return Err((e, parts)),
+                }
+            };
+        }
+        #[cfg(not(any(feature = "releasefast", feature = "tagged")))]
+        let bitmap = {
+            let mut bitmap = try_alloc!(crate::padded::PaddedSlice::try_zeroed(
+                parts.next.len().div_ceil(8)
+            ));
+            let mut slot = parts.head;
+            while slot != S::NUL {
+                bitmap[slot.to_usize() / 8] |= 1 << (slot.to_usize() & 7);
+                slot = parts.next[slot.to_usize()];
+            }
+            bitmap
+        };
+        #[cfg(any(feature = "changelog", feature = "latency_stats"))]
+        let capacity = parts.next.len();
+        #[cfg(feature = "generational")]
+        let generations = {
+            let mut generations = try_alloc!(crate::padded::PaddedSlice::try_zeroed(
+                parts.generations.len()
+            ));
+            generations.copy_from_slice(&parts.generations);
+            generations
+        };
+        #[cfg(feature = "tagged")]
+        try_alloc!(parts
+            .prev
+            .try_reserve_exact(1)
+            .map_err(|_| Error::OutOfMemory));
+        #[cfg(feature = "changelog")]
+        let changelog = try_alloc!(crate::changelog::ChangeLog::with_capacity(capacity));
+        #[cfg(feature = "latency_stats")]
+        let latency = try_alloc!(crate::latency::LatencyStats::with_capacity(capacity));
+        Ok(Slab {
+            head: parts.head,
+            tail: parts.tail,
+            free_head: parts.free_head,
+            len: S::from_usize(parts.len),
+            vec_next: parts.next.into_boxed_slice(),
+            vec_prev: {
+                #[cfg(feature = "tagged")]
+                parts.prev.push(S::FREE);
+                parts.prev.into_boxed_slice()
+            },
+            data: parts.data.into_boxed_slice(),
+            #[cfg(not(any(feature = "releasefast", feature = "tagged")))]
+            bitmap,
+            #[cfg(feature = "generational")]
+            generations,
+            deferred_head: S::NUL,
+            deferred_len: S::from_usize(0),
+            #[cfg(feature = "leak_diagnostics")]
+            leak_hook: None,
+            #[cfg(feature = "changelog")]
+            changelog,
+            #[cfg(feature = "latency_stats")]
+            latency,
+        })
+    }
+
+    /// Reassemble a slab from its components, after checking that they
+    /// describe a valid slab. Return `Error::InvalidSlot` if they don't, and
+    /// `Error::OutOfMemory` if the internal tables can't be allocated.
+    /// This is O(capacity).
+    ///
+    /// On failure, the components are returned along with the error, so
+    /// that the elements they hold can still be recovered and dropped.
+    ///
+    /// # Safety
+    ///
+    /// The elements of occupied slots must be initialized.
+    pub unsafe fn from_raw_parts_checked(
+        parts: RawParts<D, S>,
+    ) -> Result<Self, (Error, RawParts<D, S>)> {
+        if let Err(e) = Self::check_raw_parts(&parts) {
+            return Err((e, parts));
+        }
+        Self::try_from_raw_parts(parts)
+    }
+
+    fn check_raw_parts(parts: &RawParts<D, S>) -> Result<(), Error> {
+        let capacity = parts.next.len();
+        if capacity > Self::max_capacity()
+            || parts.prev.len() != capacity
+            || parts.data.len() != capacity
+            || parts.len > capacity
+        {
+            return Err(Error::InvalidSlot);
+        }
+        #[cfg(feature = "generational")]
+        if parts.generations.len() != capacity {
+            return Err(Error::InvalidSlot);
+        }
+        let mut seen = crate::try_vec(capacity)?;
+        seen.resize(capacity, false);
+        let mut count = 0;
+        let (mut prev, mut slot) = (S::NUL, parts.head);
+        while slot != S::NUL {
+            if slot.to_usize() >= capacity
+                || seen[slot.to_usize()]
+                || parts.prev[slot.to_usize()] != prev
+            {
+                return Err(Error::InvalidSlot);
+            }
+            #[cfg(feature = "generational")]
+            if parts.generations[slot.to_usize()] & 1 == 0 {
+                return Err(Error::InvalidSlot);
+            }
+            seen[slot.to_usize()] = true;
+            count += 1;
+            (prev, slot) = (slot, parts.next[slot.to_usize()]);
+        }
+        if prev != parts.tail || count != parts.len {
+            return Err(Error::InvalidSlot);
+        }
+        let (mut prev, mut slot) = (S::NUL, parts.free_head);
+        while slot != S::NUL {
+            if slot.to_usize() >= capacity || seen[slot.to_usize()] {
+                return Err(Error::InvalidSlot);
+            }
+            let expected_prev = if cfg!(feature = "tagged") {
+                S::FREE
+            } else {
+                prev
+            };
+            if parts.prev[slot.to_usize()] != expected_prev {
+                return Err(Error::InvalidSlot);
+            }
+            #[cfg(feature = "generational")]
+            if parts.generations[slot.to_usize()] & 1 == 1 {
+                return Err(Error::InvalidSlot);
+            }
+            seen[slot.to_usize()] = true;
+            count += 1;
+            (prev, slot) = (slot, parts.next[slot.to_usize()]);
+        }
+        if count != capacity {
+            return Err(Error::InvalidSlot);
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_raw() {
+    let mut slab = Slab::with_capacity(3).unwrap();
+    assert_eq!(slab.raw_head(), None);
+    let [a, b, c] = slab.push_front_many(['a', 'b', 'c']).unwrap();
+    assert_eq!(slab.raw_head(), Some(c));
+    assert_eq!(slab.raw_tail(), Some(a));
+    assert_eq!(slab.raw_next(c), Some(b));
+    assert_eq!(slab.raw_prev(c), None);
+
+    // Swap the two last elements: c, a, b.
+    unsafe {
+        slab.raw_link(Some(c), Some(a));
+        slab.raw_link(Some(a), Some(b));
+        slab.raw_link(Some(b), None);
+        slab.raw_set_ends(Some(c), Some(b));
+    }
+    assert_eq!(slab.iter().collect::<String>(), "cab");
+    assert_eq!(slab.iter().rev().collect::<String>(), "bac");
+    assert_eq!(slab.pop_back(), Some('b'));
+}
+
+#[test]
+fn test_raw_parts() {
+    let mut slab = Slab::with_capacity(4).unwrap();
+    let [a, b, c] = slab.push_front_many([1, 2, 3]).unwrap();
+    slab.remove(b).unwrap();
+    let parts = slab.into_raw_parts();
+    assert_eq!((parts.head, parts.tail, parts.len), (c, a, 2));
+    let mut slab = unsafe { Slab::from_raw_parts_checked(parts) }.unwrap();
+    assert_eq!(slab.iter().copied().collect::<Vec<_>>(), [3, 1]);
+    assert!(!slab.is_occupied(b));
+    assert_eq!(slab.push_front(4), Ok(b));
+
+    let mut parts = slab.into_raw_parts();
+    parts.len = 2;
+    let (e, mut parts) = unsafe { Slab::from_raw_parts_checked(parts) }.unwrap_err();
+    assert_eq!(e, Error::InvalidSlot);
+    parts.len = 3;
+    let slab = unsafe { Slab::from_raw_parts_checked(parts) }.unwrap();
+    assert_eq!(slab.iter().copied().collect::<Vec<_>>(), [4, 3, 1]);
+
+    let mut slab = Slab::<_, u16>::with_slot_type(3).unwrap();
+    let [a, b] = slab.push_front_many(['a', 'b']).unwrap();
+    assert_eq!(slab.raw_next(b), Some(a));
+    let parts = slab.into_raw_parts();
+    assert_eq!(parts.free_head, 2);
+    let slab = unsafe { Slab::from_raw_parts_checked(parts) }.unwrap();
+    assert_eq!(slab.iter().collect::<String>(), "ba");
+}
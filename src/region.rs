@@ -0,0 +1,180 @@
+//! A slab whose slots are partitioned into regions.
+
+use alloc::vec::Vec;
+
+use crate::{Error, Slab, SlabIterator, Slot, SlotType};
+
+/// A slab whose capacity is partitioned into contiguous regions of slots,
+/// each with its own free list.
+///
+/// `push_front_in_region()` takes a free slot from the requested region,
+/// and only falls back to the following regions when it is full. This keeps
+/// related elements, for example the ones used by threads running on the
+/// same NUMA node, in the same area of memory, and slots freed by a region
+/// are reused by that region first.
+///
+/// Region identifiers are in the `0..regions()` range; methods panic if
+/// they are given an identifier outside of that range. With the `tagged`
+/// feature, taking a slot is O(n) unless it is the next free slot of the
+/// underlying slab.
+///
+/// Every region keeps its free slots in a separate vector, allocated
+/// upfront for the size of the region, so removing an element never
+/// allocates. This costs one slot per element of capacity, plus a vector
+/// per region, on top of the links of the underlying slab.
+#[derive(Debug)]
+pub struct RegionSlab<D, S: SlotType = Slot> {
+    slab: Slab<D, S>,
+    free: Vec<Vec<S>>,
+    region_size: usize,
+}
+
+impl<D> RegionSlab<D> {
+    /// Create a new slab with the given capacity, split into `regions`
+    /// regions of equal size (the last one may be smaller).
+    /// Return `Error::TooLarge` if `regions` is zero.
+    pub fn with_regions(capacity: usize, regions: usize) -> Result<Self, Error> {
+        Self::with_slot_type(capacity, regions)
+    }
+}
+
+impl<D, S: SlotType> RegionSlab<D, S> {
+    /// Create a new slab with the given capacity, split into `regions`
+    /// regions, using the slot type `S`.
+    /// Return `Error::TooLarge` if `regions` is zero.
+    pub fn with_slot_type(capacity: usize, regions: usize) -> Result<Self, Error> {
+        if regions == 0 {
+            return Err(Error::TooLarge);
+        }
+        let slab = Slab::with_slot_type(capacity)?;
+        let region_size = capacity.div_ceil(regions).max(1);
+        let mut free = crate::try_vec(regions)?;
+        for region in 0..regions {
+            let start = (region * region_size).min(capacity);
+            let end = ((region + 1) * region_size).min(capacity);
+            let mut slots = crate::try_vec(end - start)?;
+            // Lower slots are taken first.
+            slots.extend((start..end).rev().map(S::from_usize));
+            free.push(slots);
+        }
+        Ok(Self {
+            slab,
+            free,
+            region_size,
+        })
+    }
+
+    /// Return the capacity of the slab.
+    pub fn capacity(&self) -> usize {
+        self.slab.capacity()
+    }
+
+    /// Return the number of elements.
+    pub fn len(&self) -> usize {
+        self.slab.len()
+    }
+
+    /// Return true if there are no elements.
+    pub fn is_empty(&self) -> bool {
+        self.slab.is_empty()
+    }
+
+    /// Return the number of regions.
+    pub fn regions(&self) -> usize {
+        self.free.len()
+    }
+
+    /// Return the region a slot belongs to.
+    pub fn region_of(&self, slot: S) -> usize {
+        slot.to_usize() / self.region_size
+    }
+
+    /// Return the number of free slots in the given region.
+    pub fn free_in(&self, region: usize) -> usize {
+        self.free[region].len()
+    }
+
+    /// Prepend an element to the beginning of the list, storing it in the
+    /// given region if possible, or else in the first following region
+    /// (wrapping around) with a free slot.
+    /// Return its slot.
+    pub fn push_front_in_region(&mut self, region: usize, value: D) -> Result<S, Error> {
+        assert!(region < self.regions(), "invalid region");
+        let regions = self.regions();
+        let slot = (0..regions)
+            .map(|i| (region + i) % regions)
+            .find_map(|region| self.free[region].pop())
+            .ok_or(Error::Full)?;
+        unsafe { self.slab.push_front_at(slot, value) };
+        Ok(slot)
+    }
+
+    /// Return an element given its slot.
+    pub fn get(&self, slot: S) -> Result<&D, Error> {
+        self.slab.get_checked(slot)
+    }
+
+    /// Return a mutable reference to an element given its slot.
+    pub fn get_mut(&mut self, slot: S) -> Result<&mut D, Error> {
+        self.slab.get_mut_checked(slot)
+    }
+
+    /// Remove an element given its slot, and return its slot to its region.
+    pub fn remove(&mut self, slot: S) -> Result<(), Error> {
+        self.slab.remove_checked(slot)?;
+        let region = self.region_of(slot);
+        self.free[region].push(slot);
+        Ok(())
+    }
+
+    /// Remove and return the tail element of the list.
+    pub fn pop_back(&mut self) -> Option<D> {
+        let slot = self.slab.tail;
+        let value = self.slab.pop_back()?;
+        let region = self.region_of(slot);
+        self.free[region].push(slot);
+        Some(value)
+    }
+
+    /// Iterate over the elements, from the head to the tail.
+    pub fn iter(&self) -> SlabIterator<'_, D, S> {
+        self.slab.iter()
+    }
+}
+
+#[test]
+fn test_region_slab() {
+    assert_eq!(
+        RegionSlab::<u8>::with_regions(5, 0).err(),
+        Some(Error::TooLarge)
+    );
+
+    let mut slab = RegionSlab::with_regions(5, 2).unwrap();
+    assert_eq!(slab.regions(), 2);
+    assert_eq!(slab.free_in(0), 3);
+    assert_eq!(slab.free_in(1), 2);
+
+    assert_eq!(slab.push_front_in_region(1, 'a'), Ok(3));
+    assert_eq!(slab.push_front_in_region(1, 'b'), Ok(4));
+    assert_eq!(slab.push_front_in_region(1, 'c'), Ok(0));
+    assert_eq!(slab.region_of(4), 1);
+
+    slab.remove(3).unwrap();
+    assert_eq!(slab.free_in(1), 1);
+    assert_eq!(slab.push_front_in_region(1, 'd'), Ok(3));
+    assert_eq!(slab.pop_back(), Some('b'));
+    assert_eq!(slab.push_front_in_region(0, 'e'), Ok(1));
+    assert_eq!(slab.push_front_in_region(1, 'f'), Ok(4));
+    assert_eq!(slab.push_front_in_region(1, 'g'), Ok(2));
+    assert_eq!(slab.push_front_in_region(0, 'h'), Err(Error::Full));
+    assert_eq!(
+        slab.iter().copied().collect::<Vec<_>>(),
+        ['g', 'f', 'e', 'd', 'c']
+    );
+
+    let mut slab = RegionSlab::<_, u16>::with_slot_type(4, 2).unwrap();
+    assert_eq!(slab.push_front_in_region(1, 'a'), Ok(2u16));
+    assert_eq!(slab.region_of(2), 1);
+    slab.remove(2).unwrap();
+    assert_eq!(slab.free_in(1), 2);
+}
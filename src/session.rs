@@ -0,0 +1,191 @@
+//! A session table with idle timeouts, as commonly found in proxies.
+
+use core::hash::{BuildHasher, Hash};
+
+use crate::{
+    map::{DefaultHashBuilder, SlabMap},
+    Error, Sweep,
+};
+
+#[derive(Debug)]
+struct Session<V> {
+    value: V,
+    last_seen: u64,
+}
+
+/// A fixed-capacity table of sessions, ordered by recency.
+///
+/// Every access through `insert()` or `touch()` records the current time
+/// and makes the session the most recent one, so that `expire_idle()` only
+/// has to look at the oldest sessions. Time units are chosen by the caller,
+/// and `now` is expected to be monotonic.
+#[derive(Debug)]
+pub struct SessionTable<K, V, S = DefaultHashBuilder> {
+    map: SlabMap<K, Session<V>, S>,
+}
+
+impl<K: Hash + Eq, V> SessionTable<K, V, DefaultHashBuilder> {
+    /// Create a new table with the given capacity.
+    pub fn with_capacity(capacity: usize) -> Result<Self, Error> {
+        Self::with_capacity_and_hasher(capacity, Default::default())
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> SessionTable<K, V, S> {
+    /// Create a new table with the given capacity and hasher.
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Result<Self, Error> {
+        Ok(Self {
+            map: SlabMap::with_capacity_and_hasher(capacity, hash_builder)?,
+        })
+    }
+
+    /// Return the capacity of the table.
+    pub fn capacity(&self) -> usize {
+        self.map.capacity()
+    }
+
+    /// Return the number of sessions.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Return true if there are no sessions.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Return true if the table is full.
+    pub fn is_full(&self) -> bool {
+        self.map.is_full()
+    }
+
+    /// Insert or replace a session, and mark it as the most recent one.
+    /// Return the previous value if the session already existed, or
+    /// `Error::Full` if this is a new session and the table is full.
+    pub fn insert(&mut self, key: K, value: V, now: u64) -> Result<Option<V>, Error> {
+        if let Some(session) = self.map.touch(&key) {
+            session.last_seen = now;
+            return Ok(Some(core::mem::replace(&mut session.value, value)));
+        }
+        let session = Session {
+            value,
+            last_seen: now,
+        };
+        self.map.insert(key, session).map(|_| None)
+    }
+
+    /// Record activity on a session, and mark it as the most recent one.
+    /// Return its value, or `None` if the session doesn't exist.
+    pub fn touch(&mut self, key: &K, now: u64) -> Option<&mut V> {
+        let session = self.map.touch(key)?;
+        session.last_seen = now;
+        Some(&mut session.value)
+    }
+
+    /// Return a session without recording activity.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.map.get(key).map(|session| &session.value)
+    }
+
+    /// Return a mutable reference to a session without recording activity.
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.map.get_mut(key).map(|session| &mut session.value)
+    }
+
+    /// Return the time a session was last active.
+    pub fn last_seen(&self, key: &K) -> Option<u64> {
+        self.map.get(key).map(|session| session.last_seen)
+    }
+
+    /// Remove a session, and return its value.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.map.remove(key).map(|session| session.value)
+    }
+
+    /// Remove the sessions that have been idle for more than `max_idle`
+    /// time units, and return how many were removed.
+    pub fn expire_idle(&mut self, now: u64, max_idle: u64) -> usize {
+        self.expire_idle_with(now, max_idle, |_, _| {})
+    }
+
+    /// Remove the sessions that have been idle for more than `max_idle`
+    /// time units, handing each of them to a callback.
+    /// Return how many sessions were removed.
+    pub fn expire_idle_with(&mut self, now: u64, max_idle: u64, f: impl FnMut(K, V)) -> usize {
+        self.expire_some_with(now, max_idle, usize::MAX, f)
+            .removed()
+    }
+
+    /// Remove at most `max_items` of the sessions that have been idle for
+    /// more than `max_idle` time units.
+    /// This bounds the time spent in a single call; if `Sweep::Pending` is
+    /// returned, the remaining sessions can be expired by subsequent calls.
+    pub fn expire_some(&mut self, now: u64, max_idle: u64, max_items: usize) -> Sweep {
+        self.expire_some_with(now, max_idle, max_items, |_, _| {})
+    }
+
+    /// Remove at most `max_items` of the sessions that have been idle for
+    /// more than `max_idle` time units, handing each of them to a callback.
+    pub fn expire_some_with(
+        &mut self,
+        now: u64,
+        max_idle: u64,
+        max_items: usize,
+        mut f: impl FnMut(K, V),
+    ) -> Sweep {
+        let mut count = 0;
+        while let Some((_, session)) = self.map.oldest() {
+            if now.saturating_sub(session.last_seen) <= max_idle {
+                return Sweep::Done(count);
+            }
+            if count == max_items {
+                return Sweep::Pending(count);
+            }
+            if let Some((key, session)) = self.map.pop_oldest() {
+                f(key, session.value);
+                count += 1;
+            }
+        }
+        Sweep::Done(count)
+    }
+
+    /// Iterate over the sessions, from the least to the most recently active.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> + '_ {
+        self.map.iter().map(|(key, session)| (key, &session.value))
+    }
+}
+
+#[test]
+fn test_session_table() {
+    let mut table = SessionTable::with_capacity(3).unwrap();
+    table.insert(1, "a", 0).unwrap();
+    table.insert(2, "b", 5).unwrap();
+    table.insert(3, "c", 10).unwrap();
+    assert_eq!(table.insert(4, "d", 10), Err(Error::Full));
+    assert_eq!(table.touch(&1, 12), Some(&mut "a"));
+    assert_eq!(table.insert(3, "C", 13), Ok(Some("c")));
+    assert_eq!(table.iter().map(|(&k, _)| k).collect::<Vec<_>>(), [2, 1, 3]);
+
+    let mut expired = Vec::new();
+    assert_eq!(
+        table.expire_idle_with(20, 8, |k, v| expired.push((k, v))),
+        1
+    );
+    assert_eq!(expired, [(2, "b")]);
+    assert_eq!(table.expire_idle(20, 8), 0);
+    assert_eq!(table.last_seen(&1), Some(12));
+    assert_eq!(table.expire_idle(30, 8), 2);
+    assert!(table.is_empty());
+}
+
+#[test]
+fn test_expire_some() {
+    let mut table = SessionTable::with_capacity(4).unwrap();
+    for (key, now) in [(1, 0), (2, 1), (3, 2), (4, 20)] {
+        table.insert(key, (), now).unwrap();
+    }
+    assert_eq!(table.expire_some(20, 8, 2), Sweep::Pending(2));
+    assert_eq!(table.expire_some(20, 8, 2), Sweep::Done(1));
+    assert_eq!(table.expire_some(20, 8, 2), Sweep::Done(0));
+    assert_eq!(table.iter().map(|(&k, _)| k).collect::<Vec<_>>(), [4]);
+}
@@ -0,0 +1,218 @@
+//! A pool made of several fixed-capacity slabs.
+
+use alloc::vec::Vec;
+
+use crate::{Error, Slab, Slot, SlotType};
+
+/// A collection of equally sized slabs acting as a single pool.
+///
+/// When all the slabs are full, a new one is allocated, up to a maximum
+/// number of slabs. Existing slabs are never reallocated, so this provides
+/// some growth while keeping each allocation fixed.
+///
+/// A slot encodes both the index of the slab and the slot within that slab.
+#[derive(Debug)]
+pub struct SlabSet<D, S: SlotType = Slot> {
+    slabs: Vec<Slab<D, S>>,
+    slab_capacity: usize,
+    max_slabs: usize,
+    // Index of a slab that may have free slots.
+    hint: usize,
+}
+
+impl<D> SlabSet<D> {
+    /// Create a new, empty set of slabs of `slab_capacity` elements each,
+    /// that can grow up to `max_slabs` slabs.
+    /// No slabs are allocated until needed.
+    pub fn new(slab_capacity: usize, max_slabs: usize) -> Result<Self, Error> {
+        Self::with_slot_type(slab_capacity, max_slabs)
+    }
+}
+
+impl<D, S: SlotType> SlabSet<D, S> {
+    /// Create a new, empty set of slabs, using the slot type `S`.
+    /// Slots encode the index of the slab, so the total capacity must not
+    /// exceed `Slab::<D, S>::max_capacity()`.
+    pub fn with_slot_type(slab_capacity: usize, max_slabs: usize) -> Result<Self, Error> {
+        match slab_capacity.checked_mul(max_slabs) {
+            Some(total) if total <= Slab::<D, S>::max_capacity() => {}
+            _ => return Err(Error::TooLarge),
+        }
+        Ok(Self {
+            slabs: Vec::with_capacity(max_slabs),
+            slab_capacity,
+            max_slabs,
+            hint: 0,
+        })
+    }
+
+    /// Return the capacity of each slab.
+    pub fn slab_capacity(&self) -> usize {
+        self.slab_capacity
+    }
+
+    /// Return the number of slabs currently allocated.
+    pub fn slab_count(&self) -> usize {
+        self.slabs.len()
+    }
+
+    /// Return the capacity of the slabs currently allocated.
+    pub fn capacity(&self) -> usize {
+        self.slabs.len() * self.slab_capacity
+    }
+
+    /// Return the capacity the set can grow to.
+    pub fn max_capacity(&self) -> usize {
+        self.max_slabs * self.slab_capacity
+    }
+
+    /// Return the number of elements, across all slabs.
+    pub fn len(&self) -> usize {
+        self.slabs.iter().map(Slab::len).sum()
+    }
+
+    /// Return true if there are no elements.
+    pub fn is_empty(&self) -> bool {
+        self.slabs.iter().all(Slab::is_empty)
+    }
+
+    /// Allocate slabs ahead of time, so that at least `additional` more
+    /// elements can be inserted without any further allocations.
+    /// Return `Error::Full` if this would exceed the maximum number of slabs,
+    /// in which case no slabs are allocated.
+    pub fn reserve(&mut self, additional: usize) -> Result<(), Error> {
+        let free: usize = self.slabs.iter().map(Slab::free).sum();
+        let missing = additional.saturating_sub(free);
+        if missing == 0 {
+            return Ok(());
+        }
+        if self.slab_capacity == 0 {
+            return Err(Error::Full);
+        }
+        let needed = missing.div_ceil(self.slab_capacity);
+        if self.slabs.len() + needed > self.max_slabs {
+            return Err(Error::Full);
+        }
+        for _ in 0..needed {
+            self.slabs.push(Slab::with_slot_type(self.slab_capacity)?);
+        }
+        Ok(())
+    }
+
+    /// Prepend an element to the beginning of a slab with free slots,
+    /// allocating a new slab if all of them are full.
+    /// Return the slot of the new element.
+    pub fn push_front(&mut self, value: D) -> Result<S, Error> {
+        let count = self.slabs.len();
+        let index = match (0..count)
+            .map(|i| (self.hint + i) % count)
+            .find(|&i| !self.slabs[i].is_full())
+        {
+            Some(index) => index,
+            None => {
+                if count >= self.max_slabs || self.slab_capacity == 0 {
+                    return Err(Error::Full);
+                }
+                self.slabs.push(Slab::with_slot_type(self.slab_capacity)?);
+                count
+            }
+        };
+        self.hint = index;
+        let inner = self.slabs[index].push_front(value)?;
+        Ok(self.encode(index, inner))
+    }
+
+    /// Return an element given its slot.
+    pub fn get(&self, slot: S) -> Result<&D, Error> {
+        let (index, inner) = self.decode(slot)?;
+        self.slabs[index].get_checked(inner)
+    }
+
+    /// Return a mutable reference to an element given its slot.
+    pub fn get_mut(&mut self, slot: S) -> Result<&mut D, Error> {
+        let (index, inner) = self.decode(slot)?;
+        self.slabs[index].get_mut_checked(inner)
+    }
+
+    /// Remove an element given its slot.
+    pub fn remove(&mut self, slot: S) -> Result<(), Error> {
+        let (index, inner) = self.decode(slot)?;
+        self.slabs[index].remove_checked(inner)?;
+        self.hint = index;
+        Ok(())
+    }
+
+    /// Iterate over the `(slot, &element)` pairs of all the slabs,
+    /// slab by slab, each from the head to the tail.
+    pub fn iter(&self) -> impl Iterator<Item = (S, &D)> + '_ {
+        self.slabs
+            .iter()
+            .enumerate()
+            .flat_map(move |(index, slab)| {
+                slab.iter_slots().map(move |inner| {
+                    (self.encode(index, inner), unsafe {
+                        slab.get_unchecked(inner)
+                    })
+                })
+            })
+    }
+
+    fn encode(&self, index: usize, inner: S) -> S {
+        S::from_usize(index * self.slab_capacity + inner.to_usize())
+    }
+
+    fn decode(&self, slot: S) -> Result<(usize, S), Error> {
+        if self.slab_capacity == 0 {
+            return Err(Error::InvalidSlot);
+        }
+        let index = slot.to_usize() / self.slab_capacity;
+        if index >= self.slabs.len() {
+            return Err(Error::InvalidSlot);
+        }
+        Ok((index, S::from_usize(slot.to_usize() % self.slab_capacity)))
+    }
+}
+
+#[test]
+fn test_slab_set() {
+    let mut set = SlabSet::new(2, 3).unwrap();
+    assert_eq!(set.slab_count(), 0);
+    let a = set.push_front(1).unwrap();
+    let b = set.push_front(2).unwrap();
+    let c = set.push_front(3).unwrap();
+    assert_eq!(set.slab_count(), 2);
+    assert_eq!(c, 2);
+    assert_eq!(set.get(a), Ok(&1));
+    assert_eq!(set.get(c), Ok(&3));
+
+    set.remove(a).unwrap();
+    assert_eq!(set.get(a), Err(Error::InvalidSlot));
+    assert_eq!(set.push_front(4).unwrap(), a);
+    assert_eq!(set.slab_count(), 2);
+
+    set.reserve(3).unwrap();
+    assert_eq!(set.slab_count(), 3);
+    assert_eq!(set.reserve(6), Err(Error::Full));
+    set.push_front(5).unwrap();
+    set.push_front(6).unwrap();
+    set.push_front(7).unwrap();
+    assert_eq!(set.push_front(8), Err(Error::Full));
+    assert_eq!(set.len(), 6);
+
+    *set.get_mut(b).unwrap() = 20;
+    let mut values: Vec<_> = set.iter().map(|(slot, &v)| (slot, v)).collect();
+    values.sort();
+    assert_eq!(values[..2], [(a, 4), (b, 20)]);
+    assert!(set.get(6).is_err());
+
+    let mut set = SlabSet::<_, u16>::with_slot_type(2, 2).unwrap();
+    set.push_front('a').unwrap();
+    set.push_front('b').unwrap();
+    let c: u16 = set.push_front('c').unwrap();
+    assert_eq!((c, set.slab_count()), (2, 2));
+    assert_eq!(set.get(c), Ok(&'c'));
+    assert_eq!(
+        SlabSet::<u8, u16>::with_slot_type(256, 256).err(),
+        Some(Error::TooLarge)
+    );
+}
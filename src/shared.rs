@@ -0,0 +1,268 @@
+//! A list whose elements can be borrowed independently.
+
+use alloc::boxed::Box;
+use core::{
+    cell::{Cell, UnsafeCell},
+    ops::{Deref, DerefMut},
+};
+
+use crate::{Error, Slab, Slot, SlotType};
+
+// The borrow flag of an element that is mutably borrowed. Other values are
+// the number of shared borrows.
+const EXCLUSIVE: isize = -1;
+
+/// A list whose elements can be borrowed through a shared reference, with
+/// a `RefCell`-like borrow flag for every slot.
+///
+/// Elements are added and removed through a mutable reference, but `get()`
+/// and `get_mut()` only need a shared one, so that different elements can
+/// be borrowed at the same time, for example by callbacks that each update
+/// their own element. Borrowing an element mutably while it is already
+/// borrowed, or immutably while it is mutably borrowed, returns
+/// `Error::Borrowed` instead of panicking.
+///
+/// Borrow flags are not atomic, so a `SharedSlab` can't be shared between
+/// threads.
+#[derive(Debug)]
+pub struct SharedSlab<D, S: SlotType = Slot> {
+    slab: Slab<UnsafeCell<D>, S>,
+    borrows: Box<[Cell<isize>]>,
+}
+
+impl<D> SharedSlab<D> {
+    /// Create a new list with the given capacity.
+    pub fn with_capacity(capacity: usize) -> Result<Self, Error> {
+        Self::with_slot_type(capacity)
+    }
+}
+
+impl<D, S: SlotType> SharedSlab<D, S> {
+    /// Create a new list with the given capacity, using the slot type `S`.
+    pub fn with_slot_type(capacity: usize) -> Result<Self, Error> {
+        Ok(Self {
+            slab: Slab::with_slot_type(capacity)?,
+            borrows: (0..capacity).map(|_| Cell::new(0)).collect(),
+        })
+    }
+
+    /// Return the capacity of the list.
+    pub fn capacity(&self) -> usize {
+        self.slab.capacity()
+    }
+
+    /// Return the number of elements.
+    pub fn len(&self) -> usize {
+        self.slab.len()
+    }
+
+    /// Return true if the list is empty.
+    pub fn is_empty(&self) -> bool {
+        self.slab.is_empty()
+    }
+
+    /// Return true if the list is full.
+    pub fn is_full(&self) -> bool {
+        self.slab.is_full()
+    }
+
+    /// Prepend an element to the beginning of the list, and return its slot.
+    pub fn push_front(&mut self, value: D) -> Result<S, Error> {
+        let slot = self.slab.push_front(UnsafeCell::new(value))?;
+        self.reset_borrow(slot);
+        Ok(slot)
+    }
+
+    /// Remove an element given its slot, and return it.
+    pub fn remove(&mut self, slot: S) -> Result<D, Error> {
+        let value = self.slab.take_checked(slot)?;
+        self.reset_borrow(slot);
+        Ok(value.into_inner())
+    }
+
+    /// Remove and return the tail element of the list.
+    pub fn pop_back(&mut self) -> Option<D> {
+        let slot = self.slab.raw_tail()?;
+        let value = self.slab.pop_back()?;
+        self.reset_borrow(slot);
+        Some(value.into_inner())
+    }
+
+    // No borrow can be alive while the list is mutably borrowed, but the
+    // flag of a guard that was leaked with `mem::forget()` was never
+    // released. Don't let it outlive the element.
+    fn reset_borrow(&mut self, slot: S) {
+        self.borrows[slot.to_usize()].set(0);
+    }
+
+    /// Borrow an element given its slot.
+    /// Return `Error::Borrowed` if it is currently mutably borrowed.
+    pub fn get(&self, slot: S) -> Result<SlotRef<'_, D>, Error> {
+        let cell = self.slab.get_checked(slot)?;
+        let flag = &self.borrows[slot.to_usize()];
+        if flag.get() == EXCLUSIVE {
+            return Err(Error::Borrowed);
+        }
+        flag.set(flag.get() + 1);
+        // The flag prevents the element from being mutably borrowed, and
+        // removing it requires a mutable reference to the list.
+        let value = unsafe { &*cell.get() };
+        Ok(SlotRef { value, flag })
+    }
+
+    /// Mutably borrow an element given its slot.
+    /// Return `Error::Borrowed` if it is currently borrowed.
+    pub fn get_mut(&self, slot: S) -> Result<SlotRefMut<'_, D>, Error> {
+        let cell = self.slab.get_checked(slot)?;
+        let flag = &self.borrows[slot.to_usize()];
+        if flag.get() != 0 {
+            return Err(Error::Borrowed);
+        }
+        flag.set(EXCLUSIVE);
+        // The flag prevents any other borrow of the element.
+        let value = unsafe { &mut *cell.get() };
+        Ok(SlotRefMut { value, flag })
+    }
+
+    /// Iterate over the slots of the elements, from the head to the tail.
+    pub fn iter_slots(&self) -> impl Iterator<Item = S> + '_ {
+        self.slab.iter_slots()
+    }
+}
+
+/// A shared borrow of an element of a `SharedSlab`.
+pub struct SlotRef<'a, D> {
+    value: &'a D,
+    flag: &'a Cell<isize>,
+}
+
+impl<D> Deref for SlotRef<'_, D> {
+    type Target = D;
+
+    fn deref(&self) -> &D {
+        self.value
+    }
+}
+
+impl<D> Drop for SlotRef<'_, D> {
+    fn drop(&mut self) {
+        self.flag.set(self.flag.get() - 1);
+    }
+}
+
+// The element is stored in the list, not in the guard, and can't be
+// removed or moved while the guard borrows the list.
+#[cfg(feature = "stable_deref_trait")]
+unsafe impl<D> stable_deref_trait::StableDeref for SlotRef<'_, D> {}
+
+/// A mutable borrow of an element of a `SharedSlab`.
+pub struct SlotRefMut<'a, D> {
+    value: &'a mut D,
+    flag: &'a Cell<isize>,
+}
+
+impl<D> Deref for SlotRefMut<'_, D> {
+    type Target = D;
+
+    fn deref(&self) -> &D {
+        self.value
+    }
+}
+
+impl<D> DerefMut for SlotRefMut<'_, D> {
+    fn deref_mut(&mut self) -> &mut D {
+        self.value
+    }
+}
+
+impl<D> Drop for SlotRefMut<'_, D> {
+    fn drop(&mut self) {
+        self.flag.set(0);
+    }
+}
+
+#[cfg(feature = "stable_deref_trait")]
+unsafe impl<D> stable_deref_trait::StableDeref for SlotRefMut<'_, D> {}
+
+#[test]
+fn test_shared_slab() {
+    let mut slab = SharedSlab::with_capacity(3).unwrap();
+    let a = slab.push_front(1).unwrap();
+    let b = slab.push_front(2).unwrap();
+
+    {
+        let mut x = slab.get_mut(a).unwrap();
+        let mut y = slab.get_mut(b).unwrap();
+        core::mem::swap(&mut *x, &mut *y);
+        assert_eq!(slab.get(a).err(), Some(Error::Borrowed));
+        assert_eq!(slab.get_mut(b).err(), Some(Error::Borrowed));
+    }
+
+    let x = slab.get(a).unwrap();
+    let y = slab.get(a).unwrap();
+    assert_eq!((*x, *y), (2, 2));
+    assert_eq!(slab.get_mut(a).err(), Some(Error::Borrowed));
+    drop(x);
+    assert_eq!(slab.get_mut(a).err(), Some(Error::Borrowed));
+    drop(y);
+    *slab.get_mut(a).unwrap() += 10;
+    assert_eq!(*slab.get(a).unwrap(), 12);
+
+    assert_eq!(slab.iter_slots().collect::<alloc::vec::Vec<_>>(), [b, a]);
+    assert_eq!(slab.remove(b), Ok(1));
+    assert_eq!(slab.get(b).err(), Some(Error::InvalidSlot));
+    assert_eq!(slab.pop_back(), Some(12));
+    assert!(slab.is_empty());
+
+    // A leaked borrow doesn't outlive its element.
+    let a = slab.push_front(1).unwrap();
+    core::mem::forget(slab.get_mut(a).unwrap());
+    assert_eq!(slab.get(a).err(), Some(Error::Borrowed));
+    assert_eq!(slab.remove(a), Ok(1));
+    assert_eq!(slab.push_front(2), Ok(a));
+    assert_eq!(*slab.get(a).unwrap(), 2);
+    core::mem::forget(slab.get(a).unwrap());
+    assert_eq!(slab.pop_back(), Some(2));
+    assert_eq!(slab.push_front(3), Ok(a));
+    *slab.get_mut(a).unwrap() += 1;
+    assert_eq!(*slab.get(a).unwrap(), 4);
+
+    let mut slab = SharedSlab::<_, u16>::with_slot_type(2).unwrap();
+    let a: u16 = slab.push_front('a').unwrap();
+    *slab.get_mut(a).unwrap() = 'b';
+    assert_eq!(*slab.get(a).unwrap(), 'b');
+}
+
+#[cfg(feature = "stable_deref_trait")]
+#[test]
+fn test_stable_deref() {
+    use alloc::{boxed::Box, vec::Vec};
+    use stable_deref_trait::StableDeref;
+
+    fn address<P: StableDeref<Target = u64>>(p: &P) -> *const u64 {
+        &**p
+    }
+
+    let mut slab = SharedSlab::with_capacity(4).unwrap();
+    let a = slab.push_front(1).unwrap();
+    let b = slab.push_front(2).unwrap();
+    let expected_a = address(&slab.get(a).unwrap());
+    let expected_b = address(&slab.get_mut(b).unwrap());
+
+    // Moving a borrow doesn't move the element it points to.
+    let x = slab.get(a).unwrap();
+    let y = slab.get_mut(b).unwrap();
+    let boxed = Box::new(x);
+    let moved: Vec<_> = [y].into_iter().collect();
+    assert_eq!(address(&*boxed), expected_a);
+    assert_eq!(address(&moved[0]), expected_b);
+    drop((boxed, moved));
+
+    // Adding and removing other elements doesn't move it either.
+    let c = slab.push_front(3).unwrap();
+    slab.push_front(4).unwrap();
+    slab.remove(c).unwrap();
+    slab.pop_back();
+    slab.push_front(5).unwrap();
+    assert_eq!(address(&slab.get(b).unwrap()), expected_b);
+}
@@ -0,0 +1,445 @@
+//! A slab laid out in a caller-provided memory region, such as shared memory.
+
+use core::{
+    marker::PhantomData,
+    mem::{align_of, size_of, MaybeUninit},
+};
+
+use crate::{Error, Slot, SlotType};
+
+const MAGIC: u64 = 0x5341_4c42_4947_4154;
+
+#[repr(C)]
+struct Header {
+    magic: u64,
+    slot_size: u64,
+    elem_size: u64,
+    capacity: u64,
+    head: u64,
+    tail: u64,
+    free_head: u64,
+    len: u64,
+}
+
+// Offsets of the arrays following the header, and total size.
+#[derive(Debug)]
+struct Layout {
+    next: usize,
+    prev: usize,
+    occupied: usize,
+    data: usize,
+    size: usize,
+}
+
+impl Layout {
+    fn new<D, S>(capacity: usize) -> Option<Self> {
+        fn array<T>(offset: usize, capacity: usize) -> Option<(usize, usize)> {
+            let start = offset.checked_next_multiple_of(align_of::<T>())?;
+            let end = start.checked_add(size_of::<T>().checked_mul(capacity)?)?;
+            Some((start, end))
+        }
+        let (next, end) = array::<S>(size_of::<Header>(), capacity)?;
+        let (prev, end) = array::<S>(end, capacity)?;
+        let (occupied, end) = array::<u8>(end, capacity)?;
+        let (data, size) = array::<D>(end, capacity)?;
+        Some(Layout {
+            next,
+            prev,
+            occupied,
+            data,
+            size,
+        })
+    }
+}
+
+/// Element types that can be stored in a `ShmSlab`.
+///
+/// # Safety
+///
+/// The type must be plain data: every bit pattern of its size must be a
+/// valid value, and it must not contain references, pointers, or anything
+/// else whose meaning depends on the address space of a process, such as
+/// handles to resources owned by that process.
+pub unsafe trait PlainData: Copy + 'static {}
+
+macro_rules! impl_plain_data {
+    ($($t:ty),*) => {
+        $(
+            unsafe impl PlainData for $t {}
+        )*
+    };
+}
+
+impl_plain_data!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+unsafe impl<T: PlainData, const N: usize> PlainData for [T; N] {}
+
+/// A slab stored in a memory region provided by the caller.
+///
+/// The region contains a header followed by the link arrays and the
+/// elements. Links are slot numbers, not pointers, so the region can be
+/// mapped at different addresses by different processes, which can then
+/// all operate on the same list. Accesses are not synchronized: the caller
+/// must use external locking if the region is shared.
+///
+/// This is deliberately a narrower type than `Slab`, with its own layout
+/// and a small subset of its interface. Elements must implement
+/// `PlainData`, so that they don't own resources outside of the region and
+/// are meaningful in every process mapping it. Only the links read from
+/// the region are validated, so a corrupted region can cause panics, but
+/// not out-of-bounds accesses.
+#[derive(Debug)]
+pub struct ShmSlab<'a, D: PlainData, S: SlotType = Slot> {
+    base: *mut u8,
+    layout: Layout,
+    capacity: usize,
+    _region: PhantomData<(&'a mut [MaybeUninit<D>], S)>,
+}
+
+// Links are stored as `u64` in the header, whatever the slot type.
+fn encode<S: SlotType>(slot: S) -> u64 {
+    slot.to_usize() as u64
+}
+
+fn decode<S: SlotType>(slot: u64) -> S {
+    S::from_usize(slot as usize)
+}
+
+impl<'a, D: PlainData, S: SlotType> ShmSlab<'a, D, S> {
+    /// Return the size of the region required to store `capacity` elements.
+    pub fn required_size(capacity: usize) -> Option<usize> {
+        Layout::new::<D, S>(capacity).map(|layout| layout.size)
+    }
+
+    /// Initialize an empty list in a region, and attach to it.
+    /// The region must be aligned for both `u64` and `D`, or
+    /// `Error::Misaligned` is returned. `Error::TooLarge` is returned if it
+    /// is smaller than `required_size(capacity)`.
+    pub fn init_in(region: &'a mut [u8], capacity: usize) -> Result<Self, Error> {
+        if capacity > S::MAX_CAPACITY {
+            return Err(Error::TooLarge);
+        }
+        let mut slab = Self::new(region, capacity)?;
+        unsafe {
+            slab.base.cast::<Header>().write(Header {
+                magic: MAGIC,
+                slot_size: size_of::<S>() as u64,
+                elem_size: size_of::<D>() as u64,
+                capacity: capacity as u64,
+                head: encode(S::NUL),
+                tail: encode(S::NUL),
+                free_head: if capacity > 0 { 0 } else { encode(S::NUL) },
+                len: 0,
+            });
+        }
+        for i in 0..capacity {
+            slab.next()[i] = if i + 1 < capacity {
+                S::from_usize(i + 1)
+            } else {
+                S::NUL
+            };
+            slab.prev()[i] = if i > 0 { S::from_usize(i - 1) } else { S::NUL };
+            slab.occupied()[i] = 0;
+        }
+        Ok(slab)
+    }
+
+    /// Attach to a list previously initialized with `init_in()`, possibly
+    /// by another process.
+    ///
+    /// # Safety
+    ///
+    /// The region must have been initialized by `init_in()` for the same
+    /// element type, and only modified through a `ShmSlab` since then.
+    /// The header is checked, but the elements can't be.
+    pub unsafe fn attach_in(region: &'a mut [u8]) -> Result<Self, Error> {
+        if !(region.as_ptr() as usize).is_multiple_of(align_of::<Header>()) {
+            return Err(Error::Misaligned);
+        }
+        if region.len() < size_of::<Header>() {
+            return Err(Error::TooLarge);
+        }
+        let header = unsafe { region.as_ptr().cast::<Header>().read() };
+        if header.magic != MAGIC
+            || header.slot_size != size_of::<S>() as u64
+            || header.elem_size != size_of::<D>() as u64
+        {
+            return Err(Error::InvalidSlot);
+        }
+        let capacity = usize::try_from(header.capacity)
+            .ok()
+            .filter(|&capacity| capacity <= S::MAX_CAPACITY)
+            .ok_or(Error::TooLarge)?;
+        let slab = Self::new(region, capacity)?;
+        let valid = |slot: u64| slot == encode(S::NUL) || slot < header.capacity;
+        if !valid(header.head)
+            || !valid(header.tail)
+            || !valid(header.free_head)
+            || header.len > header.capacity
+        {
+            return Err(Error::InvalidSlot);
+        }
+        Ok(slab)
+    }
+
+    fn new(region: &'a mut [u8], capacity: usize) -> Result<Self, Error> {
+        let layout = Layout::new::<D, S>(capacity).ok_or(Error::TooLarge)?;
+        let align = align_of::<Header>().max(align_of::<D>());
+        if !(region.as_ptr() as usize).is_multiple_of(align) {
+            return Err(Error::Misaligned);
+        }
+        if region.len() < layout.size {
+            return Err(Error::TooLarge);
+        }
+        Ok(Self {
+            base: region.as_mut_ptr(),
+            layout,
+            capacity,
+            _region: PhantomData,
+        })
+    }
+
+    fn header(&self) -> &Header {
+        unsafe { &*self.base.cast::<Header>() }
+    }
+
+    fn header_mut(&mut self) -> &mut Header {
+        unsafe { &mut *self.base.cast::<Header>() }
+    }
+
+    fn next(&mut self) -> &mut [S] {
+        unsafe {
+            core::slice::from_raw_parts_mut(self.base.add(self.layout.next).cast(), self.capacity)
+        }
+    }
+
+    fn next_of(&self, slot: S) -> S {
+        let next: &[S] = unsafe {
+            core::slice::from_raw_parts(self.base.add(self.layout.next).cast(), self.capacity)
+        };
+        next[slot.to_usize()]
+    }
+
+    fn prev(&mut self) -> &mut [S] {
+        unsafe {
+            core::slice::from_raw_parts_mut(self.base.add(self.layout.prev).cast(), self.capacity)
+        }
+    }
+
+    fn occupied(&mut self) -> &mut [u8] {
+        unsafe {
+            core::slice::from_raw_parts_mut(self.base.add(self.layout.occupied), self.capacity)
+        }
+    }
+
+    fn data(&self) -> &[MaybeUninit<D>] {
+        unsafe {
+            core::slice::from_raw_parts(self.base.add(self.layout.data).cast(), self.capacity)
+        }
+    }
+
+    fn data_mut(&mut self) -> &mut [MaybeUninit<D>] {
+        unsafe {
+            core::slice::from_raw_parts_mut(self.base.add(self.layout.data).cast(), self.capacity)
+        }
+    }
+
+    fn is_occupied(&self, slot: S) -> bool {
+        slot.to_usize() < self.capacity
+            && unsafe { *self.base.add(self.layout.occupied + slot.to_usize()) } != 0
+    }
+
+    /// Return the capacity of the list.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Return the number of elements.
+    pub fn len(&self) -> usize {
+        self.header().len as usize
+    }
+
+    /// Return true if the list is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return true if the list is full.
+    pub fn is_full(&self) -> bool {
+        self.header().free_head == encode(S::NUL)
+    }
+
+    /// Return an element given its slot number.
+    pub fn get(&self, slot: S) -> Result<&D, Error> {
+        if !self.is_occupied(slot) {
+            return Err(Error::InvalidSlot);
+        }
+        Ok(unsafe { self.data()[slot.to_usize()].assume_init_ref() })
+    }
+
+    /// Return a mutable reference to an element given its slot number.
+    pub fn get_mut(&mut self, slot: S) -> Result<&mut D, Error> {
+        if !self.is_occupied(slot) {
+            return Err(Error::InvalidSlot);
+        }
+        Ok(unsafe { self.data_mut()[slot.to_usize()].assume_init_mut() })
+    }
+
+    /// Prepend an element to the beginning of the list, and return its slot.
+    pub fn push_front(&mut self, value: D) -> Result<S, Error> {
+        let slot: S = decode(self.header().free_head);
+        if slot == S::NUL {
+            return Err(Error::Full);
+        }
+        let next_free = self.next()[slot.to_usize()];
+        if next_free != S::NUL {
+            self.prev()[next_free.to_usize()] = S::NUL;
+        }
+        let head: S = decode(self.header().head);
+        if head != S::NUL {
+            self.prev()[head.to_usize()] = slot;
+        }
+        self.next()[slot.to_usize()] = head;
+        self.prev()[slot.to_usize()] = S::NUL;
+        self.occupied()[slot.to_usize()] = 1;
+        self.data_mut()[slot.to_usize()] = MaybeUninit::new(value);
+        let header = self.header_mut();
+        header.free_head = encode(next_free);
+        if header.head == encode(S::NUL) {
+            header.tail = encode(slot);
+        }
+        header.head = encode(slot);
+        header.len += 1;
+        Ok(slot)
+    }
+
+    /// Remove an element given its slot, and return it.
+    pub fn remove(&mut self, slot: S) -> Result<D, Error> {
+        if !self.is_occupied(slot) {
+            return Err(Error::InvalidSlot);
+        }
+        let value = unsafe { self.data()[slot.to_usize()].assume_init_read() };
+        let prev = self.prev()[slot.to_usize()];
+        let next = self.next()[slot.to_usize()];
+        if prev != S::NUL {
+            self.next()[prev.to_usize()] = next;
+        } else {
+            self.header_mut().head = encode(next);
+        }
+        if next != S::NUL {
+            self.prev()[next.to_usize()] = prev;
+        } else {
+            self.header_mut().tail = encode(prev);
+        }
+        let free_head: S = decode(self.header().free_head);
+        if free_head != S::NUL {
+            self.prev()[free_head.to_usize()] = slot;
+        }
+        self.next()[slot.to_usize()] = free_head;
+        self.prev()[slot.to_usize()] = S::NUL;
+        self.occupied()[slot.to_usize()] = 0;
+        let header = self.header_mut();
+        header.free_head = encode(slot);
+        header.len -= 1;
+        Ok(value)
+    }
+
+    /// Remove and return the tail element.
+    pub fn pop_back(&mut self) -> Option<D> {
+        let tail: S = decode(self.header().tail);
+        if tail == S::NUL {
+            return None;
+        }
+        self.remove(tail).ok()
+    }
+
+    /// Iterate over the `(slot, &element)` pairs, from the head to the tail.
+    pub fn iter(&self) -> impl Iterator<Item = (S, &D)> + '_ {
+        let mut slot: S = decode(self.header().head);
+        let mut remaining = self.len();
+        core::iter::from_fn(move || {
+            if remaining == 0 || !self.is_occupied(slot) {
+                return None;
+            }
+            remaining -= 1;
+            let current = slot;
+            slot = self.next_of(current);
+            Some((current, unsafe {
+                self.data()[current.to_usize()].assume_init_ref()
+            }))
+        })
+    }
+}
+
+#[test]
+fn test_shm() {
+    fn bytes_of(region: &mut [u64], size: usize) -> &mut [u8] {
+        unsafe { core::slice::from_raw_parts_mut(region.as_mut_ptr().cast::<u8>(), size) }
+    }
+
+    let capacity = 3;
+    let size = ShmSlab::<u64>::required_size(capacity).unwrap();
+    let mut region = alloc::vec![0u64; size.div_ceil(8)];
+    let mut slab = ShmSlab::<u64>::init_in(bytes_of(&mut region, size), capacity).unwrap();
+    let a = slab.push_front(1).unwrap();
+    let b = slab.push_front(2).unwrap();
+    slab.push_front(3).unwrap();
+    assert_eq!(slab.push_front(4), Err(Error::Full));
+    assert_eq!(slab.remove(b), Ok(2));
+
+    // Another process would map the same region, possibly elsewhere.
+    let mut copy = region.clone();
+    let mut attached = unsafe { ShmSlab::<u64>::attach_in(bytes_of(&mut copy, size)) }.unwrap();
+    assert_eq!(attached.len(), 2);
+    assert_eq!(attached.iter().map(|(_, &v)| v).collect::<Vec<_>>(), [3, 1]);
+    assert_eq!(attached.get(a), Ok(&1));
+    assert!(attached.get(b).is_err());
+    assert_eq!(attached.pop_back(), Some(1));
+    assert_eq!(attached.push_front(5), Ok(a));
+    assert_eq!(attached.iter().map(|(_, &v)| v).collect::<Vec<_>>(), [5, 3]);
+
+    let wrong_type = unsafe { ShmSlab::<u32>::attach_in(bytes_of(&mut region, size)) };
+    assert_eq!(wrong_type.err(), Some(Error::InvalidSlot));
+    let wrong_slot = unsafe { ShmSlab::<u64, u16>::attach_in(bytes_of(&mut region, size)) };
+    assert_eq!(wrong_slot.err(), Some(Error::InvalidSlot));
+
+    // Misaligned and undersized regions are told apart.
+    let bytes = bytes_of(&mut region, size);
+    let misaligned = ShmSlab::<u64>::init_in(&mut bytes[1..], 1);
+    assert_eq!(misaligned.err(), Some(Error::Misaligned));
+    let misaligned = unsafe { ShmSlab::<u64>::attach_in(&mut bytes[4..]) };
+    assert_eq!(misaligned.err(), Some(Error::Misaligned));
+    let small = ShmSlab::<u64>::init_in(&mut bytes[..size - 1], capacity);
+    assert_eq!(small.err(), Some(Error::TooLarge));
+
+    // Other slot types.
+    let size = ShmSlab::<u8, u16>::required_size(2).unwrap();
+    let mut region = alloc::vec![0u64; size.div_ceil(8)];
+    let mut slab = ShmSlab::<u8, u16>::init_in(bytes_of(&mut region, size), 2).unwrap();
+    let a: u16 = slab.push_front(1).unwrap();
+    slab.push_front(2).unwrap();
+    assert_eq!(slab.push_front(3), Err(Error::Full));
+    assert_eq!(slab.remove(a), Ok(1));
+    let attached = unsafe { ShmSlab::<u8, u16>::attach_in(bytes_of(&mut region, size)) }.unwrap();
+    assert_eq!(attached.iter().map(|(_, &v)| v).collect::<Vec<_>>(), [2]);
+
+    // User-defined plain data.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    #[repr(C)]
+    struct Point {
+        x: i32,
+        y: [f32; 2],
+    }
+
+    unsafe impl PlainData for Point {}
+
+    let size = ShmSlab::<Point>::required_size(1).unwrap();
+    let mut region = alloc::vec![0u64; size.div_ceil(8)];
+    let mut slab = ShmSlab::<Point>::init_in(bytes_of(&mut region, size), 1).unwrap();
+    let point = Point {
+        x: 1,
+        y: [2.0, 3.0],
+    };
+    let slot = slab.push_front(point).unwrap();
+    assert_eq!(slab.get(slot), Ok(&point));
+}
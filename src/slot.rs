@@ -0,0 +1,76 @@
+//! The integer types that can be used as slots.
+
+use core::{
+    fmt::{Debug, Display},
+    hash::Hash,
+};
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// An unsigned integer type that can be used as a slot, selected with the
+/// second type parameter of `Slab`.
+///
+/// It is implemented for `u16`, `u32`, `u64` and `usize`. The largest value
+/// of the type is reserved, so a list with `u16` slots can hold up to 65534
+/// elements.
+pub trait SlotType:
+    sealed::Sealed + Copy + Eq + Ord + Hash + Debug + Display + Send + Sync + 'static
+{
+    /// The value representing the absence of a slot.
+    #[doc(hidden)]
+    const NUL: Self;
+
+    /// The backward link of free slots, when occupancy is tracked by
+    /// tagging them.
+    #[doc(hidden)]
+    const FREE: Self;
+
+    /// The largest capacity of a list using this slot type. `NUL` is
+    /// reserved, so slots go up to `NUL - 1`, excluded.
+    const MAX_CAPACITY: usize;
+
+    /// Convert an index to a slot. The index must be lower than
+    /// `MAX_CAPACITY`, or be the value of `NUL`.
+    #[doc(hidden)]
+    fn from_usize(index: usize) -> Self;
+
+    /// Convert a slot to an index.
+    #[doc(hidden)]
+    fn to_usize(self) -> usize;
+}
+
+macro_rules! impl_slot_type {
+    ($($t:ty),*) => {
+        $(
+            impl sealed::Sealed for $t {}
+
+            impl SlotType for $t {
+                const NUL: Self = <$t>::MAX;
+                const FREE: Self = <$t>::MAX - 1;
+                const MAX_CAPACITY: usize = if (<$t>::MAX - 1) as u128 > usize::MAX as u128 {
+                    usize::MAX
+                } else {
+                    (<$t>::MAX - 1) as usize
+                };
+
+                #[inline(always)]
+                fn from_usize(index: usize) -> Self {
+                    invariant!(
+                        index <= Self::MAX_CAPACITY || index as u128 == <$t>::MAX as u128,
+                        "index out of range for the slot type"
+                    );
+                    index as $t
+                }
+
+                #[inline(always)]
+                fn to_usize(self) -> usize {
+                    self as usize
+                }
+            }
+        )*
+    };
+}
+
+impl_slot_type!(u16, u32, u64, usize);
@@ -0,0 +1,281 @@
+//! A small list stored inline, without any heap allocations.
+
+use core::mem::MaybeUninit;
+
+use crate::{Error, Slot, SlotType, NUL};
+
+/// A list with a capacity of `N` elements, whose links and elements are
+/// stored inline.
+///
+/// It only provides the core of the `Slab` interface: `push_front()`,
+/// `get()`, `get_mut()`, `contains_slot()`, `remove()`, `pop_back()`,
+/// `clear()`, `iter()` and the length and capacity queries, with the
+/// default slot type. It is meant for many small lists, for example one
+/// per entity, where allocating each of them would dominate. Since
+/// everything is stored inline, moving a `SmallSlab` moves its elements.
+pub struct SmallSlab<D, const N: usize> {
+    head: Slot,
+    tail: Slot,
+    free_head: Slot,
+    len: Slot,
+    next: [Slot; N],
+    prev: [Slot; N],
+    occupied: [bool; N],
+    data: [MaybeUninit<D>; N],
+}
+
+impl<D, const N: usize> SmallSlab<D, N> {
+    /// Create a new, empty list.
+    /// Panics if `N` is larger than `Slab::max_capacity()`.
+    ///
+    /// This is a `const fn`, so that a list, including its free list, can
+    /// be built at compile time and stored in a `static`, without any
+    /// initialization at startup.
+    pub const fn new() -> Self {
+        assert!(N <= Slot::MAX_CAPACITY, "capacity is too large");
+        let mut next = [NUL; N];
+        let mut i = 0;
+        while i + 1 < N {
+            next[i] = i as Slot + 1;
+            i += 1;
+        }
+        Self {
+            head: NUL,
+            tail: NUL,
+            free_head: if N > 0 { 0 } else { NUL },
+            len: 0,
+            next,
+            prev: [NUL; N],
+            occupied: [false; N],
+            data: [const { MaybeUninit::uninit() }; N],
+        }
+    }
+
+    /// Return the capacity of the list.
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Return the number of elements.
+    pub fn len(&self) -> usize {
+        self.len.to_usize()
+    }
+
+    /// Return the number of elements that can still be added.
+    pub fn free(&self) -> usize {
+        N - self.len()
+    }
+
+    /// Return true if the list is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return true if the list is full.
+    pub fn is_full(&self) -> bool {
+        self.len() == N
+    }
+
+    /// Check if the slot contains an element.
+    pub fn contains_slot(&self, slot: Slot) -> bool {
+        self.occupied.get(slot.to_usize()).copied().unwrap_or(false)
+    }
+
+    /// Return an element given its slot number.
+    pub fn get(&self, slot: Slot) -> Result<&D, Error> {
+        if !self.contains_slot(slot) {
+            return Err(Error::InvalidSlot);
+        }
+        Ok(unsafe { self.data[slot.to_usize()].assume_init_ref() })
+    }
+
+    /// Return a mutable reference to an element given its slot number.
+    pub fn get_mut(&mut self, slot: Slot) -> Result<&mut D, Error> {
+        if !self.contains_slot(slot) {
+            return Err(Error::InvalidSlot);
+        }
+        Ok(unsafe { self.data[slot.to_usize()].assume_init_mut() })
+    }
+
+    /// Prepend an element to the beginning of the list.
+    /// Return its slot.
+    pub fn push_front(&mut self, value: D) -> Result<Slot, Error> {
+        let slot = self.free_head;
+        if slot == NUL {
+            return Err(Error::Full);
+        }
+        self.free_head = self.next[slot.to_usize()];
+        self.next[slot.to_usize()] = self.head;
+        self.prev[slot.to_usize()] = NUL;
+        if self.head != NUL {
+            self.prev[self.head.to_usize()] = slot;
+        } else {
+            self.tail = slot;
+        }
+        self.head = slot;
+        self.data[slot.to_usize()] = MaybeUninit::new(value);
+        self.occupied[slot.to_usize()] = true;
+        self.len += 1;
+        Ok(slot)
+    }
+
+    /// Remove an element given its slot.
+    pub fn remove(&mut self, slot: Slot) -> Result<(), Error> {
+        self.take(slot).map(drop)
+    }
+
+    /// Remove and return the tail element of the list.
+    pub fn pop_back(&mut self) -> Option<D> {
+        self.take(self.tail).ok()
+    }
+
+    fn take(&mut self, slot: Slot) -> Result<D, Error> {
+        if !self.contains_slot(slot) {
+            return Err(Error::InvalidSlot);
+        }
+        let value = unsafe { self.data[slot.to_usize()].assume_init_read() };
+        let prev = self.prev[slot.to_usize()];
+        let next = self.next[slot.to_usize()];
+        if prev != NUL {
+            self.next[prev.to_usize()] = next;
+        } else {
+            self.head = next;
+        }
+        if next != NUL {
+            self.prev[next.to_usize()] = prev;
+        } else {
+            self.tail = prev;
+        }
+        self.next[slot.to_usize()] = self.free_head;
+        self.free_head = slot;
+        self.occupied[slot.to_usize()] = false;
+        self.len -= 1;
+        Ok(value)
+    }
+
+    /// Remove all the elements.
+    pub fn clear(&mut self) {
+        while self.pop_back().is_some() {}
+    }
+
+    /// Iterate over the elements, from the head to the tail.
+    pub fn iter(&self) -> SmallSlabIter<'_, D, N> {
+        SmallSlabIter {
+            list: self,
+            slot: self.head,
+            remaining: self.len(),
+        }
+    }
+}
+
+impl<D, const N: usize> Default for SmallSlab<D, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<D, const N: usize> Drop for SmallSlab<D, N> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<D: core::fmt::Debug, const N: usize> core::fmt::Debug for SmallSlab<D, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<D, const N: usize> core::ops::Index<Slot> for SmallSlab<D, N> {
+    type Output = D;
+
+    fn index(&self, slot: Slot) -> &D {
+        self.get(slot).expect("invalid slot")
+    }
+}
+
+impl<D, const N: usize> core::ops::IndexMut<Slot> for SmallSlab<D, N> {
+    fn index_mut(&mut self, slot: Slot) -> &mut D {
+        self.get_mut(slot).expect("invalid slot")
+    }
+}
+
+/// An iterator over the elements of a `SmallSlab`, from the head to the tail.
+pub struct SmallSlabIter<'a, D, const N: usize> {
+    list: &'a SmallSlab<D, N>,
+    slot: Slot,
+    remaining: usize,
+}
+
+impl<'a, D, const N: usize> Iterator for SmallSlabIter<'a, D, N> {
+    type Item = &'a D;
+
+    fn next(&mut self) -> Option<&'a D> {
+        if self.slot == NUL {
+            return None;
+        }
+        let value = unsafe { self.list.data[self.slot.to_usize()].assume_init_ref() };
+        self.slot = self.list.next[self.slot.to_usize()];
+        self.remaining -= 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<D, const N: usize> ExactSizeIterator for SmallSlabIter<'_, D, N> {}
+
+impl<'a, D, const N: usize> IntoIterator for &'a SmallSlab<D, N> {
+    type IntoIter = SmallSlabIter<'a, D, N>;
+    type Item = &'a D;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[test]
+fn test_small_slab() {
+    use std::rc::Rc;
+
+    let counter = Rc::new(());
+    let mut slab = SmallSlab::<Rc<()>, 3>::new();
+    let a = slab.push_front(counter.clone()).unwrap();
+    let b = slab.push_front(counter.clone()).unwrap();
+    let c = slab.push_front(counter.clone()).unwrap();
+    assert_eq!(slab.push_front(counter.clone()), Err(Error::Full));
+    assert!(slab.is_full());
+    slab.remove(b).unwrap();
+    assert_eq!(slab.remove(b), Err(Error::InvalidSlot));
+    assert_eq!(slab.push_front(counter.clone()), Ok(b));
+    assert_eq!(slab.len(), 3);
+    assert!(slab.pop_back().is_some());
+    assert!(!slab.contains_slot(a));
+    assert_eq!(slab.iter().count(), 2);
+    assert_eq!(slab.iter().len(), 2);
+    assert!(slab.get(c).is_ok());
+    drop(slab);
+    assert_eq!(Rc::strong_count(&counter), 1);
+
+    let mut slab = SmallSlab::<u8, 0>::default();
+    assert_eq!(slab.push_front(1), Err(Error::Full));
+}
+
+#[test]
+fn test_small_slab_const() {
+    static EMPTY: SmallSlab<u32, 4> = SmallSlab::new();
+    assert!(EMPTY.is_empty());
+    assert_eq!(EMPTY.free(), 4);
+
+    #[cfg(feature = "std")]
+    {
+        static LIST: std::sync::Mutex<SmallSlab<u32, 4>> = std::sync::Mutex::new(SmallSlab::new());
+        let mut list = LIST.lock().unwrap();
+        let slots: Vec<_> = (0..4).map(|i| list.push_front(i).unwrap()).collect();
+        assert_eq!(slots, [0, 1, 2, 3]);
+        assert_eq!(list.push_front(4), Err(Error::Full));
+        assert!(list.iter().eq([3, 2, 1, 0].iter()));
+    }
+}
@@ -0,0 +1,170 @@
+//! An asynchronous queue, draining elements as a `Stream`.
+
+use core::{
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+use std::sync::{Arc, Mutex};
+
+use futures_core::Stream;
+
+use crate::{Error, Slab, Slot};
+
+#[derive(Debug)]
+struct Inner<D> {
+    slab: Slab<D>,
+    waker: Option<Waker>,
+    producers: usize,
+}
+
+/// The producing side of an asynchronous queue.
+///
+/// It can be cloned to add elements from multiple places.
+#[derive(Debug)]
+pub struct Producer<D> {
+    inner: Arc<Mutex<Inner<D>>>,
+}
+
+/// The consuming side of an asynchronous queue.
+///
+/// It is a `Stream` of the elements, from the oldest to the newest. The
+/// stream ends once the queue is empty and all the producers have been
+/// dropped.
+#[derive(Debug)]
+pub struct AsyncDrain<D> {
+    inner: Arc<Mutex<Inner<D>>>,
+}
+
+/// Create an asynchronous queue with the given capacity.
+pub fn channel<D>(capacity: usize) -> Result<(Producer<D>, AsyncDrain<D>), Error> {
+    let inner = Arc::new(Mutex::new(Inner {
+        slab: Slab::with_capacity(capacity)?,
+        waker: None,
+        producers: 1,
+    }));
+    let producer = Producer {
+        inner: inner.clone(),
+    };
+    Ok((producer, AsyncDrain { inner }))
+}
+
+impl<D> Producer<D> {
+    /// Prepend an element to the queue, and wake the consumer if the queue
+    /// was empty.
+    pub fn push_front(&self, value: D) -> Result<Slot, Error> {
+        let mut inner = self.inner.lock().unwrap();
+        let was_empty = inner.slab.is_empty();
+        let slot = inner.slab.push_front(value)?;
+        if was_empty {
+            if let Some(waker) = inner.waker.take() {
+                drop(inner);
+                waker.wake();
+            }
+        }
+        Ok(slot)
+    }
+
+    /// Return the number of elements waiting to be consumed.
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().slab.len()
+    }
+
+    /// Return true if there are no elements waiting to be consumed.
+    pub fn is_empty(&self) -> bool {
+        self.inner.lock().unwrap().slab.is_empty()
+    }
+
+    /// Return true if no more elements can be added.
+    pub fn is_full(&self) -> bool {
+        self.inner.lock().unwrap().slab.is_full()
+    }
+}
+
+impl<D> Clone for Producer<D> {
+    fn clone(&self) -> Self {
+        self.inner.lock().unwrap().producers += 1;
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<D> Drop for Producer<D> {
+    fn drop(&mut self) {
+        let Ok(mut inner) = self.inner.lock() else {
+            return;
+        };
+        inner.producers -= 1;
+        if inner.producers == 0 {
+            if let Some(waker) = inner.waker.take() {
+                drop(inner);
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl<D> Stream for AsyncDrain<D> {
+    type Item = D;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<D>> {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(value) = inner.slab.pop_back() {
+            return Poll::Ready(Some(value));
+        }
+        if inner.producers == 0 {
+            return Poll::Ready(None);
+        }
+        match &mut inner.waker {
+            Some(waker) => waker.clone_from(cx.waker()),
+            waker => *waker = Some(cx.waker().clone()),
+        }
+        Poll::Pending
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let inner = self.inner.lock().unwrap();
+        let len = inner.slab.len();
+        if inner.producers == 0 {
+            (len, Some(len))
+        } else {
+            (len, None)
+        }
+    }
+}
+
+#[test]
+fn test_async_drain() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::task::Wake;
+
+    struct Counter(AtomicUsize);
+
+    impl Wake for Counter {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let counter = Arc::new(Counter(AtomicUsize::new(0)));
+    let waker = Waker::from(counter.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    let (producer, mut drain) = channel(2).unwrap();
+    let mut drain = Pin::new(&mut drain);
+    assert_eq!(drain.as_mut().poll_next(&mut cx), Poll::Pending);
+
+    producer.push_front(1).unwrap();
+    producer.push_front(2).unwrap();
+    assert!(producer.push_front(3).is_err());
+    assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+
+    assert_eq!(drain.as_mut().poll_next(&mut cx), Poll::Ready(Some(1)));
+    let other = producer.clone();
+    drop(producer);
+    assert_eq!(drain.as_mut().poll_next(&mut cx), Poll::Ready(Some(2)));
+    assert_eq!(drain.as_mut().poll_next(&mut cx), Poll::Pending);
+    drop(other);
+    assert_eq!(counter.0.load(Ordering::SeqCst), 2);
+    assert_eq!(drain.as_mut().poll_next(&mut cx), Poll::Ready(None));
+}
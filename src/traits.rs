@@ -0,0 +1,201 @@
+//! A common interface to the list types.
+
+use crate::{
+    small::{SmallSlab, SmallSlabIter},
+    DefaultCapacity, Error, Slab, SlabIterator, Slot, SlotType,
+};
+
+/// The operations shared by all the list types, so that code can be
+/// generic over how the elements are stored.
+pub trait SlabLike<D> {
+    /// The type of the slots.
+    type Slot: SlotType;
+
+    /// The iterator returned by `iter()`.
+    type Iter<'a>: Iterator<Item = &'a D>
+    where
+        Self: 'a,
+        D: 'a;
+
+    /// Return the capacity of the list.
+    fn capacity(&self) -> usize;
+
+    /// Return the number of elements.
+    fn len(&self) -> usize;
+
+    /// Return true if the list is empty.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return true if the list is full.
+    fn is_full(&self) -> bool {
+        self.len() == self.capacity()
+    }
+
+    /// Prepend an element to the beginning of the list, and return its slot.
+    fn push_front(&mut self, value: D) -> Result<Self::Slot, Error>;
+
+    /// Remove and return the tail element of the list.
+    fn pop_back(&mut self) -> Option<D>;
+
+    /// Return an element given its slot.
+    fn get(&self, slot: Self::Slot) -> Result<&D, Error>;
+
+    /// Return a mutable reference to an element given its slot.
+    fn get_mut(&mut self, slot: Self::Slot) -> Result<&mut D, Error>;
+
+    /// Remove an element given its slot.
+    fn remove(&mut self, slot: Self::Slot) -> Result<(), Error>;
+
+    /// Iterate over the elements, from the head to the tail.
+    fn iter(&self) -> Self::Iter<'_>;
+}
+
+impl<D, S: SlotType> SlabLike<D> for Slab<D, S> {
+    type Slot = S;
+
+    type Iter<'a>
+        = SlabIterator<'a, D, S>
+    where
+        D: 'a;
+
+    fn capacity(&self) -> usize {
+        Slab::capacity(self)
+    }
+
+    fn len(&self) -> usize {
+        Slab::len(self)
+    }
+
+    fn push_front(&mut self, value: D) -> Result<S, Error> {
+        Slab::push_front(self, value)
+    }
+
+    fn pop_back(&mut self) -> Option<D> {
+        Slab::pop_back(self)
+    }
+
+    fn get(&self, slot: S) -> Result<&D, Error> {
+        Slab::get(self, slot)
+    }
+
+    fn get_mut(&mut self, slot: S) -> Result<&mut D, Error> {
+        Slab::get_mut(self, slot)
+    }
+
+    fn remove(&mut self, slot: S) -> Result<(), Error> {
+        Slab::remove(self, slot)
+    }
+
+    fn iter(&self) -> SlabIterator<'_, D, S> {
+        Slab::iter(self)
+    }
+}
+
+impl<D, const N: usize> SlabLike<D> for DefaultCapacity<D, N> {
+    type Slot = Slot;
+
+    type Iter<'a>
+        = SlabIterator<'a, D>
+    where
+        D: 'a;
+
+    fn capacity(&self) -> usize {
+        Slab::capacity(self)
+    }
+
+    fn len(&self) -> usize {
+        Slab::len(self)
+    }
+
+    fn push_front(&mut self, value: D) -> Result<Slot, Error> {
+        Slab::push_front(self, value)
+    }
+
+    fn pop_back(&mut self) -> Option<D> {
+        Slab::pop_back(self)
+    }
+
+    fn get(&self, slot: Slot) -> Result<&D, Error> {
+        Slab::get(self, slot)
+    }
+
+    fn get_mut(&mut self, slot: Slot) -> Result<&mut D, Error> {
+        Slab::get_mut(self, slot)
+    }
+
+    fn remove(&mut self, slot: Slot) -> Result<(), Error> {
+        Slab::remove(self, slot)
+    }
+
+    fn iter(&self) -> SlabIterator<'_, D> {
+        Slab::iter(self)
+    }
+}
+
+impl<D, const N: usize> SlabLike<D> for SmallSlab<D, N> {
+    type Slot = Slot;
+
+    type Iter<'a>
+        = SmallSlabIter<'a, D, N>
+    where
+        D: 'a;
+
+    fn capacity(&self) -> usize {
+        SmallSlab::capacity(self)
+    }
+
+    fn len(&self) -> usize {
+        SmallSlab::len(self)
+    }
+
+    fn push_front(&mut self, value: D) -> Result<Slot, Error> {
+        SmallSlab::push_front(self, value)
+    }
+
+    fn pop_back(&mut self) -> Option<D> {
+        SmallSlab::pop_back(self)
+    }
+
+    fn get(&self, slot: Slot) -> Result<&D, Error> {
+        SmallSlab::get(self, slot)
+    }
+
+    fn get_mut(&mut self, slot: Slot) -> Result<&mut D, Error> {
+        SmallSlab::get_mut(self, slot)
+    }
+
+    fn remove(&mut self, slot: Slot) -> Result<(), Error> {
+        SmallSlab::remove(self, slot)
+    }
+
+    fn iter(&self) -> SmallSlabIter<'_, D, N> {
+        SmallSlab::iter(self)
+    }
+}
+
+#[test]
+fn test_slab_like() {
+    fn exercise(list: &mut impl SlabLike<u32>) -> Vec<u32> {
+        let a = list.push_front(1).unwrap();
+        let b = list.push_front(2).unwrap();
+        list.push_front(3).unwrap();
+        assert!(list.is_full());
+        assert!(list.push_front(4).is_err());
+        *list.get_mut(b).unwrap() = 20;
+        list.remove(a).unwrap();
+        #[cfg(not(feature = "releasefast"))]
+        assert!(list.get(a).is_err());
+        assert_eq!(list.pop_back(), Some(20));
+        list.iter().copied().collect()
+    }
+
+    assert_eq!(exercise(&mut Slab::with_capacity(3).unwrap()), [3]);
+    assert_eq!(exercise(&mut SmallSlab::<_, 3>::new()), [3]);
+    assert_eq!(exercise(&mut DefaultCapacity::<_, 3>::default()), [3]);
+    assert_eq!(
+        exercise(&mut Slab::<_, u16>::with_slot_type(3).unwrap()),
+        [3]
+    );
+}
@@ -0,0 +1,106 @@
+//! A registry of wakers with stable identifiers.
+
+use core::task::Waker;
+
+use crate::{Error, Slab, Slot};
+
+/// A fixed-capacity registry of wakers, for executors and I/O drivers.
+///
+/// Every registered waker is identified by a slot that doesn't change until
+/// it is removed. Wakers are woken by reference, so they remain registered
+/// after being woken.
+#[derive(Debug)]
+pub struct WakerSlab {
+    slab: Slab<Waker>,
+}
+
+impl WakerSlab {
+    /// Create a new registry with the given capacity.
+    pub fn with_capacity(capacity: usize) -> Result<Self, Error> {
+        Ok(Self {
+            slab: Slab::with_capacity(capacity)?,
+        })
+    }
+
+    /// Return the capacity of the registry.
+    pub fn capacity(&self) -> usize {
+        self.slab.capacity()
+    }
+
+    /// Return the number of registered wakers.
+    pub fn len(&self) -> usize {
+        self.slab.len()
+    }
+
+    /// Return true if no wakers are registered.
+    pub fn is_empty(&self) -> bool {
+        self.slab.is_empty()
+    }
+
+    /// Register a waker, and return its slot.
+    pub fn register(&mut self, waker: &Waker) -> Result<Slot, Error> {
+        self.slab.push_front(waker.clone())
+    }
+
+    /// Replace the waker registered at the given slot.
+    /// The waker is only cloned if it wouldn't wake the same task as the
+    /// registered one.
+    pub fn update(&mut self, slot: Slot, waker: &Waker) -> Result<(), Error> {
+        self.slab.get_mut_checked(slot)?.clone_from(waker);
+        Ok(())
+    }
+
+    /// Wake the waker registered at the given slot.
+    pub fn wake(&self, slot: Slot) -> Result<(), Error> {
+        self.slab.get_checked(slot)?.wake_by_ref();
+        Ok(())
+    }
+
+    /// Wake all the registered wakers, and return their number.
+    pub fn wake_all(&self) -> usize {
+        self.slab.iter().map(Waker::wake_by_ref).count()
+    }
+
+    /// Unregister the waker at the given slot.
+    pub fn remove(&mut self, slot: Slot) -> Result<(), Error> {
+        self.slab.remove_checked(slot)
+    }
+}
+
+#[test]
+fn test_waker_slab() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::Wake;
+
+    struct Counter(AtomicUsize);
+
+    impl Wake for Counter {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let a = Arc::new(Counter(AtomicUsize::new(0)));
+    let b = Arc::new(Counter(AtomicUsize::new(0)));
+    let waker_a = Waker::from(a.clone());
+    let waker_b = Waker::from(b.clone());
+
+    let mut wakers = WakerSlab::with_capacity(2).unwrap();
+    let x = wakers.register(&waker_a).unwrap();
+    let y = wakers.register(&waker_a).unwrap();
+    assert!(wakers.register(&waker_b).is_err());
+
+    wakers.wake(x).unwrap();
+    assert_eq!(a.0.load(Ordering::SeqCst), 1);
+    wakers.update(y, &waker_b).unwrap();
+    assert_eq!(wakers.wake_all(), 2);
+    assert_eq!(a.0.load(Ordering::SeqCst), 2);
+    assert_eq!(b.0.load(Ordering::SeqCst), 1);
+
+    wakers.remove(x).unwrap();
+    assert_eq!(wakers.wake(x), Err(Error::InvalidSlot));
+    assert_eq!(wakers.wake_all(), 1);
+    assert_eq!(b.0.load(Ordering::SeqCst), 2);
+    assert_eq!(Arc::strong_count(&a), 2);
+}